@@ -57,7 +57,8 @@ BOT_SERVICE="/etc/systemd/system/$BOT_NAME.service"
 BOT_LOG="/var/log/$BOT_NAME.log"
 BOT_BACKUP_DIR="/etc/$BOT_NAME.bak"
 ENV_FILE="$BOT_CONFIG_DIR/env"
-SCHEDULER_STATE="/etc/$BOT_NAME/scheduler_state.json"
+STATE_DIR="/var/lib/$BOT_NAME"
+SCHEDULER_STATE="$STATE_DIR/scheduler_state.json"
 CREDSTORE_DIR="/etc/credstore"
 BOT_TOKEN_CRED="$CREDSTORE_DIR/$BOT_NAME.bot-token"
 CHAT_ID_CRED="$CREDSTORE_DIR/$BOT_NAME.chat-id"
@@ -165,7 +166,7 @@ uninstall_vps_bot() {
     [ -f "$BOT_SERVICE" ] && print_info "  • Systemd 服务: $BOT_SERVICE"
     [ -d "$BOT_BACKUP_DIR" ] && print_info "  • 备份目录: $BOT_BACKUP_DIR"
     [ -f "$BOT_LOG" ] && print_info "  • 日志文件: $BOT_LOG"
-    [ -f "$SCHEDULER_STATE" ] && print_info "  • 调度器状态: $SCHEDULER_STATE"
+    [ -d "$STATE_DIR" ] && print_info "  • 状态目录: $STATE_DIR"
     echo
 
     # 询问是否保留配置
@@ -268,13 +269,13 @@ uninstall_vps_bot() {
         print_info "  ℹ️  日志文件不存在"
     fi
 
-    # 7. 删除调度器状态文件
-    print_info "[7/7] 删除调度器状态文件..."
-    if [ -f "$SCHEDULER_STATE" ]; then
-        rm -f "$SCHEDULER_STATE"
-        print_success "调度器状态文件已删除"
+    # 7. 删除状态目录（调度器状态文件及隔离的损坏文件）
+    print_info "[7/7] 删除状态目录..."
+    if [ -d "$STATE_DIR" ]; then
+        rm -rf "$STATE_DIR"
+        print_success "状态目录已删除"
     else
-        print_info "  ℹ️  调度器状态文件不存在"
+        print_info "  ℹ️  状态目录不存在"
     fi
 
     # 8. 删除凭证文件
@@ -555,6 +556,8 @@ Type=simple
 User=root
 Group=root
 WorkingDirectory=$BOT_CONFIG_DIR
+# 调度器状态保存在 /var/lib/$BOT_NAME
+StateDirectory=$BOT_NAME
 ExecStart=$BOT_BINARY run
 Restart=on-failure
 RestartSec=10
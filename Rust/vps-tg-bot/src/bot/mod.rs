@@ -1,10 +1,15 @@
 use teloxide::prelude::*;
 use teloxide::utils::command::BotCommands;
-use teloxide::types::{InlineKeyboardMarkup, InlineKeyboardButton};
+use teloxide::types::{InlineKeyboardMarkup, InlineKeyboardButton, User};
 use crate::config::Config;
+use crate::monitor;
 use crate::system;
+use crate::system::lock::{MaintenanceGuard, MaintenanceLock};
 use crate::scheduler;
-use crate::scheduler::task_types::TaskType;
+use crate::scheduler::custom::{CustomCommand, CustomCommandId};
+use crate::scheduler::pipeline::{Pipeline, PipelineId};
+use crate::scheduler::task_types::{ScheduledTask, TaskId, TaskType};
+use crate::scheduler::window::{BlackoutPeriod, MaintenanceWindows};
 use crate::scheduler::maintenance_history::{record_maintenance, MaintenanceResult};
 
 #[derive(BotCommands, Clone)]
@@ -31,7 +36,7 @@ pub enum Command {
     MaintainRules,
     #[command(description = "查看日志")]
     Logs,
-    #[command(description = "设置调度计划")]
+    #[command(description = "设置调度计划: /setschedule [任务标识] [tz=时区] Cron表达式|every 6h|once 2026-11-02 03:00|boot 10m|on disk / 10%")]
     SetSchedule(String),
     #[command(description = "查看维护历史")]
     MaintenanceHistory,
@@ -39,6 +44,22 @@ pub enum Command {
     FullMaintenance,
     #[command(description = "更新 Bot")]
     UpdateBot,
+    #[command(description = "查看 TLS 证书状态")]
+    Certs,
+    #[command(description = "定义任务流水线: /setpipeline 名称 步骤... (步骤后加 ! 失败继续，加 ? 仅上一步成功时执行)")]
+    SetPipeline(String),
+    #[command(description = "删除任务流水线: /delpipeline 名称")]
+    DelPipeline(String),
+    #[command(description = "定义自定义命令: /setcustom 名称 [timeout=秒] 程序绝对路径 参数... (程序需在白名单中)")]
+    SetCustom(String),
+    #[command(description = "删除自定义命令: /delcustom 名称")]
+    DelCustom(String),
+    #[command(description = "设置任务标签: /labeltask 任务ID [标签] (省略标签时清除)")]
+    LabelTask(String),
+    #[command(description = "修改任务的 Cron 表达式: /edittask 任务ID Cron表达式")]
+    EditTask(String),
+    #[command(description = "查看执行中的任务，可从列表中取消")]
+    Jobs,
 }
 
 // 构建主菜单 Inline Keyboard
@@ -60,6 +81,27 @@ fn build_main_menu_keyboard() -> InlineKeyboardMarkup {
     InlineKeyboardMarkup::new(keyboard)
 }
 
+/// 执行中任务列表
+fn format_running_jobs(jobs: &[scheduler::jobs::JobInfo]) -> String {
+    if jobs.is_empty() {
+        return "📭 当前没有执行中的任务".to_string();
+    }
+    let mut text = format!("⚙️ 执行中的任务 ({}):", jobs.len());
+    for job in jobs {
+        text.push_str(&format!("\n   • #{} {} (开始于 {})", job.id, job.name, job.started_at.format("%m-%d %H:%M:%S")));
+    }
+    text
+}
+
+/// 每个执行中任务一个取消按钮，与开始通知中的按钮共用回调
+fn build_running_jobs_keyboard(jobs: &[scheduler::jobs::JobInfo]) -> InlineKeyboardMarkup {
+    let keyboard = jobs
+        .iter()
+        .map(|job| vec![InlineKeyboardButton::callback(format!("⛔ 取消 #{} {}", job.id, job.name), format!("cancel_job_{}", job.id))])
+        .collect::<Vec<_>>();
+    InlineKeyboardMarkup::new(keyboard)
+}
+
 // 构建维护菜单 Inline Keyboard
 fn build_maintain_menu_keyboard() -> InlineKeyboardMarkup {
     let keyboard = vec![
@@ -75,6 +117,9 @@ fn build_maintain_menu_keyboard() -> InlineKeyboardMarkup {
             InlineKeyboardButton::callback("🔄 完整维护", "cmd_full_maintenance"),
             InlineKeyboardButton::callback("🤖 更新 Bot", "cmd_update_bot"),
         ],
+        vec![
+            InlineKeyboardButton::callback("🧩 流水线", "menu_pipelines"),
+        ],
         vec![
             InlineKeyboardButton::callback("🔙 返回主菜单", "back_to_main"),
         ],
@@ -98,6 +143,10 @@ fn build_task_type_menu_keyboard() -> InlineKeyboardMarkup {
             InlineKeyboardButton::callback("📦 更新 Sing-box", "task_update_singbox"),
             InlineKeyboardButton::callback("📋 查看任务列表", "view_tasks"),
         ],
+        vec![
+            InlineKeyboardButton::callback("🧩 流水线", "task_pipelines"),
+            InlineKeyboardButton::callback("⚙️ 自定义命令", "task_commands"),
+        ],
         vec![
             InlineKeyboardButton::callback("🔙 返回", "back_to_main"),
         ],
@@ -106,6 +155,166 @@ fn build_task_type_menu_keyboard() -> InlineKeyboardMarkup {
     InlineKeyboardMarkup::new(keyboard)
 }
 
+// 构建任务列表键盘：每个任务一行删除按钮和错过执行策略按钮，会重启系统的任务附带重启策略按钮，
+// 第二行为通知策略和静默送达按钮。回调数据使用任务 ID，列表变化后旧键盘不会操作到其他任务
+fn build_task_list_keyboard(tasks: &[ScheduledTask]) -> InlineKeyboardMarkup {
+    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = tasks
+        .iter()
+        .flat_map(|task| {
+            let mut row = vec![InlineKeyboardButton::callback(
+                format!("🗑️ 删除 #{}", task.id),
+                format!("del_task_{}", task.id),
+            )];
+            if task.task_type.can_reboot() {
+                row.push(InlineKeyboardButton::callback(
+                    format!("🔁 {}", task.reboot_policy.get_display_name()),
+                    format!("reboot_policy_{}", task.id),
+                ));
+            }
+            row.push(InlineKeyboardButton::callback(
+                format!("⏰ {}", task.missed_run_policy.get_display_name()),
+                format!("missed_policy_{}", task.id),
+            ));
+            let notification_row = vec![
+                InlineKeyboardButton::callback(
+                    format!("📣 #{} {}", task.id, task.notification_policy.get_display_name()),
+                    format!("notify_policy_{}", task.id),
+                ),
+                InlineKeyboardButton::callback(
+                    if task.silent_delivery { "🔕 静默送达" } else { "🔔 响铃提醒" },
+                    format!("silent_delivery_{}", task.id),
+                ),
+            ];
+            [row, notification_row]
+        })
+        .collect();
+
+    keyboard.push(vec![
+        InlineKeyboardButton::callback("➕ 添加新任务", "add_new_task"),
+        InlineKeyboardButton::callback("🔙 返回", "back_to_task_types"),
+    ]);
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+// 构建流水线列表键盘：`callback_prefix` 后接流水线 ID，列表变化后旧按钮不会指向其他流水线
+fn build_pipeline_list_keyboard(pipelines: &[Pipeline], callback_prefix: &str, back_callback: &str) -> InlineKeyboardMarkup {
+    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = pipelines
+        .iter()
+        .map(|pipeline| {
+            vec![InlineKeyboardButton::callback(
+                format!("🧩 {}", pipeline.name),
+                format!("{}{}", callback_prefix, pipeline.id),
+            )]
+        })
+        .collect();
+    keyboard.push(vec![InlineKeyboardButton::callback("🔙 返回", back_callback)]);
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+// 冻结期内手动维护的确认按钮，回调数据带此前缀表示已确认
+const FORCE_PREFIX: &str = "force_";
+
+// 冻结期内需要二次确认的维护回调
+fn requires_blackout_confirmation(data: &str) -> bool {
+    matches!(data, "cmd_maintain_core" | "cmd_maintain_rules" | "cmd_update_xray" | "cmd_update_sb" | "cmd_full_maintenance")
+        || data.starts_with("run_pipeline_")
+}
+
+// 冻结期确认回调对应的维护命令
+const CONFIRMABLE_COMMANDS: [(&str, Command); 6] = [
+    ("run_command_maintain", Command::Maintain),
+    ("run_command_maintain_core", Command::MaintainCore),
+    ("run_command_maintain_rules", Command::MaintainRules),
+    ("run_command_update_xray", Command::UpdateXray),
+    ("run_command_update_sb", Command::UpdateSb),
+    ("run_command_full_maintenance", Command::FullMaintenance),
+];
+
+// 维护命令对应的回调，冻结期内通过按钮确认后执行原命令
+fn blackout_confirmation_callback(command: &Command) -> Option<&'static str> {
+    CONFIRMABLE_COMMANDS
+        .iter()
+        .find(|(_, confirmable)| std::mem::discriminant(confirmable) == std::mem::discriminant(command))
+        .map(|(callback, _)| *callback)
+}
+
+// 冻结期确认回调对应的命令
+fn confirmed_command(data: &str) -> Option<Command> {
+    CONFIRMABLE_COMMANDS
+        .iter()
+        .find(|(callback, _)| *callback == data)
+        .map(|(_, command)| command.clone())
+}
+
+// 当前所处的维护冻结期
+fn current_blackout() -> Option<BlackoutPeriod> {
+    let config = Config::load().ok()?;
+    let windows = MaintenanceWindows::from_config(&config.scheduler).ok()?;
+    windows.blackout_at(&chrono::Utc::now().with_timezone(&scheduler::timezone::default_timezone())).cloned()
+}
+
+fn format_blackout_warning(blackout: &BlackoutPeriod) -> String {
+    format!("⚠️ 当前处于维护冻结期 ({})\n\n冻结期内不建议执行维护操作，确定仍要执行吗？", blackout)
+}
+
+// 构建冻结期确认键盘
+fn build_blackout_confirm_keyboard(data: &str) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(vec![vec![
+        InlineKeyboardButton::callback("⚠️ 仍然执行", format!("{}{}", FORCE_PREFIX, data)),
+        InlineKeyboardButton::callback("🔙 取消", "menu_maintain"),
+    ]])
+}
+
+// 按回调数据中的 ID 查找流水线，流水线已删除时返回 None
+async fn find_pipeline(id: &str) -> Option<Pipeline> {
+    scheduler::get_pipeline_by_id(id.parse::<PipelineId>().ok()?).await
+}
+
+// 流水线列表文本
+fn format_pipeline_list(pipelines: &[Pipeline], title: &str) -> String {
+    if pipelines.is_empty() {
+        return "🧩 暂无流水线\n\n使用 /setpipeline 名称 步骤... 定义流水线".to_string();
+    }
+    let mut text = format!("{}\n", title);
+    for pipeline in pipelines {
+        text.push_str(&format!("\n• {}: {}\n   /setpipeline {}", pipeline.name, pipeline.describe(), pipeline.to_definition()));
+    }
+    text.push_str(&format!("\n\n可用步骤: {}\n步骤后加 ! 表示失败继续，加 ? 表示仅在上一步成功时执行", TaskType::builtin_keys().join(", ")));
+    text
+}
+
+// 构建自定义命令列表键盘：按钮回调为 `task_command_` 后接命令 ID
+fn build_custom_command_list_keyboard(commands: &[CustomCommand]) -> InlineKeyboardMarkup {
+    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = commands
+        .iter()
+        .map(|command| {
+            vec![InlineKeyboardButton::callback(
+                format!("⚙️ {}", command.name),
+                format!("task_command_{}", command.id),
+            )]
+        })
+        .collect();
+    keyboard.push(vec![InlineKeyboardButton::callback("🔙 返回", "back_to_task_types")]);
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+// 按回调数据中的 ID 查找自定义命令，命令已删除时返回 None
+async fn find_custom_command(id: &str) -> Option<CustomCommand> {
+    scheduler::get_custom_command_by_id(id.parse::<CustomCommandId>().ok()?).await
+}
+
+// 自定义命令列表文本
+fn format_custom_command_list(commands: &[CustomCommand]) -> String {
+    if commands.is_empty() {
+        return "⚙️ 暂无自定义命令\n\n使用 /setcustom 名称 [timeout=秒] 程序绝对路径 参数... 定义命令，程序需在 CUSTOM_COMMAND_ALLOWLIST 白名单中".to_string();
+    }
+    let mut text = "⚙️ 选择要定时执行的自定义命令:\n".to_string();
+    for command in commands {
+        text.push_str(&format!("\n• {}: {}\n   /setcustom {}", command.name, command.describe(), command.to_definition()));
+    }
+    text
+}
+
 // 构建预设时间菜单
 fn build_schedule_presets_keyboard(task_type: &str) -> InlineKeyboardMarkup {
     let (_daily, _weekly, _monthly) = match task_type {
@@ -182,6 +391,8 @@ fn get_task_display_name(task_type: &str) -> &'static str {
         "rules_maintenance" => "🌍 规则维护",
         "update_xray" => "🔧 更新 Xray",
         "update_singbox" => "📦 更新 Sing-box",
+        key if key.starts_with("pipeline_") => "🧩 任务流水线",
+        key if key.starts_with("command_") => "⚙️ 自定义命令",
         _ => "❓ 未知任务",
     }
 }
@@ -316,54 +527,70 @@ pub async fn run_bot(config: Config) -> anyhow::Result<()> {
 }
 
 async fn answer(bot: Bot, message: Message, command: Command) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    // 冻结期内的手动维护需要通过按钮确认
+    if let Some(callback) = blackout_confirmation_callback(&command) {
+        if let Some(blackout) = current_blackout() {
+            bot.send_message(message.chat.id, format_blackout_warning(&blackout))
+                .reply_markup(build_blackout_confirm_keyboard(callback))
+                .await?;
+            return Ok(());
+        }
+    }
+
+    run_command(bot, message.chat.id, message.from().cloned(), command).await
+}
+
+// 执行命令，`from` 为发起人；冻结期内确认后由回调直接调用
+async fn run_command(bot: Bot, chat_id: ChatId, from: Option<User>, command: Command) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
     match command {
         Command::Start => {
             let welcome_message = "🚀 欢迎使用 VPS 管理机器人!\n\n请选择您要执行的操作:";
             let keyboard = build_main_menu_keyboard();
-            bot.send_message(message.chat.id, welcome_message)
+            bot.send_message(chat_id, welcome_message)
                 .reply_markup(keyboard)
                 .await?;
         }
         Command::Status => {
-            match system::get_system_status() {
+            // 采样需要阻塞约 1 秒，放到阻塞线程池中执行
+            match tokio::task::spawn_blocking(system::get_system_status).await? {
                 Ok(status) => {
-                    let reply = format!(
-                        "📊 系统状态:\n\n{}",
-                        format!("🔹 CPU 使用率: {:.2}%\n", status.cpu_usage) +
-                        &format!("🔹 内存使用: {} MB / {} MB\n", status.memory_used / 1024 / 1024, status.memory_total / 1024 / 1024) +
-                        &format!("🔹 磁盘使用: {} GB / {} GB\n", status.disk_used / 1024 / 1024 / 1024, status.disk_total / 1024 / 1024 / 1024) +
-                        &format!("🔹 网络接收: {} MB\n", status.network_rx / 1024 / 1024) +
-                        &format!("🔹 网络发送: {} MB\n", status.network_tx / 1024 / 1024) +
-                        &format!("🔹 运行时间: {} 秒", status.uptime)
-                    );
-                    bot.send_message(message.chat.id, reply).await?;
+                    let mut reply = format_system_status(&status);
+                    reply.push_str(&format!("\n\n{}", system::reboot::check_reboot_required().await.describe()));
+                    bot.send_message(chat_id, reply).await?;
                 }
                 Err(e) => {
-                    bot.send_message(message.chat.id, format!("❌ 无法获取系统状态: {}", e)).await?;
+                    bot.send_message(chat_id, format!("❌ 无法获取系统状态: {}", e)).await?;
                 }
             }
         }
         Command::Maintain => {
-            bot.send_message(message.chat.id, "🔄 正在执行系统维护...").await?;
+            let Some(_guard) = lock_maintenance_or_reply(&bot, chat_id, "🔄 系统维护", from.as_ref()).await? else {
+                return Ok(());
+            };
+            bot.send_message(chat_id, "🔄 正在执行系统维护...").await?;
             match system::ops::perform_maintenance().await {
                 Ok(log) => {
-                    bot.send_message(message.chat.id, format!("✅ 系统维护完成:\n{}", log)).await?;
+                    bot.send_message(chat_id, format!("✅ 系统维护完成:\n{}", log)).await?;
                     
                     // 系统维护完成后自动重启
-                    bot.send_message(message.chat.id, "🔄 系统维护完成，将在 5 秒后自动重启...").await?;
+                    bot.send_message(chat_id, "🔄 系统维护完成，将在 5 秒后自动重启...").await?;
                     tokio::time::sleep(std::time::Duration::from_secs(5)).await;
                     
                     if let Err(e) = system::ops::reboot_system().await {
-                        bot.send_message(message.chat.id, format!("❌ 自动重启失败: {}", e)).await?;
+                        bot.send_message(chat_id, format!("❌ 自动重启失败: {}", e)).await?;
                     }
                 }
                 Err(e) => {
-                    bot.send_message(message.chat.id, format!("❌ 系统维护失败: {}", e)).await?;
+                    bot.send_message(chat_id, format!("❌ 系统维护失败: {}", e)).await?;
                 }
             }
         }
         Command::Reboot => {
-            bot.send_message(message.chat.id, "⚠️ 确认重启系统？回复 'YES' 确认。").await?;
+            // 有维护任务执行中时拒绝重启，避免中断 apt/dpkg
+            let Some(_guard) = lock_maintenance_or_reply(&bot, chat_id, "🔄 重启系统", from.as_ref()).await? else {
+                return Ok(());
+            };
+            bot.send_message(chat_id, "⚠️ 确认重启系统？回复 'YES' 确认。").await?;
             // 注意: 重启确认逻辑需要额外的状态处理
             // 为简化，我们将在确认后继续重启
             // 在实际实现中，您需要跟踪确认状态
@@ -371,107 +598,213 @@ async fn answer(bot: Bot, message: Message, command: Command) -> Result<(), Box<
             // 直接执行重启（在实际实现中应添加确认逻辑）
             match system::ops::reboot_system().await {
                 Ok(_) => {
-                    bot.send_message(message.chat.id, "🔄 系统重启中...").await?;
+                    bot.send_message(chat_id, "🔄 系统重启中...").await?;
                 }
                 Err(e) => {
-                    bot.send_message(message.chat.id, format!("❌ 重启失败: {}", e)).await?;
+                    bot.send_message(chat_id, format!("❌ 重启失败: {}", e)).await?;
                 }
             }
         }
         Command::UpdateXray => {
-            bot.send_message(message.chat.id, "🔄 正在更新 Xray...").await?;
+            let Some(_guard) = lock_maintenance_or_reply(&bot, chat_id, "🔧 更新 Xray", from.as_ref()).await? else {
+                return Ok(());
+            };
+            bot.send_message(chat_id, "🔄 正在更新 Xray...").await?;
             match system::ops::update_xray().await {
                 Ok(log) => {
-                    bot.send_message(message.chat.id, format!("✅ Xray 更新完成:\n{}", log)).await?;
+                    bot.send_message(chat_id, format!("✅ Xray 更新完成:\n{}", log)).await?;
                 }
                 Err(e) => {
-                    bot.send_message(message.chat.id, format!("❌ Xray 更新失败: {}", e)).await?;
+                    bot.send_message(chat_id, format!("❌ Xray 更新失败: {}", e)).await?;
                 }
             }
         }
         Command::UpdateSb => {
-            bot.send_message(message.chat.id, "🔄 正在更新 Sing-box...").await?;
+            let Some(_guard) = lock_maintenance_or_reply(&bot, chat_id, "📦 更新 Sing-box", from.as_ref()).await? else {
+                return Ok(());
+            };
+            bot.send_message(chat_id, "🔄 正在更新 Sing-box...").await?;
             match system::ops::update_singbox().await {
                 Ok(log) => {
-                    bot.send_message(message.chat.id, format!("✅ Sing-box 更新完成:\n{}", log)).await?;
+                    bot.send_message(chat_id, format!("✅ Sing-box 更新完成:\n{}", log)).await?;
                 }
                 Err(e) => {
-                    bot.send_message(message.chat.id, format!("❌ Sing-box 更新失败: {}", e)).await?;
+                    bot.send_message(chat_id, format!("❌ Sing-box 更新失败: {}", e)).await?;
                 }
             }
         }
         Command::MaintainCore => {
-            bot.send_message(message.chat.id, "🔄 正在执行核心维护...\n⚠️ 维护完成后系统将自动重启").await?;
+            let Some(_guard) = lock_maintenance_or_reply(&bot, chat_id, "🚀 核心维护", from.as_ref()).await? else {
+                return Ok(());
+            };
+            bot.send_message(chat_id, "🔄 正在执行核心维护...\n⚠️ 维护完成后系统将自动重启").await?;
             match system::ops::maintain_core().await {
                 Ok(log) => {
-                    bot.send_message(message.chat.id, format!("✅ 核心维护完成:\n{}\n\n🔄 系统将在 3 秒后自动重启，请保存您的工作！", log)).await?;
+                    bot.send_message(chat_id, format!("✅ 核心维护完成:\n{}\n\n🔄 系统将在 3 秒后自动重启，请保存您的工作！", log)).await?;
                 }
                 Err(e) => {
-                    bot.send_message(message.chat.id, format!("❌ 核心维护失败: {}", e)).await?;
+                    bot.send_message(chat_id, format!("❌ 核心维护失败: {}", e)).await?;
                 }
             }
         }
         Command::MaintainRules => {
-            bot.send_message(message.chat.id, "🔄 正在执行规则维护...").await?;
+            let Some(_guard) = lock_maintenance_or_reply(&bot, chat_id, "🌍 规则维护", from.as_ref()).await? else {
+                return Ok(());
+            };
+            bot.send_message(chat_id, "🔄 正在执行规则维护...").await?;
             match system::ops::maintain_rules().await {
                 Ok(log) => {
-                    bot.send_message(message.chat.id, format!("✅ 规则维护完成:\n{}", log)).await?;
+                    bot.send_message(chat_id, format!("✅ 规则维护完成:\n{}", log)).await?;
                 }
                 Err(e) => {
-                    bot.send_message(message.chat.id, format!("❌ 规则维护失败: {}", e)).await?;
+                    bot.send_message(chat_id, format!("❌ 规则维护失败: {}", e)).await?;
                 }
             }
         }
         Command::Logs => {
-            bot.send_message(message.chat.id, "🔄 正在获取系统日志...").await?;
+            bot.send_message(chat_id, "🔄 正在获取系统日志...").await?;
             match system::ops::get_system_logs(20).await {
                 Ok(log) => {
-                    bot.send_message(message.chat.id, format!("📋 系统日志:\n{}", log)).await?;
+                    bot.send_message(chat_id, format!("📋 系统日志:\n{}", log)).await?;
                 }
                 Err(e) => {
-                    bot.send_message(message.chat.id, format!("❌ 无法获取日志: {}", e)).await?;
+                    bot.send_message(chat_id, format!("❌ 无法获取日志: {}", e)).await?;
                 }
             }
         }
         Command::SetSchedule(cron_expr) => {
-            bot.send_message(message.chat.id, "🔄 正在更新调度计划...").await?;
+            bot.send_message(chat_id, "🔄 正在更新调度计划...").await?;
             match scheduler::update_schedule(&cron_expr).await {
                 Ok(response_message) => {
-                    bot.send_message(message.chat.id, response_message).await?;
+                    bot.send_message(chat_id, response_message).await?;
                 }
                 Err(e) => {
-                    bot.send_message(message.chat.id, format!("❌ 更新调度失败: {}", e)).await?;
+                    bot.send_message(chat_id, format!("❌ 更新调度失败: {}", e)).await?;
                 }
             }
         }
+        Command::SetPipeline(definition) => {
+            let reply = match Pipeline::parse_definition(&definition) {
+                Ok(pipeline) => match scheduler::SCHEDULER_MANAGER.lock().await.as_ref() {
+                    Some(manager) => manager
+                        .set_pipeline(pipeline)
+                        .await
+                        .unwrap_or_else(|e| format!("❌ 保存流水线失败: {}", e)),
+                    None => "❌ 调度器尚未初始化".to_string(),
+                },
+                Err(e) => format!("❌ {}\n\n示例: /setpipeline 每周维护 core_maintenance! rules_maintenance? update_xray", e),
+            };
+            bot.send_message(chat_id, reply).await?;
+        }
+        Command::DelPipeline(name) => {
+            let reply = match scheduler::SCHEDULER_MANAGER.lock().await.as_ref() {
+                Some(manager) => manager
+                    .remove_pipeline(name.trim())
+                    .await
+                    .unwrap_or_else(|e| format!("❌ 删除流水线失败: {}", e)),
+                None => "❌ 调度器尚未初始化".to_string(),
+            };
+            bot.send_message(chat_id, reply).await?;
+        }
+        Command::SetCustom(definition) => {
+            let reply = match CustomCommand::parse_definition(&definition) {
+                Ok(command) => match scheduler::SCHEDULER_MANAGER.lock().await.as_ref() {
+                    Some(manager) => manager
+                        .set_custom_command(command)
+                        .await
+                        .unwrap_or_else(|e| format!("❌ 保存自定义命令失败: {}", e)),
+                    None => "❌ 调度器尚未初始化".to_string(),
+                },
+                Err(e) => format!("❌ {}\n\n示例: /setcustom 清理日志 timeout=600 /usr/bin/journalctl --vacuum-time=7d", e),
+            };
+            bot.send_message(chat_id, reply).await?;
+        }
+        Command::DelCustom(name) => {
+            let reply = match scheduler::SCHEDULER_MANAGER.lock().await.as_ref() {
+                Some(manager) => manager
+                    .remove_custom_command(name.trim())
+                    .await
+                    .unwrap_or_else(|e| format!("❌ 删除自定义命令失败: {}", e)),
+                None => "❌ 调度器尚未初始化".to_string(),
+            };
+            bot.send_message(chat_id, reply).await?;
+        }
+        Command::LabelTask(args) => {
+            let args = args.trim();
+            let (id, label) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+            let reply = match id.trim_start_matches('#').parse::<TaskId>() {
+                Ok(id) => match scheduler::SCHEDULER_MANAGER.lock().await.as_ref() {
+                    Some(manager) => manager
+                        .set_task_label(id, label)
+                        .await
+                        .unwrap_or_else(|e| format!("❌ 设置任务标签失败: {}", e)),
+                    None => "❌ 调度器尚未初始化".to_string(),
+                },
+                Err(_) => "❌ 请提供任务 ID，示例: /labeltask 3 夜间更新".to_string(),
+            };
+            bot.send_message(chat_id, reply).await?;
+        }
+        Command::EditTask(args) => {
+            let args = args.trim();
+            let (id, cron_expr) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+            let reply = match id.trim_start_matches('#').parse::<TaskId>() {
+                Ok(id) => match scheduler::SCHEDULER_MANAGER.lock().await.as_ref() {
+                    Some(manager) => manager
+                        .update_task_by_id(id, cron_expr.trim())
+                        .await
+                        .unwrap_or_else(|e| format!("❌ 更新任务失败: {}", e)),
+                    None => "❌ 调度器尚未初始化".to_string(),
+                },
+                Err(_) => "❌ 请提供任务 ID，示例: /edittask 3 0 4 * * Sun".to_string(),
+            };
+            bot.send_message(chat_id, reply).await?;
+        }
+        Command::Jobs => {
+            let jobs = scheduler::jobs::running();
+            let message = bot.send_message(chat_id, format_running_jobs(&jobs));
+            if jobs.is_empty() {
+                message.await?;
+            } else {
+                message.reply_markup(build_running_jobs_keyboard(&jobs)).await?;
+            }
+        }
         Command::MaintenanceHistory => {
-            bot.send_message(message.chat.id, "📜 正在加载维护历史...").await?;
+            bot.send_message(chat_id, "📜 正在加载维护历史...").await?;
             let history_summary = crate::scheduler::maintenance_history::get_maintenance_summary().await;
             let keyboard = build_maintenance_history_keyboard(0);
-            bot.send_message(message.chat.id, history_summary)
+            bot.send_message(chat_id, history_summary)
                 .reply_markup(keyboard)
                 .await?;
         }
         Command::FullMaintenance => {
-            bot.send_message(message.chat.id, "🔄 正在执行完整维护...").await?;
+            let Some(_guard) = lock_maintenance_or_reply(&bot, chat_id, "🔧 完整维护", from.as_ref()).await? else {
+                return Ok(());
+            };
+            bot.send_message(chat_id, "🔄 正在执行完整维护...").await?;
             match system::perform_full_maintenance().await {
                 Ok(log) => {
-                    bot.send_message(message.chat.id, format!("✅ 完整维护完成:\n{}", log)).await?;
+                    bot.send_message(chat_id, format!("✅ 完整维护完成:\n{}", log)).await?;
                 }
                 Err(e) => {
-                    bot.send_message(message.chat.id, format!("❌ 完整维护失败: {}", e)).await?;
+                    bot.send_message(chat_id, format!("❌ 完整维护失败: {}", e)).await?;
                 }
             }
         }
+        Command::Certs => {
+            let monitor_config = Config::load().map(|c| c.monitor).unwrap_or_default();
+            let certs = tokio::task::spawn_blocking(move || monitor::certs::collect_certificates(&monitor_config)).await?;
+            let reply = monitor::certs::format_certificate_list(&certs, chrono::Utc::now());
+            bot.send_message(chat_id, reply).await?;
+        }
         Command::UpdateBot => {
-            bot.send_message(message.chat.id, "🔍 正在检查更新...").await?;
+            bot.send_message(chat_id, "🔍 正在检查更新...").await?;
             
             match system::update::check_latest_version().await {
                 Ok(status) => {
                     match status {
                         system::update::UpdateStatus::UpToDate => {
                             let current_version = system::update::get_current_version();
-                            bot.send_message(message.chat.id, 
+                            bot.send_message(chat_id, 
                                 format!("✅ 当前已是最新版本: v{}", current_version)).await?;
                         }
                         system::update::UpdateStatus::UpdateAvailable { current, latest, release_notes } => {
@@ -498,18 +831,18 @@ async fn answer(bot: Bot, message: Message, command: Command) -> Result<(), Box<
                                 ],
                             ]);
                             
-                            bot.send_message(message.chat.id, msg)
+                            bot.send_message(chat_id, msg)
                                 .reply_markup(keyboard)
                                 .await?;
                         }
                         system::update::UpdateStatus::Unknown(reason) => {
-                            bot.send_message(message.chat.id, 
+                            bot.send_message(chat_id, 
                                 format!("⚠️ 无法确定版本状态: {}", reason)).await?;
                         }
                     }
                 }
                 Err(e) => {
-                    bot.send_message(message.chat.id, 
+                    bot.send_message(chat_id, 
                         format!("❌ 检查更新失败: {}", e)).await?;
                 }
             }
@@ -532,8 +865,31 @@ async fn handle_callback_query(
                    callback_query.message.as_ref().unwrap().id);
         let chat_id = callback_query.message.as_ref().unwrap().chat.id;
         let message_id = callback_query.message.as_ref().unwrap().id;
+
+        // 冻结期内的手动维护先要求确认，确认后的回调带 force_ 前缀
+        let (data, confirmed) = match data.strip_prefix(FORCE_PREFIX) {
+            Some(data) => (data, true),
+            None => (data.as_str(), false),
+        };
+        if !confirmed && requires_blackout_confirmation(data) {
+            if let Some(blackout) = current_blackout() {
+                bot.answer_callback_query(&callback_query.id).await?;
+                bot.edit_message_text(chat_id, message_id, format_blackout_warning(&blackout))
+                    .reply_markup(build_blackout_confirm_keyboard(data))
+                    .await?;
+                return Ok(());
+            }
+        }
+        // 确认后的命令按原命令执行
+        if confirmed {
+            if let Some(command) = confirmed_command(data) {
+                bot.answer_callback_query(&callback_query.id).await?;
+                bot.edit_message_text(chat_id, message_id, "⚠️ 已确认在冻结期内执行").await?;
+                return run_command(bot, chat_id, Some(callback_query.from.clone()), command).await;
+            }
+        }
         
-        match data.as_str() {
+        match data {
             // 主菜单按钮
             "cmd_status" => {
                 log::info!("🎯 处理主菜单: cmd_status 命令");
@@ -624,6 +980,9 @@ async fn handle_callback_query(
             "cmd_full_maintenance" => {
                 log::info!("🎯 处理完整维护: cmd_full_maintenance 命令");
                 bot.answer_callback_query(&callback_query.id).await?;
+                let Some(guard) = lock_maintenance_or_edit(&bot, &callback_query, "🔧 完整维护").await? else {
+                    return Ok(());
+                };
                 
                 let message = "🚀 正在执行完整维护（核心+规则）...";
                 let keyboard = build_maintain_menu_keyboard();
@@ -638,6 +997,7 @@ async fn handle_callback_query(
                 let message_id_clone = message_id;
                 
                 tokio::spawn(async move {
+                    let _guard = guard;
                     match system::perform_full_maintenance().await {
                         Ok(log) => {
                             let _ = bot_clone.edit_message_text(
@@ -854,48 +1214,123 @@ async fn handle_callback_query(
                 log::info!("✅ task_update_singbox 处理完成");
             }
 
+            "menu_pipelines" => {
+                log::info!("🎯 处理维护菜单: menu_pipelines 命令");
+                bot.answer_callback_query(&callback_query.id).await?;
+
+                let pipelines = scheduler::get_pipelines().await;
+                bot.edit_message_text(chat_id, message_id, format_pipeline_list(&pipelines, "🧩 选择要立即执行的流水线:"))
+                    .reply_markup(build_pipeline_list_keyboard(&pipelines, "run_pipeline_", "menu_maintain"))
+                    .await?;
+
+                log::info!("✅ menu_pipelines 处理完成");
+            }
+            "task_pipelines" => {
+                log::info!("🎯 处理任务类型: pipelines");
+                bot.answer_callback_query(&callback_query.id).await?;
+
+                let pipelines = scheduler::get_pipelines().await;
+                bot.edit_message_text(chat_id, message_id, format_pipeline_list(&pipelines, "🧩 选择要定时执行的流水线:"))
+                    .reply_markup(build_pipeline_list_keyboard(&pipelines, "task_pipeline_", "back_to_task_types"))
+                    .await?;
+
+                log::info!("✅ task_pipelines 处理完成");
+            }
+            cmd if cmd.starts_with("task_pipeline_") => {
+                let Some(pipeline) = find_pipeline(cmd.trim_start_matches("task_pipeline_")).await else {
+                    bot.answer_callback_query(&callback_query.id).text("❌ 流水线不存在").await?;
+                    return Ok(());
+                };
+                log::info!("🎯 处理任务类型: pipeline {}", pipeline.name);
+                bot.answer_callback_query(&callback_query.id).await?;
+
+                let message = format!("🧩 流水线「{}」定时设置\n\n请选择执行时间:", pipeline.name);
+                let keyboard = build_schedule_presets_keyboard(&format!("pipeline_{}", pipeline.id));
+
+                bot.edit_message_text(chat_id, message_id, message)
+                    .reply_markup(keyboard)
+                    .await?;
+
+                log::info!("✅ task_pipeline 处理完成");
+            }
+            "task_commands" => {
+                log::info!("🎯 处理任务类型: commands");
+                bot.answer_callback_query(&callback_query.id).await?;
+
+                let commands = scheduler::get_custom_commands().await;
+                bot.edit_message_text(chat_id, message_id, format_custom_command_list(&commands))
+                    .reply_markup(build_custom_command_list_keyboard(&commands))
+                    .await?;
+
+                log::info!("✅ task_commands 处理完成");
+            }
+            cmd if cmd.starts_with("task_command_") => {
+                let Some(command) = find_custom_command(cmd.trim_start_matches("task_command_")).await else {
+                    bot.answer_callback_query(&callback_query.id).text("❌ 自定义命令不存在").await?;
+                    return Ok(());
+                };
+                log::info!("🎯 处理任务类型: command {}", command.name);
+                bot.answer_callback_query(&callback_query.id).await?;
+
+                let message = format!("⚙️ 自定义命令「{}」定时设置\n\n请选择执行时间:", command.name);
+                let keyboard = build_schedule_presets_keyboard(&format!("command_{}", command.id));
+
+                bot.edit_message_text(chat_id, message_id, message)
+                    .reply_markup(keyboard)
+                    .await?;
+
+                log::info!("✅ task_command 处理完成");
+            }
+            cmd if cmd.starts_with("run_pipeline_") => {
+                let Some(pipeline) = find_pipeline(cmd.trim_start_matches("run_pipeline_")).await else {
+                    bot.answer_callback_query(&callback_query.id).text("❌ 流水线不存在").await?;
+                    return Ok(());
+                };
+
+                log::info!("🎯 处理流水线执行: {}", pipeline.name);
+                bot.answer_callback_query(&callback_query.id).await?;
+
+                let manager = scheduler::SCHEDULER_MANAGER.lock().await.clone();
+                let Some(manager) = manager else {
+                    bot.edit_message_text(chat_id, message_id, "❌ 调度器尚未初始化")
+                        .reply_markup(build_maintain_menu_keyboard())
+                        .await?;
+                    return Ok(());
+                };
+
+                bot.edit_message_text(chat_id, message_id, format!("🧩 流水线「{}」已开始执行，进度将单独通知\n\n请选择下一步操作:", pipeline.name))
+                    .reply_markup(build_maintain_menu_keyboard())
+                    .await?;
+
+                let config = Config::load().unwrap_or_else(|_| Config { bot_token: "".to_string(), chat_id: 0, check_interval: 300, monitor: Default::default(), scheduler: Default::default() });
+                // 结果通知发送到发起操作的会话
+                let config = Config { chat_id: chat_id.0, ..config };
+                let name = pipeline.name.clone();
+                let initiator = format!("手动 {}", describe_initiator(Some(&callback_query.from)));
+                let bot = bot.clone();
+                tokio::spawn(async move {
+                    manager.run_pipeline_now(&config, bot, &name, initiator).await;
+                });
+
+                log::info!("✅ run_pipeline 处理完成");
+                return Ok(());
+            }
             "view_tasks" => {
                 log::info!("🎯 处理任务查看");
                 bot.answer_callback_query(&callback_query.id).await?;
                 
                 let tasks_summary = scheduler::get_tasks_summary().await.unwrap_or_else(|_| "❌ 无法获取任务列表".to_string());
                 
-                // 如果有任务，为每个任务添加删除按钮
-                if !tasks_summary.contains("暂无定时任务") {
-                    // 解析任务列表，为每个任务添加删除按钮
-                    let mut keyboard = Vec::new();
-                    
-                    // 分析任务列表，提取任务数量
-                    let task_count = tasks_summary.matches("✅").count() + tasks_summary.matches("⏸️").count();
-                    
-                    // 为每个任务添加删除按钮
-                    for i in 0..task_count {
-                        let task_row = vec![
-                            InlineKeyboardButton::callback(
-                                format!("🗑️ 删除任务 {}", i + 1), 
-                                format!("del_task_{}", i)
-                            )
-                        ];
-                        keyboard.push(task_row);
-                    }
-                    
-                    // 添加通用按钮
-                    keyboard.push(vec![
-                        InlineKeyboardButton::callback("➕ 添加新任务", "add_new_task"),
-                        InlineKeyboardButton::callback("🔙 返回", "back_to_task_types"),
-                    ]);
-                    
-                    let keyboard = InlineKeyboardMarkup::new(keyboard);
-                    bot.edit_message_text(chat_id, message_id, tasks_summary)
-                        .reply_markup(keyboard)
-                        .await?;
-                } else {
+                let tasks = scheduler::get_tasks().await;
+                let keyboard = if tasks.is_empty() {
                     // 没有任务时显示默认键盘
-                    let keyboard = build_task_type_menu_keyboard();
-                    bot.edit_message_text(chat_id, message_id, tasks_summary)
-                        .reply_markup(keyboard)
-                        .await?;
-                }
+                    build_task_type_menu_keyboard()
+                } else {
+                    build_task_list_keyboard(&tasks)
+                };
+                bot.edit_message_text(chat_id, message_id, tasks_summary)
+                    .reply_markup(keyboard)
+                    .await?;
                 
                 log::info!("✅ view_tasks 处理完成");
             }
@@ -999,7 +1434,11 @@ async fn handle_callback_query(
                 
                 bot.answer_callback_query(&callback_query.id).await?;
                 
-                let message = format!("⏰ 自定义 {} 定时任务设置\n\n📝 请发送 Cron 表达式:\n\n示例:\n• 每天凌晨4点: 0 4 * * *\n• 每周日凌晨4点: 0 4 * * Sun\n• 每月1号凌晨4点: 0 4 1 * *\n\n使用命令: /set_schedule <cron_expression>", get_task_display_name(task_type));
+                let command = match TaskType::from_key(task_type) {
+                    Some(_) => format!("/setschedule {} <计划>", task_type),
+                    None => "/setschedule <计划>".to_string(),
+                };
+                let message = format!("⏰ 自定义 {} 定时任务设置\n\n📝 请发送 Cron 表达式或执行计划:\n\n示例:\n• 每天凌晨4点: 0 4 * * *\n• 每周日凌晨4点: 0 4 * * Sun\n• 每月1号凌晨4点: 0 4 1 * *\n• 工作日早上8点半: 30 8 * * Mon-Fri\n• 从现在起每6小时: every 6h\n• 指定时间执行一次: once 2026-11-02 03:00（加 remove 执行后删除）\n• 每次启动后10分钟: boot 10m\n• 开机后10分钟: on boot 10m\n• 根分区剩余空间低于10%: on disk / 10%\n• 服务运行失败: on unit xray.service\n• 公网 IP 变化: on ip\n\n事件计划后加 cooldown=1h 可设置冷却时间（默认 30 分钟）\n支持 JAN-DEC、SUN-SAT 等月份和星期名称\n计划前加 tz=Asia/Shanghai 可指定任务时区\n\n使用命令: {}", get_task_display_name(task_type), command);
                 
                 let keyboard = build_task_type_menu_keyboard();
                 
@@ -1144,9 +1583,26 @@ async fn handle_callback_query(
                             .await?;
                         
                         let bot_clone = bot.clone();
-                        let config = Config::load().unwrap_or_else(|_| Config { bot_token: "".to_string(), chat_id: 0, check_interval: 300 });
                         let _chat_id_clone = chat_id;
                         let task_type_enum = match task_type.as_str() {
+                            key if key.starts_with("pipeline_") => {
+                                match find_pipeline(key.trim_start_matches("pipeline_")).await {
+                                    Some(pipeline) => TaskType::Pipeline(pipeline.name),
+                                    None => {
+                                        let _ = bot.send_message(chat_id, "❌ 流水线不存在").await;
+                                        return Ok(());
+                                    }
+                                }
+                            }
+                            key if key.starts_with("command_") => {
+                                match find_custom_command(key.trim_start_matches("command_")).await {
+                                    Some(command) => command.to_task_type(),
+                                    None => {
+                                        let _ = bot.send_message(chat_id, "❌ 自定义命令不存在").await;
+                                        return Ok(());
+                                    }
+                                }
+                            }
                             "system_maintenance" | "system" => TaskType::SystemMaintenance,
                             "core_maintenance" => TaskType::CoreMaintenance,
                             "rules_maintenance" => TaskType::RulesMaintenance,
@@ -1166,11 +1622,6 @@ async fn handle_callback_query(
                         let chat_id_for_message = chat_id;
                         let task_type_enum_for_task = task_type_enum.clone();
                         let cron_expr_for_task = cron_expr.to_string();
-                        let config_for_task = Config { 
-                            bot_token: config.bot_token.clone(), 
-                            chat_id: config.chat_id, 
-                            check_interval: config.check_interval 
-                        };
                         
                         tokio::spawn(async move {
                             // 等待调度器初始化
@@ -1181,8 +1632,6 @@ async fn handle_callback_query(
                                 let manager_guard = crate::scheduler::SCHEDULER_MANAGER.lock().await;
                                 if let Some(manager) = &*manager_guard {
                                     let result = manager.add_new_task(
-                                        config_for_task.clone(), 
-                                        bot_clone.clone(), 
                                         task_type_enum_for_task.clone(), 
                                         &cron_expr_for_task
                                     ).await;
@@ -1451,15 +1900,128 @@ async fn handle_callback_query(
                 log::info!("✅ maintenance_history 处理完成");
                 return Ok(());
             }
+            // 取消正在执行的任务
+            cmd if cmd.starts_with("cancel_job_") => {
+                let Ok(job_id) = cmd.trim_start_matches("cancel_job_").parse::<u64>() else {
+                    bot.answer_callback_query(&callback_query.id).text("❌ 无效的任务").await?;
+                    return Ok(());
+                };
+
+                log::info!("🎯 处理取消任务请求: {}", job_id);
+                let text = if scheduler::jobs::cancel(job_id) {
+                    "⛔ 已发送取消请求"
+                } else {
+                    "任务已结束"
+                };
+                bot.answer_callback_query(&callback_query.id).text(text).await?;
+            }
+            cmd if cmd.starts_with("missed_policy_") => {
+                let Ok(task_id) = cmd.trim_start_matches("missed_policy_").parse::<TaskId>() else {
+                    bot.answer_callback_query(&callback_query.id).text("❌ 无效的任务").await?;
+                    return Ok(());
+                };
+
+                log::info!("🎯 处理错过执行策略切换: 任务 #{}", task_id);
+                bot.answer_callback_query(&callback_query.id).await?;
+
+                let response_msg = {
+                    let manager_guard = crate::scheduler::SCHEDULER_MANAGER.lock().await;
+                    match &*manager_guard {
+                        Some(manager) => manager
+                            .cycle_missed_run_policy_by_id(task_id)
+                            .await
+                            .unwrap_or_else(|e| format!("❌ 设置错过执行策略失败: {}", e)),
+                        None => "❌ 调度器尚未初始化".to_string(),
+                    }
+                };
+
+                let tasks_summary = scheduler::get_tasks_summary().await.unwrap_or_else(|_| "❌ 无法获取任务列表".to_string());
+                let keyboard = build_task_list_keyboard(&scheduler::get_tasks().await);
+                bot.edit_message_text(chat_id, message_id, format!("{}\n\n{}", response_msg, tasks_summary))
+                    .reply_markup(keyboard)
+                    .await?;
+
+                log::info!("✅ missed_policy 处理完成");
+                return Ok(());
+            }
+            cmd if cmd.starts_with("notify_policy_") || cmd.starts_with("silent_delivery_") => {
+                let (is_policy, id) = match cmd.strip_prefix("notify_policy_") {
+                    Some(id) => (true, id),
+                    None => (false, cmd.trim_start_matches("silent_delivery_")),
+                };
+                let Ok(task_id) = id.parse::<TaskId>() else {
+                    bot.answer_callback_query(&callback_query.id).text("❌ 无效的任务").await?;
+                    return Ok(());
+                };
+
+                log::info!("🎯 处理通知设置切换: 任务 #{}", task_id);
+                bot.answer_callback_query(&callback_query.id).await?;
+
+                let response_msg = {
+                    let manager_guard = crate::scheduler::SCHEDULER_MANAGER.lock().await;
+                    match &*manager_guard {
+                        Some(manager) if is_policy => manager
+                            .cycle_notification_policy_by_id(task_id)
+                            .await
+                            .unwrap_or_else(|e| format!("❌ 设置通知策略失败: {}", e)),
+                        Some(manager) => manager
+                            .toggle_silent_delivery_by_id(task_id)
+                            .await
+                            .unwrap_or_else(|e| format!("❌ 设置静默送达失败: {}", e)),
+                        None => "❌ 调度器尚未初始化".to_string(),
+                    }
+                };
+
+                let tasks_summary = scheduler::get_tasks_summary().await.unwrap_or_else(|_| "❌ 无法获取任务列表".to_string());
+                let keyboard = build_task_list_keyboard(&scheduler::get_tasks().await);
+                bot.edit_message_text(chat_id, message_id, format!("{}\n\n{}", response_msg, tasks_summary))
+                    .reply_markup(keyboard)
+                    .await?;
+
+                log::info!("✅ 通知设置处理完成");
+                return Ok(());
+            }
+            // 切换任务重启策略
+            cmd if cmd.starts_with("reboot_policy_") => {
+                let Ok(task_id) = cmd.trim_start_matches("reboot_policy_").parse::<TaskId>() else {
+                    bot.answer_callback_query(&callback_query.id).text("❌ 无效的任务").await?;
+                    return Ok(());
+                };
+
+                log::info!("🎯 处理重启策略切换: 任务 #{}", task_id);
+                bot.answer_callback_query(&callback_query.id).await?;
+
+                let response_msg = {
+                    let manager_guard = crate::scheduler::SCHEDULER_MANAGER.lock().await;
+                    match &*manager_guard {
+                        Some(manager) => manager
+                            .cycle_reboot_policy_by_id(task_id)
+                            .await
+                            .unwrap_or_else(|e| format!("❌ 设置重启策略失败: {}", e)),
+                        None => "❌ 调度器尚未初始化".to_string(),
+                    }
+                };
+
+                let tasks_summary = scheduler::get_tasks_summary().await.unwrap_or_else(|_| "❌ 无法获取任务列表".to_string());
+                let keyboard = build_task_list_keyboard(&scheduler::get_tasks().await);
+                bot.edit_message_text(chat_id, message_id, format!("{}\n\n{}", response_msg, tasks_summary))
+                    .reply_markup(keyboard)
+                    .await?;
+
+                log::info!("✅ reboot_policy 处理完成");
+                return Ok(());
+            }
             // 删除任务处理
             cmd if cmd.starts_with("del_task_") => {
-                let task_index_str = cmd.strip_prefix("del_task_").unwrap_or("0");
-                let task_index = task_index_str.parse::<usize>().unwrap_or(0);
+                let Ok(task_id) = cmd.trim_start_matches("del_task_").parse::<TaskId>() else {
+                    bot.answer_callback_query(&callback_query.id).text("❌ 无效的任务").await?;
+                    return Ok(());
+                };
                 
-                log::info!("🎯 处理删除任务: 索引 {}", task_index);
+                log::info!("🎯 处理删除任务: 任务 #{}", task_id);
                 bot.answer_callback_query(&callback_query.id).await?;
                 
-                let message = format!("🗑️ 正在删除任务 {}...", task_index + 1);
+                let message = format!("🗑️ 正在删除任务 #{}...", task_id);
                 
                 // 暂时显示加载消息
                 bot.edit_message_text(chat_id, message_id, message).await?;
@@ -1468,7 +2030,6 @@ async fn handle_callback_query(
                 let bot_clone = bot.clone();
                 let chat_id_clone = chat_id;
                 let message_id_clone = message_id;
-                let config = Config::load().unwrap_or_else(|_| Config { bot_token: "".to_string(), chat_id: 0, check_interval: 300 });
                 
                 tokio::spawn(async move {
                     let mut retry_count = 0;
@@ -1477,11 +2038,7 @@ async fn handle_callback_query(
                     while retry_count < max_retries {
                         let manager_guard = crate::scheduler::SCHEDULER_MANAGER.lock().await;
                         if let Some(manager) = &*manager_guard {
-                            let result = manager.remove_task_by_index(
-                                config.clone(),
-                                Bot::new(config.bot_token.clone()),
-                                task_index
-                            ).await;
+                            let result = manager.remove_task_by_id(task_id).await;
                             
                             drop(manager_guard); // 立即释放锁
                             
@@ -1491,33 +2048,9 @@ async fn handle_callback_query(
                                     let tasks_summary = crate::scheduler::get_tasks_summary().await.unwrap_or_else(|_| "❌ 无法获取任务列表".to_string());
                                     
                                     // 重新构建键盘
-                                    let mut keyboard = Vec::new();
+                                    let keyboard = build_task_list_keyboard(&crate::scheduler::get_tasks().await);
                                     
-                                    if !tasks_summary.contains("暂无定时任务") {
-                                        // 分析任务列表，提取任务数量
-                                        let new_task_count = tasks_summary.matches("✅").count() + tasks_summary.matches("⏸️").count();
-                                        
-                                        // 为每个任务添加删除按钮
-                                        for i in 0..new_task_count {
-                                            let task_row = vec![
-                                                InlineKeyboardButton::callback(
-                                                    format!("🗑️ 删除任务 {}", i + 1), 
-                                                    format!("del_task_{}", i)
-                                                )
-                                            ];
-                                            keyboard.push(task_row);
-                                        }
-                                    }
-                                    
-                                    // 添加通用按钮
-                                    keyboard.push(vec![
-                                        InlineKeyboardButton::callback("➕ 添加新任务", "add_new_task"),
-                                        InlineKeyboardButton::callback("🔙 返回", "back_to_task_types"),
-                                    ]);
-                                    
-                                    let keyboard = InlineKeyboardMarkup::new(keyboard);
-                                    
-                                    let final_message = format!("✅ {}\n\n{}", response_msg, tasks_summary);
+                                    let final_message = format!("{}\n\n{}", response_msg, tasks_summary);
                                     let _ = bot_clone.edit_message_text(
                                         chat_id_clone,
                                         message_id_clone,
@@ -1606,21 +2139,143 @@ async fn handle_callback_query(
     Ok(())
 }
 
+// 辅助函数：格式化系统状态消息
+fn format_system_status(status: &system::SystemStatus) -> String {
+    let mut reply = format!(
+        "📊 系统状态:\n\n{}",
+        format!("🔹 CPU 使用率: {:.2}%\n", status.cpu_usage) +
+        &format!("🔹 内存使用: {} MB / {} MB\n", status.memory_used / 1024 / 1024, status.memory_total / 1024 / 1024) +
+        &format!("🔹 磁盘使用: {} GB / {} GB\n", status.disk_used / 1024 / 1024 / 1024, status.disk_total / 1024 / 1024 / 1024)
+    );
+
+    for mount in &status.mounts {
+        reply.push_str(&format!(
+            "   • {} ({}): {:.1} GB / {:.1} GB ({:.0}%)",
+            mount.mount_point,
+            mount.fs_type,
+            mount.used_bytes as f64 / 1024.0 / 1024.0 / 1024.0,
+            mount.total_bytes as f64 / 1024.0 / 1024.0 / 1024.0,
+            mount.usage_percent()
+        ));
+        if let Some(inode_percent) = mount.inode_percent() {
+            reply.push_str(&format!("，inode {:.0}%", inode_percent));
+        }
+        reply.push('\n');
+    }
+
+    reply.push_str(&format!(
+        "🔹 负载: {:.2} / {:.2} / {:.2}\n",
+        status.load_average.one, status.load_average.five, status.load_average.fifteen
+    ));
+    if status.swap_total > 0 {
+        reply.push_str(&format!("🔹 Swap: {} MB / {} MB\n", status.swap_used / 1024 / 1024, status.swap_total / 1024 / 1024));
+    }
+    reply.push_str(&format!("🔹 iowait: {:.1}%  steal: {:.1}%\n", status.iowait_percent, status.steal_percent));
+    let pressure: Vec<String> = [
+        ("CPU", status.pressure.cpu),
+        ("内存", status.pressure.memory),
+        ("IO", status.pressure.io),
+    ]
+    .iter()
+    .filter_map(|(name, stall)| stall.map(|stall| format!("{} {:.1}%", name, stall.some_avg10)))
+    .collect();
+    if !pressure.is_empty() {
+        reply.push_str(&format!("🔹 PSI (avg10): {}\n", pressure.join(" / ")));
+    }
+    reply.push_str(&format!("🔹 网络接收: {} MB\n", status.network_rx / 1024 / 1024));
+    reply.push_str(&format!("🔹 网络发送: {} MB\n", status.network_tx / 1024 / 1024));
+    for interface in &status.interfaces {
+        reply.push_str(&format!(
+            "   • {}: ↓ {}/s ↑ {}/s\n",
+            interface.name,
+            monitor::traffic::format_bytes(interface.rx_rate as u64),
+            monitor::traffic::format_bytes(interface.tx_rate as u64)
+        ));
+    }
+    let state_dir = Config::load().map(|c| c.scheduler).unwrap_or_default().state_dir;
+    if let Some(summary) = monitor::traffic::usage_summary(&state_dir) {
+        reply.push_str(&format!("🔹 {}\n", summary));
+    }
+    reply.push_str(&format!("🔹 运行时间: {} 秒", status.uptime));
+
+    if !status.top_cpu_processes.is_empty() {
+        reply.push_str("\n\n🔝 CPU 占用前 5:");
+        for process in &status.top_cpu_processes {
+            reply.push_str(&format!("\n   • {} ({}): {:.1}%", process.name, process.pid, process.cpu_percent));
+        }
+    }
+    if !status.top_memory_processes.is_empty() {
+        reply.push_str("\n\n🔝 内存占用前 5:");
+        for process in &status.top_memory_processes {
+            reply.push_str(&format!("\n   • {} ({}): {} MB", process.name, process.pid, process.rss_bytes / 1024 / 1024));
+        }
+    }
+    reply
+}
+
+/// 发起人描述，用于维护锁的持有者信息
+fn describe_initiator(user: Option<&User>) -> String {
+    match user {
+        Some(user) => match &user.username {
+            Some(username) => format!("@{}", username),
+            None => format!("{} ({})", user.full_name(), user.id),
+        },
+        None => "未知用户".to_string(),
+    }
+}
+
+/// 尝试获取维护锁，已被占用时返回拒绝提示
+fn try_lock_maintenance(operation: &str, user: Option<&User>) -> Result<MaintenanceGuard, String> {
+    MaintenanceLock::global()
+        .try_acquire(operation, &describe_initiator(user))
+        .map_err(|e| format!("🚫 无法执行{}: {}", operation, e))
+}
+
+// 辅助函数：获取维护锁，失败时回复消息
+async fn lock_maintenance_or_reply(
+    bot: &Bot,
+    chat_id: ChatId,
+    operation: &str,
+    user: Option<&User>,
+) -> Result<Option<MaintenanceGuard>, Box<dyn std::error::Error + Send + Sync + 'static>> {
+    match try_lock_maintenance(operation, user) {
+        Ok(guard) => Ok(Some(guard)),
+        Err(reason) => {
+            bot.send_message(chat_id, reason).await?;
+            Ok(None)
+        }
+    }
+}
+
+// 辅助函数：获取维护锁，失败时在维护菜单中提示
+async fn lock_maintenance_or_edit(
+    bot: &Bot,
+    callback_query: &CallbackQuery,
+    operation: &str,
+) -> Result<Option<MaintenanceGuard>, Box<dyn std::error::Error + Send + Sync + 'static>> {
+    match try_lock_maintenance(operation, Some(&callback_query.from)) {
+        Ok(guard) => Ok(Some(guard)),
+        Err(reason) => {
+            bot.edit_message_text(
+                callback_query.message.as_ref().unwrap().chat.id,
+                callback_query.message.as_ref().unwrap().id,
+                format!("{}\n\n请选择下一步操作:", reason),
+            )
+            .reply_markup(build_maintain_menu_keyboard())
+            .await?;
+            Ok(None)
+        }
+    }
+}
+
 // 辅助函数：处理状态命令
 async fn handle_status_command(
     bot: &Bot,
     callback_query: &CallbackQuery,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
-    if let Ok(status) = system::get_system_status() {
-        let reply = format!(
-            "📊 系统状态:\n\n{}",
-            format!("🔹 CPU 使用率: {:.2}%\n", status.cpu_usage) +
-            &format!("🔹 内存使用: {} MB / {} MB\n", status.memory_used / 1024 / 1024, status.memory_total / 1024 / 1024) +
-            &format!("🔹 磁盘使用: {} GB / {} GB\n", status.disk_used / 1024 / 1024 / 1024, status.disk_total / 1024 / 1024 / 1024) +
-            &format!("🔹 网络接收: {} MB\n", status.network_rx / 1024 / 1024) +
-            &format!("🔹 网络发送: {} MB\n", status.network_tx / 1024 / 1024) +
-            &format!("🔹 运行时间: {} 秒", status.uptime)
-        );
+    if let Ok(Ok(status)) = tokio::task::spawn_blocking(system::get_system_status).await {
+        let mut reply = format_system_status(&status);
+        reply.push_str(&format!("\n\n{}", system::reboot::check_reboot_required().await.describe()));
         
         bot.edit_message_text(
             callback_query.message.as_ref().unwrap().chat.id,
@@ -1646,6 +2301,10 @@ async fn handle_maintain_core_command(
     bot: &Bot,
     callback_query: &CallbackQuery,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let Some(_guard) = lock_maintenance_or_edit(bot, callback_query, "🚀 核心维护").await? else {
+        return Ok(());
+    };
+
     bot.edit_message_text(
         callback_query.message.as_ref().unwrap().chat.id,
         callback_query.message.as_ref().unwrap().id,
@@ -1687,6 +2346,10 @@ async fn handle_maintain_rules_command(
     bot: &Bot,
     callback_query: &CallbackQuery,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let Some(_guard) = lock_maintenance_or_edit(bot, callback_query, "🌍 规则维护").await? else {
+        return Ok(());
+    };
+
     bot.edit_message_text(
         callback_query.message.as_ref().unwrap().chat.id,
         callback_query.message.as_ref().unwrap().id,
@@ -1728,6 +2391,10 @@ async fn handle_update_xray_command(
     bot: &Bot,
     callback_query: &CallbackQuery,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let Some(_guard) = lock_maintenance_or_edit(bot, callback_query, "🔧 更新 Xray").await? else {
+        return Ok(());
+    };
+
     bot.edit_message_text(
         callback_query.message.as_ref().unwrap().chat.id,
         callback_query.message.as_ref().unwrap().id,
@@ -1769,6 +2436,10 @@ async fn handle_update_sb_command(
     bot: &Bot,
     callback_query: &CallbackQuery,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let Some(_guard) = lock_maintenance_or_edit(bot, callback_query, "📦 更新 Sing-box").await? else {
+        return Ok(());
+    };
+
     bot.edit_message_text(
         callback_query.message.as_ref().unwrap().chat.id,
         callback_query.message.as_ref().unwrap().id,
@@ -1830,6 +2501,32 @@ mod tests {
         assert_eq!(commands.len(), 12); // 确保所有命令都被测试到
     }
 
+    #[test]
+    fn test_running_jobs_list_has_cancel_buttons() {
+        assert_eq!(format_running_jobs(&[]), "📭 当前没有执行中的任务");
+
+        let started_at = chrono::Local::now();
+        let jobs = vec![
+            scheduler::jobs::JobInfo { id: 3, name: "🔄 系统维护".to_string(), started_at },
+            scheduler::jobs::JobInfo { id: 7, name: "🚀 核心维护".to_string(), started_at },
+        ];
+        let text = format_running_jobs(&jobs);
+        assert!(text.contains("执行中的任务 (2)"));
+        assert!(text.contains("#3 🔄 系统维护"));
+
+        let keyboard = build_running_jobs_keyboard(&jobs);
+        let callbacks: Vec<_> = keyboard
+            .inline_keyboard
+            .iter()
+            .flatten()
+            .map(|button| match &button.kind {
+                teloxide::types::InlineKeyboardButtonKind::CallbackData(data) => data.clone(),
+                _ => String::new(),
+            })
+            .collect();
+        assert_eq!(callbacks, vec!["cancel_job_3", "cancel_job_7"]);
+    }
+
     #[test]
     fn test_get_task_display_name() {
         // 测试已知任务类型
@@ -2161,7 +2858,7 @@ mod tests {
         let keyboard = build_task_type_menu_keyboard();
         
         // 检查键盘行数
-        assert_eq!(keyboard.inline_keyboard.len(), 4);
+        assert_eq!(keyboard.inline_keyboard.len(), 5);
         
         // 检查第一行（系统维护 + 核心维护）
         let first_row = &keyboard.inline_keyboard[0];
@@ -2181,10 +2878,16 @@ mod tests {
         assert_eq!(third_row[0].text, "📦 更新 Sing-box");
         assert_eq!(third_row[1].text, "📋 查看任务列表");
         
-        // 检查第四行（返回）
+        // 检查第四行（流水线 + 自定义命令）
         let fourth_row = &keyboard.inline_keyboard[3];
-        assert_eq!(fourth_row.len(), 1);
-        assert_eq!(fourth_row[0].text, "🔙 返回");
+        assert_eq!(fourth_row.len(), 2);
+        assert_eq!(fourth_row[0].text, "🧩 流水线");
+        assert_eq!(fourth_row[1].text, "⚙️ 自定义命令");
+
+        // 检查第五行（返回）
+        let fifth_row = &keyboard.inline_keyboard[4];
+        assert_eq!(fifth_row.len(), 1);
+        assert_eq!(fifth_row[0].text, "🔙 返回");
     }
     
     #[test]
@@ -2353,6 +3056,65 @@ mod tests {
         assert!(reply.contains("🔹 网络发送: 512 MB"));
         assert!(reply.contains("🔹 运行时间: 86400 秒"));
     }
+
+    #[test]
+    fn test_format_system_status_lists_mounts() {
+        let status = system::SystemStatus {
+            disk_used: 93 * 1024 * 1024 * 1024,
+            disk_total: 100 * 1024 * 1024 * 1024,
+            mounts: vec![system::disk::MountUsage {
+                mount_point: "/var".to_string(),
+                fs_type: "xfs".to_string(),
+                used_bytes: 93 * 1024 * 1024 * 1024,
+                total_bytes: 100 * 1024 * 1024 * 1024,
+                inodes_used: 1200,
+                inodes_total: 10000,
+            }],
+            ..Default::default()
+        };
+
+        let reply = format_system_status(&status);
+        assert!(reply.starts_with("📊 系统状态:"));
+        assert!(reply.contains("🔹 磁盘使用: 93 GB / 100 GB"));
+        assert!(reply.contains("• /var (xfs): 93.0 GB / 100.0 GB (93%)，inode 12%"));
+    }
+
+    #[test]
+    fn test_format_system_status_extended_metrics() {
+        let status = system::SystemStatus {
+            load_average: system::procfs::LoadAverage { one: 1.5, five: 0.75, fifteen: 0.25 },
+            swap_used: 256 * 1024 * 1024,
+            swap_total: 1024 * 1024 * 1024,
+            iowait_percent: 12.5,
+            steal_percent: 3.0,
+            pressure: system::procfs::PressureInfo {
+                cpu: Some(system::procfs::PressureStall { some_avg10: 4.2, full_avg10: None }),
+                memory: None,
+                io: Some(system::procfs::PressureStall { some_avg10: 1.0, full_avg10: Some(0.5) }),
+            },
+            top_cpu_processes: vec![system::procfs::ProcessUsage {
+                pid: 42,
+                name: "nginx".to_string(),
+                cpu_percent: 55.5,
+                rss_bytes: 64 * 1024 * 1024,
+            }],
+            top_memory_processes: vec![system::procfs::ProcessUsage {
+                pid: 7,
+                name: "mysqld".to_string(),
+                cpu_percent: 1.0,
+                rss_bytes: 512 * 1024 * 1024,
+            }],
+            ..Default::default()
+        };
+
+        let reply = format_system_status(&status);
+        assert!(reply.contains("🔹 负载: 1.50 / 0.75 / 0.25"));
+        assert!(reply.contains("🔹 Swap: 256 MB / 1024 MB"));
+        assert!(reply.contains("🔹 iowait: 12.5%  steal: 3.0%"));
+        assert!(reply.contains("🔹 PSI (avg10): CPU 4.2% / IO 1.0%"));
+        assert!(reply.contains("• nginx (42): 55.5%"));
+        assert!(reply.contains("• mysqld (7): 512 MB"));
+    }
     
     #[test]
     fn test_maintenance_report_message_format() {
@@ -2595,4 +3357,77 @@ mod tests {
             }
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_blackout_confirmation_keyboard() {
+        // 维护按钮需要确认，确认后的回调不再要求确认
+        assert!(requires_blackout_confirmation("cmd_full_maintenance"));
+        assert!(requires_blackout_confirmation("run_pipeline_0"));
+        assert!(!requires_blackout_confirmation("cmd_status"));
+        // 确认后执行原命令，而不是菜单中相近的操作
+        assert_eq!(blackout_confirmation_callback(&Command::Maintain), Some("run_command_maintain"));
+        assert_eq!(blackout_confirmation_callback(&Command::MaintainCore), Some("run_command_maintain_core"));
+        assert_eq!(blackout_confirmation_callback(&Command::Status), None);
+        assert!(matches!(confirmed_command("run_command_maintain"), Some(Command::Maintain)));
+        assert!(matches!(confirmed_command("run_command_full_maintenance"), Some(Command::FullMaintenance)));
+        assert!(confirmed_command("cmd_maintain_core").is_none());
+
+        let keyboard = build_blackout_confirm_keyboard("cmd_update_xray");
+        let callbacks: Vec<String> = keyboard.inline_keyboard[0]
+            .iter()
+            .map(|button| match &button.kind {
+                teloxide::types::InlineKeyboardButtonKind::CallbackData(data) => data.clone(),
+                _ => String::new(),
+            })
+            .collect();
+        assert_eq!(callbacks, vec!["force_cmd_update_xray".to_string(), "menu_maintain".to_string()]);
+    }
+
+    #[test]
+    fn test_task_list_keyboard_notification_buttons() {
+        let mut task = ScheduledTask::new(crate::scheduler::task_types::TaskType::RulesMaintenance, "0 */6 * * *");
+        task.id = 7;
+        task.silent_delivery = true;
+
+        let keyboard = build_task_list_keyboard(&[task]);
+        // 任务占两行，最后一行为添加和返回按钮
+        assert_eq!(keyboard.inline_keyboard.len(), 3);
+        let callbacks: Vec<String> = keyboard.inline_keyboard[1]
+            .iter()
+            .map(|button| match &button.kind {
+                teloxide::types::InlineKeyboardButtonKind::CallbackData(data) => data.clone(),
+                _ => String::new(),
+            })
+            .collect();
+        assert_eq!(callbacks, vec!["notify_policy_7".to_string(), "silent_delivery_7".to_string()]);
+        assert_eq!(keyboard.inline_keyboard[1][0].text, "📣 #7 总是通知");
+        assert_eq!(keyboard.inline_keyboard[1][1].text, "🔕 静默送达");
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_keyboard_uses_stable_ids() {
+        let mut pipeline = Pipeline::full_maintenance();
+        pipeline.id = 5;
+        let keyboard = build_pipeline_list_keyboard(&[pipeline], "run_pipeline_", "menu_maintain");
+        match &keyboard.inline_keyboard[0][0].kind {
+            teloxide::types::InlineKeyboardButtonKind::CallbackData(data) => assert_eq!(data, "run_pipeline_5"),
+            _ => panic!("应为回调按钮"),
+        }
+
+        // 找不到对应 ID 的旧按钮被拒绝，而不是执行其他流水线
+        assert!(find_pipeline("5").await.is_none());
+        assert!(find_pipeline("abc").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_custom_command_keyboard_uses_stable_ids() {
+        let mut command = CustomCommand::parse_definition("重载 /usr/bin/systemctl reload nginx").unwrap();
+        command.id = 3;
+        let keyboard = build_custom_command_list_keyboard(&[command]);
+        match &keyboard.inline_keyboard[0][0].kind {
+            teloxide::types::InlineKeyboardButtonKind::CallbackData(data) => assert_eq!(data, "task_command_3"),
+            _ => panic!("应为回调按钮"),
+        }
+        assert!(find_custom_command("3").await.is_none());
+    }
+}
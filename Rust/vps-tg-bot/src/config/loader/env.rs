@@ -1,13 +1,22 @@
 //! 环境变量配置加载器
 //! 
 //! 从环境变量 BOT_TOKEN、CHAT_ID、CHECK_INTERVAL 加载配置
+//! 资源告警阈值从 ALERT_* 环境变量加载，流量配额从 TRAFFIC_* 环境变量加载，
+//! 服务监控从 WATCH_UNITS / UNIT_* 环境变量加载，证书监控从 CERT_* 环境变量加载，
+//! 入站端口检查由 PORT_CHECK 控制，SSH 登录通知从 SSH_* 环境变量加载，
+//! 内核事件监控从 KERNEL_* 环境变量加载，任务超时从 TASK_TIMEOUT / TASK_TIMEOUTS 加载，
+//! 自定义命令白名单从 CUSTOM_COMMAND_ALLOWLIST 加载，
+//! 维护窗口从 MAINTENANCE_WINDOWS / MAINTENANCE_BLACKOUTS / MAINTENANCE_WINDOW_POLICY 加载（均可选），
+//! 任务默认时区从 DEFAULT_TIMEZONE 加载（可选），调度器状态目录从 STATE_DIR 加载（可选）
 //! 支持从 systemd 凭证文件读取敏感信息
 //! 优先级：环境变量 > systemd 凭证文件
 
 use crate::config::loader::{ConfigLoader};
-use crate::config::types::{Config, ConfigError, ConfigResult, ConfigSource};
+use crate::config::types::{Config, ConfigError, ConfigResult, ConfigSource, MonitorConfig, SchedulerConfig};
+use std::collections::HashMap;
 use log::{debug, warn};
 use std::env;
+use std::path::PathBuf;
 use std::cell::RefCell;
 
 /// 环境变量配置加载器
@@ -77,6 +86,128 @@ impl EnvironmentLoader {
         Some((bot_token, chat_id))
     }
     
+    /// 从 ALERT_* / TRAFFIC_* / UNIT_* / CERT_* 等环境变量加载监控配置，未设置或格式无效时使用默认值
+    fn load_monitor_config() -> MonitorConfig {
+        let defaults = MonitorConfig::default();
+        
+        MonitorConfig {
+            cpu_threshold: Self::env_or("ALERT_CPU_THRESHOLD", defaults.cpu_threshold),
+            memory_threshold: Self::env_or("ALERT_MEMORY_THRESHOLD", defaults.memory_threshold),
+            disk_threshold: Self::env_or("ALERT_DISK_THRESHOLD", defaults.disk_threshold),
+            sustained_checks: Self::env_or("ALERT_SUSTAINED_CHECKS", defaults.sustained_checks),
+            recovery_margin: Self::env_or("ALERT_RECOVERY_MARGIN", defaults.recovery_margin),
+            traffic_quota_gb: Self::env_or("TRAFFIC_QUOTA_GB", defaults.traffic_quota_gb),
+            traffic_reset_day: Self::env_or("TRAFFIC_RESET_DAY", defaults.traffic_reset_day),
+            watched_units: Self::env_list("WATCH_UNITS"),
+            unit_check_interval: Self::env_or("UNIT_CHECK_INTERVAL", defaults.unit_check_interval),
+            unit_auto_restart: Self::env_or("UNIT_AUTO_RESTART", defaults.unit_auto_restart),
+            unit_restart_max_attempts: Self::env_or("UNIT_RESTART_MAX_ATTEMPTS", defaults.unit_restart_max_attempts),
+            unit_restart_backoff: Self::env_or("UNIT_RESTART_BACKOFF", defaults.unit_restart_backoff),
+            cert_paths: Self::env_list("CERT_PATHS"),
+            cert_alert_days: Self::env_parsed_list("CERT_ALERT_DAYS").unwrap_or(defaults.cert_alert_days),
+            port_check: Self::env_or("PORT_CHECK", defaults.port_check),
+            ssh_notify: Self::env_or("SSH_NOTIFY", defaults.ssh_notify),
+            ssh_trusted_ips: Self::env_list("SSH_TRUSTED_IPS"),
+            ssh_failed_summary_interval: Self::env_or(
+                "SSH_FAILED_SUMMARY_INTERVAL",
+                defaults.ssh_failed_summary_interval,
+            ),
+            kernel_watch: Self::env_or("KERNEL_WATCH", defaults.kernel_watch),
+            // 正则中常含逗号，规则之间用分号分隔
+            kernel_patterns: Self::env_list_separated("KERNEL_PATTERNS", ';'),
+        }
+    }
+    
+    /// 从 TASK_* 环境变量加载定时任务执行配置
+    fn load_scheduler_config() -> SchedulerConfig {
+        let defaults = SchedulerConfig::default();
+        SchedulerConfig {
+            default_task_timeout: Self::env_or("TASK_TIMEOUT", defaults.default_task_timeout),
+            task_timeouts: Self::env_key_values("TASK_TIMEOUTS").unwrap_or(defaults.task_timeouts),
+            custom_command_allowlist: Self::env_list("CUSTOM_COMMAND_ALLOWLIST"),
+            // 星期列表中含逗号，窗口之间用分号分隔
+            maintenance_windows: Self::env_list_separated("MAINTENANCE_WINDOWS", ';'),
+            blackout_dates: Self::env_list("MAINTENANCE_BLACKOUTS"),
+            outside_window_policy: Self::env_or("MAINTENANCE_WINDOW_POLICY", defaults.outside_window_policy),
+            default_timezone: env::var("DEFAULT_TIMEZONE")
+                .ok()
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty()),
+            state_dir: env::var("STATE_DIR")
+                .ok()
+                .map(|dir| dir.trim().to_string())
+                .filter(|dir| !dir.is_empty())
+                .map(PathBuf::from)
+                .unwrap_or(defaults.state_dir),
+        }
+    }
+    
+    /// 读取逗号分隔的 `键=值` 列表，任一项格式无效时返回 None
+    fn env_key_values<T: std::str::FromStr>(name: &str) -> Option<HashMap<String, T>> {
+        let items = Self::env_list(name);
+        if items.is_empty() {
+            return None;
+        }
+        let parsed = items
+            .iter()
+            .map(|item| {
+                let (key, value) = item.split_once('=')?;
+                Some((key.trim().to_string(), value.trim().parse::<T>().ok()?))
+            })
+            .collect::<Option<HashMap<String, T>>>();
+        if parsed.is_none() {
+            warn!("⚠️  {} 格式无效，使用默认值", name);
+        }
+        parsed
+    }
+    
+    /// 读取逗号分隔的列表型环境变量，忽略空项
+    fn env_list(name: &str) -> Vec<String> {
+        Self::env_list_separated(name, ',')
+    }
+    
+    /// 读取按指定分隔符分隔的列表，忽略空项
+    fn env_list_separated(name: &str, separator: char) -> Vec<String> {
+        env::var(name)
+            .map(|value| {
+                value
+                    .split(separator)
+                    .map(|item| item.trim().to_string())
+                    .filter(|item| !item.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+    
+    /// 读取逗号分隔的数值列表，未设置或任一项格式无效时返回 None
+    fn env_parsed_list<T: std::str::FromStr>(name: &str) -> Option<Vec<T>> {
+        let items = Self::env_list(name);
+        if items.is_empty() {
+            return None;
+        }
+        match items.iter().map(|item| item.parse::<T>()).collect::<Result<Vec<T>, _>>() {
+            Ok(values) => Some(values),
+            Err(_) => {
+                warn!("⚠️  {} 格式无效，使用默认值", name);
+                None
+            }
+        }
+    }
+    
+    /// 读取并解析可选环境变量
+    fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+        match env::var(name) {
+            Ok(value) if !value.trim().is_empty() => match value.trim().parse::<T>() {
+                Ok(parsed) => parsed,
+                Err(_) => {
+                    warn!("⚠️  {} 格式无效，使用默认值", name);
+                    default
+                }
+            },
+            _ => default,
+        }
+    }
+    
     /// 检查环境变量是否设置且有效
     fn check_env_vars() -> bool {
         // 检查必需的变量
@@ -171,6 +302,8 @@ impl ConfigLoader for EnvironmentLoader {
                     bot_token,
                     chat_id,
                     check_interval,
+                    monitor: Self::load_monitor_config(),
+                    scheduler: Self::load_scheduler_config(),
                 };
                 
                 debug!("✅ 从环境变量成功加载配置");
@@ -198,6 +331,8 @@ impl ConfigLoader for EnvironmentLoader {
                 bot_token,
                 chat_id,
                 check_interval,
+                monitor: Self::load_monitor_config(),
+                scheduler: Self::load_scheduler_config(),
             };
             
             debug!("✅ 从 systemd 凭证文件成功加载配置");
@@ -332,4 +467,148 @@ mod tests {
         
         cleanup_test_env();
     }
+
+    #[test]
+    fn test_load_monitor_config_from_env() {
+        env::set_var("ALERT_CPU_THRESHOLD", "85");
+        env::set_var("ALERT_SUSTAINED_CHECKS", "not_a_number");
+        
+        let monitor = EnvironmentLoader::load_monitor_config();
+        assert_eq!(monitor.cpu_threshold, 85.0);
+        // 格式无效时回退到默认值
+        assert_eq!(monitor.sustained_checks, MonitorConfig::default().sustained_checks);
+        assert_eq!(monitor.memory_threshold, MonitorConfig::default().memory_threshold);
+        
+        env::remove_var("ALERT_CPU_THRESHOLD");
+        env::remove_var("ALERT_SUSTAINED_CHECKS");
+    }
+    
+    #[test]
+    fn test_load_watched_units_from_env() {
+        env::set_var("WATCH_UNITS", " xray , sing-box,,nginx.service ");
+        
+        let monitor = EnvironmentLoader::load_monitor_config();
+        assert_eq!(monitor.watched_units, vec!["xray", "sing-box", "nginx.service"]);
+        
+        env::remove_var("WATCH_UNITS");
+    }
+    
+    #[test]
+    fn test_load_cert_alert_days_from_env() {
+        env::set_var("CERT_ALERT_DAYS", "30, 3");
+        assert_eq!(EnvironmentLoader::load_monitor_config().cert_alert_days, vec![30, 3]);
+        
+        env::set_var("CERT_ALERT_DAYS", "30,abc");
+        assert_eq!(EnvironmentLoader::load_monitor_config().cert_alert_days, vec![14, 7, 1]);
+        
+        env::remove_var("CERT_ALERT_DAYS");
+    }
+    
+    #[test]
+    fn test_load_ssh_settings_from_env() {
+        env::set_var("SSH_TRUSTED_IPS", "203.0.113.5, 10.0.0.0/8");
+        env::set_var("SSH_FAILED_SUMMARY_INTERVAL", "600");
+        
+        let monitor = EnvironmentLoader::load_monitor_config();
+        assert!(monitor.ssh_notify);
+        assert_eq!(monitor.ssh_trusted_ips, vec!["203.0.113.5", "10.0.0.0/8"]);
+        assert_eq!(monitor.ssh_failed_summary_interval, 600);
+        
+        env::remove_var("SSH_TRUSTED_IPS");
+        env::remove_var("SSH_FAILED_SUMMARY_INTERVAL");
+    }
+    
+    #[test]
+    fn test_load_kernel_patterns_from_env() {
+        env::set_var("KERNEL_PATTERNS", "oom=Killed process (?P<pid>\\d+), ; nvme=nvme\\d+: I/O \\d+ timeout");
+        
+        let monitor = EnvironmentLoader::load_monitor_config();
+        assert_eq!(
+            monitor.kernel_patterns,
+            vec!["oom=Killed process (?P<pid>\\d+),", "nvme=nvme\\d+: I/O \\d+ timeout"]
+        );
+        assert!(monitor.validate().is_ok());
+        
+        env::remove_var("KERNEL_PATTERNS");
+    }
+    
+    #[test]
+    fn test_load_task_timeouts_from_env() {
+        env::set_var("TASK_TIMEOUT", "1800");
+        env::set_var("TASK_TIMEOUTS", "update_xray=600, rules_maintenance=300");
+        
+        let scheduler = EnvironmentLoader::load_scheduler_config();
+        assert_eq!(scheduler.task_timeout("update_xray").as_secs(), 600);
+        assert_eq!(scheduler.task_timeout("system_maintenance").as_secs(), 1800);
+        
+        env::set_var("TASK_TIMEOUTS", "update_xray=soon");
+        assert!(EnvironmentLoader::load_scheduler_config().task_timeouts.is_empty());
+        
+        env::remove_var("TASK_TIMEOUT");
+        env::remove_var("TASK_TIMEOUTS");
+    }
+    
+    #[test]
+    fn test_load_custom_command_allowlist_from_env() {
+        env::set_var("CUSTOM_COMMAND_ALLOWLIST", "/usr/bin/systemctl, /usr/local/bin/backup.sh");
+        
+        let scheduler = EnvironmentLoader::load_scheduler_config();
+        assert_eq!(scheduler.custom_command_allowlist, vec!["/usr/bin/systemctl", "/usr/local/bin/backup.sh"]);
+        assert!(scheduler.validate().is_ok());
+        
+        env::set_var("CUSTOM_COMMAND_ALLOWLIST", "systemctl");
+        assert!(EnvironmentLoader::load_scheduler_config().validate().is_err());
+        
+        env::remove_var("CUSTOM_COMMAND_ALLOWLIST");
+    }
+    
+    #[test]
+    fn test_load_maintenance_windows_from_env() {
+        env::set_var("MAINTENANCE_WINDOWS", "Mon-Fri 02:00-06:00; Sat,Sun 00:00-08:00");
+        env::set_var("MAINTENANCE_BLACKOUTS", "2026-12-24..2026-12-26, 2027-01-01");
+        env::set_var("MAINTENANCE_WINDOW_POLICY", "skip");
+        
+        let scheduler = EnvironmentLoader::load_scheduler_config();
+        assert_eq!(scheduler.maintenance_windows, vec!["Mon-Fri 02:00-06:00", "Sat,Sun 00:00-08:00"]);
+        assert_eq!(scheduler.blackout_dates.len(), 2);
+        assert_eq!(scheduler.outside_window_policy, crate::scheduler::window::OutsideWindowPolicy::Skip);
+        assert!(scheduler.validate().is_ok());
+        
+        env::set_var("MAINTENANCE_WINDOWS", "weekdays 02:00-06:00");
+        assert!(EnvironmentLoader::load_scheduler_config().validate().is_err());
+        
+        env::remove_var("MAINTENANCE_WINDOWS");
+        env::remove_var("MAINTENANCE_BLACKOUTS");
+        env::remove_var("MAINTENANCE_WINDOW_POLICY");
+    }
+    
+    #[test]
+    fn test_load_default_timezone_from_env() {
+        env::set_var("DEFAULT_TIMEZONE", "Asia/Shanghai");
+        let scheduler = EnvironmentLoader::load_scheduler_config();
+        assert_eq!(scheduler.default_timezone.as_deref(), Some("Asia/Shanghai"));
+        assert_eq!(scheduler.timezone(), chrono_tz::Asia::Shanghai);
+        assert!(scheduler.validate().is_ok());
+        
+        env::set_var("DEFAULT_TIMEZONE", "Mars/Olympus");
+        assert!(EnvironmentLoader::load_scheduler_config().validate().is_err());
+        
+        env::remove_var("DEFAULT_TIMEZONE");
+        assert_eq!(EnvironmentLoader::load_scheduler_config().default_timezone, None);
+    }
+    
+    #[test]
+    fn test_load_state_dir_from_env() {
+        env::remove_var("STATE_DIR");
+        let scheduler = EnvironmentLoader::load_scheduler_config();
+        assert_eq!(scheduler.state_dir, PathBuf::from("/var/lib/vps-tg-bot-rust"));
+        
+        env::set_var("STATE_DIR", "/srv/bot-state");
+        assert_eq!(EnvironmentLoader::load_scheduler_config().state_dir, PathBuf::from("/srv/bot-state"));
+        
+        env::remove_var("STATE_DIR");
+        
+        let relative = SchedulerConfig { state_dir: PathBuf::from("relative/state"), ..Default::default() };
+        assert!(relative.validate().is_err());
+    }
 }
@@ -8,7 +8,7 @@ pub mod types;
 
 // 使用新的类型定义
 use crate::config::types::Config as NewConfig;
-use crate::config::types::{ConfigError, ConfigResult};
+use crate::config::types::{ConfigError, ConfigResult, MonitorConfig, SchedulerConfig};
 use crate::config::loader::{load_config, get_available_sources};
 
 // 保留旧的结构体定义以确保向后兼容
@@ -18,6 +18,10 @@ pub struct Config {
     pub chat_id: i64,
     #[serde(default = "default_check_interval")]
     pub check_interval: u64,
+    #[serde(default)]
+    pub monitor: MonitorConfig,
+    #[serde(default)]
+    pub scheduler: SchedulerConfig,
 }
 
 fn default_check_interval() -> u64 {
@@ -34,6 +38,8 @@ impl Config {
                     bot_token: new_config.bot_token,
                     chat_id: new_config.chat_id,
                     check_interval: new_config.check_interval,
+                    monitor: new_config.monitor,
+                    scheduler: new_config.scheduler,
                 })
             }
             Err(e) => {
@@ -71,6 +77,8 @@ impl Config {
             bot_token: self.bot_token.clone(),
             chat_id: self.chat_id,
             check_interval: self.check_interval,
+            monitor: self.monitor.clone(),
+            scheduler: self.scheduler.clone(),
         };
         
         new_config.validate()
@@ -98,6 +106,8 @@ mod tests {
             bot_token: "test_save_token".to_string(),
             chat_id: 555666777,
             check_interval: 1200,
+            monitor: MonitorConfig::default(),
+            scheduler: SchedulerConfig::default(),
         };
 
         let temp_path = "test_config_save.toml";
@@ -132,6 +142,8 @@ mod tests {
             bot_token: "123456789:ABCdefGHIjklMNOpqrsTUVwxyz".to_string(),
             chat_id: 123456789,
             check_interval: 300,
+            monitor: MonitorConfig::default(),
+            scheduler: SchedulerConfig::default(),
         };
         
         assert!(valid_config.validate().is_ok());
@@ -140,6 +152,8 @@ mod tests {
             bot_token: "".to_string(),
             chat_id: 0,
             check_interval: 30,
+            monitor: MonitorConfig::default(),
+            scheduler: SchedulerConfig::default(),
         };
         
         assert!(invalid_config.validate().is_err());
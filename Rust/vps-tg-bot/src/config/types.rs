@@ -2,7 +2,13 @@
 //! 
 //! 定义配置相关的类型和结构体
 
+use crate::scheduler::timezone;
+use crate::scheduler::window::{MaintenanceWindows, OutsideWindowPolicy};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
 
 /// 配置结构体
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -11,12 +17,239 @@ pub struct Config {
     pub chat_id: i64,
     #[serde(default = "default_check_interval")]
     pub check_interval: u64,
+    #[serde(default)]
+    pub monitor: MonitorConfig,
+    #[serde(default)]
+    pub scheduler: SchedulerConfig,
 }
 
 fn default_check_interval() -> u64 {
     300
 }
 
+/// 资源监控告警配置
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct MonitorConfig {
+    /// CPU 使用率告警阈值（百分比）
+    pub cpu_threshold: f32,
+    /// 内存使用率告警阈值（百分比）
+    pub memory_threshold: f32,
+    /// 磁盘使用率告警阈值（百分比）
+    pub disk_threshold: f32,
+    /// 连续多少次采样越过阈值后才触发告警或恢复
+    pub sustained_checks: u32,
+    /// 恢复滞后量：数值需回落到 阈值 - 滞后量 以下才视为恢复
+    pub recovery_margin: f32,
+    /// 每个计费周期的流量配额（GB，收发合计），0 表示不限制
+    pub traffic_quota_gb: u64,
+    /// 计费周期重置日（每月 1-28 日）
+    pub traffic_reset_day: u32,
+    /// 需要监控的 systemd 服务列表
+    pub watched_units: Vec<String>,
+    /// 服务状态轮询间隔（秒）
+    pub unit_check_interval: u64,
+    /// 服务异常时是否自动重启
+    pub unit_auto_restart: bool,
+    /// 自动重启的最大尝试次数，超过后放弃
+    pub unit_restart_max_attempts: u32,
+    /// 自动重启的初始退避时间（秒），每次重启后翻倍
+    pub unit_restart_backoff: u64,
+    /// 额外需要检查到期时间的证书路径
+    pub cert_paths: Vec<String>,
+    /// 证书到期告警档位（剩余天数）
+    pub cert_alert_days: Vec<u32>,
+    /// 是否检查代理入站端口是否处于监听状态
+    pub port_check: bool,
+    /// 是否推送 SSH 登录通知
+    pub ssh_notify: bool,
+    /// 受信任的 SSH 来源（IP 或 CIDR），成功登录不推送
+    pub ssh_trusted_ips: Vec<String>,
+    /// SSH 失败登录汇总间隔（秒）
+    pub ssh_failed_summary_interval: u64,
+    /// 是否监控内核日志中的严重事件（OOM、文件系统只读、段错误、任务挂起）
+    pub kernel_watch: bool,
+    /// 额外的内核日志匹配规则，格式为 `类别=正则`
+    pub kernel_patterns: Vec<String>,
+}
+
+impl Default for MonitorConfig {
+    fn default() -> Self {
+        Self {
+            cpu_threshold: 90.0,
+            memory_threshold: 90.0,
+            disk_threshold: 90.0,
+            sustained_checks: 3,
+            recovery_margin: 10.0,
+            traffic_quota_gb: 0,
+            traffic_reset_day: 1,
+            watched_units: Vec::new(),
+            unit_check_interval: 30,
+            unit_auto_restart: false,
+            unit_restart_max_attempts: 5,
+            unit_restart_backoff: 10,
+            cert_paths: Vec::new(),
+            cert_alert_days: vec![14, 7, 1],
+            port_check: true,
+            ssh_notify: true,
+            ssh_trusted_ips: Vec::new(),
+            ssh_failed_summary_interval: 3600,
+            kernel_watch: true,
+            kernel_patterns: Vec::new(),
+        }
+    }
+}
+
+impl MonitorConfig {
+    /// 验证告警阈值配置
+    pub fn validate(&self) -> ConfigResult<()> {
+        let thresholds = [
+            ("CPU", self.cpu_threshold),
+            ("内存", self.memory_threshold),
+            ("磁盘", self.disk_threshold),
+        ];
+        for (name, threshold) in thresholds {
+            if !(threshold > 0.0 && threshold <= 100.0) {
+                return Err(ConfigError::ValidationError(
+                    format!("{}告警阈值必须在 0-100 之间", name)
+                ));
+            }
+            if self.recovery_margin >= threshold {
+                return Err(ConfigError::ValidationError(
+                    format!("恢复滞后量必须小于{}告警阈值", name)
+                ));
+            }
+        }
+
+        if self.sustained_checks == 0 {
+            return Err(ConfigError::ValidationError(
+                "告警持续次数不能为0".to_string()
+            ));
+        }
+
+        if self.recovery_margin < 0.0 {
+            return Err(ConfigError::ValidationError(
+                "恢复滞后量不能为负数".to_string()
+            ));
+        }
+
+        if !(1..=28).contains(&self.traffic_reset_day) {
+            return Err(ConfigError::ValidationError(
+                "流量重置日必须在 1-28 之间".to_string()
+            ));
+        }
+
+        if self.unit_check_interval == 0 {
+            return Err(ConfigError::ValidationError(
+                "服务检查间隔不能为0".to_string()
+            ));
+        }
+
+        if self.unit_auto_restart && self.unit_restart_max_attempts == 0 {
+            return Err(ConfigError::ValidationError(
+                "启用自动重启时最大重启次数不能为0".to_string()
+            ));
+        }
+
+        for rule in &self.kernel_patterns {
+            let Some((category, pattern)) = rule.split_once('=') else {
+                return Err(ConfigError::ValidationError(
+                    format!("内核日志匹配规则格式应为 类别=正则: {}", rule)
+                ));
+            };
+            if category.trim().is_empty() || regex::Regex::new(pattern.trim()).is_err() {
+                return Err(ConfigError::ValidationError(
+                    format!("内核日志匹配规则无效: {}", rule)
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// 定时任务执行配置
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct SchedulerConfig {
+    /// 任务默认超时时间（秒），限制单次尝试的时长，重试前的等待不计入
+    pub default_task_timeout: u64,
+    /// 按任务类型覆盖的超时时间（秒），键为任务类型标识，如 `update_xray`
+    pub task_timeouts: HashMap<String, u64>,
+    /// 自定义命令允许执行的程序（绝对路径）
+    pub custom_command_allowlist: Vec<String>,
+    /// 允许定时维护的时间段，例如 `Mon-Fri 02:00-06:00`，为空表示不限制
+    pub maintenance_windows: Vec<String>,
+    /// 禁止维护的日期，例如 `2026-12-24..2026-12-26`
+    pub blackout_dates: Vec<String>,
+    /// 定时任务在维护窗口外触发时推迟还是跳过
+    pub outside_window_policy: OutsideWindowPolicy,
+    /// 任务的默认 IANA 时区，例如 `Asia/Shanghai`，未设置时使用系统时区
+    pub default_timezone: Option<String>,
+    /// 调度器状态文件所在目录
+    pub state_dir: PathBuf,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            default_task_timeout: 3600,
+            task_timeouts: HashMap::new(),
+            custom_command_allowlist: Vec::new(),
+            maintenance_windows: Vec::new(),
+            blackout_dates: Vec::new(),
+            outside_window_policy: OutsideWindowPolicy::default(),
+            default_timezone: None,
+            state_dir: PathBuf::from("/var/lib/vps-tg-bot-rust"),
+        }
+    }
+}
+
+impl SchedulerConfig {
+    /// 指定任务类型的超时时间
+    pub fn task_timeout(&self, task_key: &str) -> Duration {
+        let secs = self.task_timeouts.get(task_key).copied().unwrap_or(self.default_task_timeout);
+        Duration::from_secs(secs)
+    }
+
+    /// 任务的默认时区
+    pub fn timezone(&self) -> Tz {
+        self.default_timezone
+            .as_deref()
+            .and_then(|name| timezone::parse(name).ok())
+            .unwrap_or_else(timezone::system_timezone)
+    }
+
+    /// 验证任务执行配置
+    pub fn validate(&self) -> ConfigResult<()> {
+        if self.default_task_timeout == 0 || self.task_timeouts.values().any(|&secs| secs == 0) {
+            return Err(ConfigError::ValidationError(
+                "任务超时时间不能为0".to_string()
+            ));
+        }
+
+        if let Some(program) = self.custom_command_allowlist.iter().find(|program| !program.starts_with('/')) {
+            return Err(ConfigError::ValidationError(
+                format!("自定义命令白名单中的程序必须使用绝对路径: {}", program)
+            ));
+        }
+
+        MaintenanceWindows::from_config(self).map_err(ConfigError::ValidationError)?;
+
+        if let Some(name) = &self.default_timezone {
+            timezone::parse(name).map_err(ConfigError::ValidationError)?;
+        }
+
+        if !self.state_dir.is_absolute() {
+            return Err(ConfigError::ValidationError(
+                format!("状态目录必须使用绝对路径: {}", self.state_dir.display())
+            ));
+        }
+
+        Ok(())
+    }
+}
+
 /// 配置来源枚举
 #[derive(Debug, Clone, PartialEq)]
 #[allow(dead_code)]
@@ -74,6 +307,9 @@ impl Config {
             ));
         }
         
+        self.monitor.validate()?;
+        self.scheduler.validate()?;
+        
         Ok(())
     }
 }
@@ -88,6 +324,8 @@ mod tests {
             bot_token: "123456789:ABCdefGHIjklMNOpqrsTUVwxyz".to_string(),
             chat_id: 123456789,
             check_interval: 300,
+            monitor: MonitorConfig::default(),
+            scheduler: SchedulerConfig::default(),
         };
         
         assert!(config.validate().is_ok());
@@ -99,6 +337,8 @@ mod tests {
             bot_token: "".to_string(),
             chat_id: 123456789,
             check_interval: 300,
+            monitor: MonitorConfig::default(),
+            scheduler: SchedulerConfig::default(),
         };
         
         assert!(config.validate().is_err());
@@ -110,6 +350,8 @@ mod tests {
             bot_token: "123456789:ABCdefGHIjklMNOpqrsTUVwxyz".to_string(),
             chat_id: 0,
             check_interval: 300,
+            monitor: MonitorConfig::default(),
+            scheduler: SchedulerConfig::default(),
         };
         
         assert!(config.validate().is_err());
@@ -121,8 +363,61 @@ mod tests {
             bot_token: "123456789:ABCdefGHIjklMNOpqrsTUVwxyz".to_string(),
             chat_id: 123456789,
             check_interval: 30,
+            monitor: MonitorConfig::default(),
+            scheduler: SchedulerConfig::default(),
         };
         
         assert!(config.validate().is_err());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_monitor_config_default_is_valid() {
+        assert!(MonitorConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_monitor_config_invalid_threshold() {
+        let monitor = MonitorConfig {
+            cpu_threshold: 120.0,
+            ..MonitorConfig::default()
+        };
+        assert!(monitor.validate().is_err());
+
+        let monitor = MonitorConfig {
+            disk_threshold: 0.0,
+            ..MonitorConfig::default()
+        };
+        assert!(monitor.validate().is_err());
+    }
+
+    #[test]
+    fn test_monitor_config_invalid_hysteresis() {
+        let monitor = MonitorConfig {
+            sustained_checks: 0,
+            ..MonitorConfig::default()
+        };
+        assert!(monitor.validate().is_err());
+
+        let monitor = MonitorConfig {
+            memory_threshold: 50.0,
+            recovery_margin: 60.0,
+            ..MonitorConfig::default()
+        };
+        assert!(monitor.validate().is_err());
+    }
+
+    #[test]
+    fn test_monitor_config_invalid_traffic_reset_day() {
+        let monitor = MonitorConfig {
+            traffic_reset_day: 31,
+            ..MonitorConfig::default()
+        };
+        assert!(monitor.validate().is_err());
+
+        let monitor = MonitorConfig {
+            traffic_reset_day: 0,
+            ..MonitorConfig::default()
+        };
+        assert!(monitor.validate().is_err());
+    }
+}
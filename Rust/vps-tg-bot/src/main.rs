@@ -6,6 +6,7 @@ use std::io;
 
 mod bot;
 mod config;
+mod monitor;
 mod scheduler;
 mod system;
 
@@ -233,7 +234,16 @@ async fn run_bot() -> Result<()> {
     let bot_instance = teloxide::Bot::new(config.bot_token.clone());
     let config_for_scheduler = config.clone();
 
-    // 首先启动调度器
+    // 初始化维护历史管理器，调度器补偿执行错过的任务时会写入历史
+    info!("📜 初始化维护历史管理器...");
+    let history_result = scheduler::maintenance_history::init_maintenance_history(&config.scheduler.state_dir).await;
+    if let Err(e) = history_result {
+        error!("❌ 维护历史管理器初始化失败: {:?}", e);
+        return Err(anyhow::anyhow!("维护历史管理器初始化失败"));
+    }
+    info!("✅ 维护历史管理器初始化成功");
+
+    // 启动调度器
     info!("⏰ 初始化调度器...");
     let scheduler_result = scheduler::start_scheduler(config_for_scheduler.clone(), bot_instance.clone()).await;
     if let Err(e) = scheduler_result {
@@ -242,14 +252,8 @@ async fn run_bot() -> Result<()> {
     }
     info!("✅ 调度器初始化成功");
 
-    // 初始化维护历史管理器
-    info!("📜 初始化维护历史管理器...");
-    let history_result = scheduler::maintenance_history::init_maintenance_history().await;
-    if let Err(e) = history_result {
-        error!("❌ 维护历史管理器初始化失败: {:?}", e);
-        return Err(anyhow::anyhow!("维护历史管理器初始化失败"));
-    }
-    info!("✅ 维护历史管理器初始化成功");
+    // 启动资源监控
+    monitor::start_monitor(config.clone(), bot_instance.clone());
 
     // 启动后台任务保持调度器运行
     let _scheduler_config = config.clone();
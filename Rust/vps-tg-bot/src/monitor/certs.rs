@@ -0,0 +1,348 @@
+//! TLS 证书到期监控
+//!
+//! 解析配置路径以及 Xray/Sing-box 配置中引用的 X.509 证书，
+//! 在剩余天数跨过各告警档位（默认 14/7/1 天）时按不同级别通知
+
+use crate::config::types::MonitorConfig;
+use crate::config::Config;
+use crate::system::proxy_config;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use log::{info, warn};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::Duration;
+use teloxide::prelude::*;
+use x509_parser::extensions::GeneralName;
+use x509_parser::pem::Pem;
+use x509_parser::prelude::*;
+
+/// 证书检查间隔
+const CERT_CHECK_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// 证书摘要信息
+#[derive(Debug, Clone, PartialEq)]
+pub struct CertificateInfo {
+    pub path: String,
+    pub subject: String,
+    pub sans: Vec<String>,
+    pub not_after: DateTime<Utc>,
+}
+
+impl CertificateInfo {
+    /// 剩余天数（向下取整，已过期时为负数）
+    pub fn days_remaining(&self, now: DateTime<Utc>) -> i64 {
+        (self.not_after - now).num_seconds().div_euclid(86400)
+    }
+}
+
+/// 解析 PEM 或 DER 格式证书，证书链只取第一张（叶子证书）
+pub fn parse_certificate(path: &str, data: &[u8]) -> Result<CertificateInfo> {
+    if let Some(pem) = Pem::iter_from_buffer(data).next() {
+        let pem = pem.map_err(|e| anyhow!("PEM 解析失败: {}", e))?;
+        let cert = pem.parse_x509().map_err(|e| anyhow!("证书解析失败: {}", e))?;
+        return certificate_info(path, &cert);
+    }
+
+    let (_, cert) = parse_x509_certificate(data).map_err(|e| anyhow!("证书解析失败: {}", e))?;
+    certificate_info(path, &cert)
+}
+
+fn certificate_info(path: &str, cert: &X509Certificate) -> Result<CertificateInfo> {
+    let not_after = Utc
+        .timestamp_opt(cert.validity().not_after.timestamp(), 0)
+        .single()
+        .ok_or_else(|| anyhow!("证书到期时间无效"))?;
+
+    let mut sans = Vec::new();
+    if let Ok(Some(extension)) = cert.subject_alternative_name() {
+        for name in &extension.value.general_names {
+            match name {
+                GeneralName::DNSName(dns) => sans.push(dns.to_string()),
+                GeneralName::IPAddress(bytes) => {
+                    let ip = match bytes.len() {
+                        4 => <[u8; 4]>::try_from(*bytes).ok().map(IpAddr::from),
+                        16 => <[u8; 16]>::try_from(*bytes).ok().map(IpAddr::from),
+                        _ => None,
+                    };
+                    if let Some(ip) = ip {
+                        sans.push(ip.to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(CertificateInfo {
+        path: path.to_string(),
+        subject: cert.subject().to_string(),
+        sans,
+        not_after,
+    })
+}
+
+/// 读取并解析证书文件
+pub fn load_certificate(path: &str) -> Result<CertificateInfo> {
+    let data = std::fs::read(path).map_err(|e| anyhow!("无法读取 {}: {}", path, e))?;
+    parse_certificate(path, &data)
+}
+
+/// 汇总需要检查的证书路径：配置的路径 + 代理配置中发现的路径
+pub fn certificate_paths(config: &MonitorConfig) -> Vec<String> {
+    let mut paths = config.cert_paths.clone();
+    paths.extend(proxy_config::discover_certificate_paths());
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+/// 加载所有证书，返回 (路径, 解析结果)
+pub fn collect_certificates(config: &MonitorConfig) -> Vec<(String, Result<CertificateInfo>)> {
+    certificate_paths(config)
+        .into_iter()
+        .map(|path| {
+            let result = load_certificate(&path);
+            (path, result)
+        })
+        .collect()
+}
+
+/// 证书到期告警跟踪器
+#[derive(Debug)]
+pub struct CertExpiryTracker {
+    /// 告警档位（天），从大到小排列
+    alert_days: Vec<u32>,
+    /// 每个证书最近一次通知的 (到期时间, 档位)，证书续期后重新开始
+    notified: HashMap<String, (DateTime<Utc>, u32)>,
+}
+
+impl CertExpiryTracker {
+    pub fn new(config: &MonitorConfig) -> Self {
+        let mut alert_days = config.cert_alert_days.clone();
+        alert_days.sort_unstable_by(|a, b| b.cmp(a));
+        alert_days.dedup();
+        Self {
+            alert_days,
+            notified: HashMap::new(),
+        }
+    }
+
+    /// 评估证书剩余天数，进入新的告警档位时返回通知消息
+    pub fn evaluate(&mut self, cert: &CertificateInfo, now: DateTime<Utc>) -> Option<String> {
+        let days = cert.days_remaining(now);
+
+        // 已过期视为档位 0
+        let level = if days < 0 {
+            0
+        } else {
+            *self.alert_days.iter().rev().find(|&&d| days <= d as i64)?
+        };
+
+        if let Some((not_after, last_level)) = self.notified.get(&cert.path) {
+            if *not_after == cert.not_after && *last_level <= level {
+                return None;
+            }
+        }
+        self.notified.insert(cert.path.clone(), (cert.not_after, level));
+
+        let (icon, severity) = self.severity(level);
+        if days < 0 {
+            Some(format!(
+                "{} 证书已过期: {}\n主题: {}\n过期时间: {}",
+                icon,
+                cert.path,
+                cert.subject,
+                cert.not_after.format("%Y-%m-%d %H:%M UTC")
+            ))
+        } else {
+            Some(format!(
+                "{} 证书即将到期 [{}]: {}\n主题: {}\n剩余 {} 天 (到期时间 {})",
+                icon,
+                severity,
+                cert.path,
+                cert.subject,
+                days,
+                cert.not_after.format("%Y-%m-%d %H:%M UTC")
+            ))
+        }
+    }
+
+    /// 档位越小级别越高：最小档位为紧急，最大档位为提醒
+    fn severity(&self, level: u32) -> (&'static str, &'static str) {
+        let smallest = self.alert_days.last().copied().unwrap_or(0);
+        let largest = self.alert_days.first().copied().unwrap_or(0);
+        if level <= smallest {
+            ("🚨", "紧急")
+        } else if level >= largest {
+            ("🔔", "提醒")
+        } else {
+            ("⚠️", "警告")
+        }
+    }
+}
+
+/// 格式化 /certs 命令的证书列表
+pub fn format_certificate_list(certs: &[(String, Result<CertificateInfo>)], now: DateTime<Utc>) -> String {
+    if certs.is_empty() {
+        return "🔐 未找到任何证书\n\n可通过 CERT_PATHS 环境变量配置证书路径，或在 Xray/Sing-box 配置中引用证书".to_string();
+    }
+
+    let mut message = String::from("🔐 TLS 证书状态:\n");
+    for (path, result) in certs {
+        message.push_str(&format!("\n📄 {}\n", path));
+        match result {
+            Ok(cert) => {
+                let days = cert.days_remaining(now);
+                let icon = if days < 0 {
+                    "❌"
+                } else if days <= 7 {
+                    "⚠️"
+                } else {
+                    "✅"
+                };
+                message.push_str(&format!("   主题: {}\n", cert.subject));
+                if !cert.sans.is_empty() {
+                    message.push_str(&format!("   SAN: {}\n", cert.sans.join(", ")));
+                }
+                message.push_str(&format!(
+                    "   {} 到期: {} (剩余 {} 天)\n",
+                    icon,
+                    cert.not_after.format("%Y-%m-%d"),
+                    days
+                ));
+            }
+            Err(e) => message.push_str(&format!("   ❌ {}\n", e)),
+        }
+    }
+    message
+}
+
+/// 启动证书到期监控任务
+pub fn start_cert_monitor(config: Config, bot: Bot) {
+    info!("🔐 启动证书到期监控 (告警档位: {:?} 天)", config.monitor.cert_alert_days);
+
+    tokio::spawn(async move {
+        let mut tracker = CertExpiryTracker::new(&config.monitor);
+        let mut ticker = tokio::time::interval(CERT_CHECK_INTERVAL);
+
+        loop {
+            ticker.tick().await;
+
+            let monitor_config = config.monitor.clone();
+            let certs = match tokio::task::spawn_blocking(move || collect_certificates(&monitor_config)).await {
+                Ok(certs) => certs,
+                Err(e) => {
+                    warn!("⚠️  证书检查任务异常: {}", e);
+                    continue;
+                }
+            };
+
+            let now = Utc::now();
+            for (path, result) in certs {
+                let cert = match result {
+                    Ok(cert) => cert,
+                    Err(e) => {
+                        warn!("⚠️  证书 {} 检查失败: {}", path, e);
+                        continue;
+                    }
+                };
+                if let Some(message) = tracker.evaluate(&cert, now) {
+                    info!("{}", message);
+                    if let Err(e) = bot.send_message(ChatId(config.chat_id), message).await {
+                        warn!("发送证书告警失败: {}", e);
+                    }
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE_PEM: &[u8] = include_bytes!("../../tests/fixtures/certs/example.pem");
+
+    fn example_cert() -> CertificateInfo {
+        parse_certificate("/etc/ssl/example.pem", EXAMPLE_PEM).unwrap()
+    }
+
+    fn tracker() -> CertExpiryTracker {
+        CertExpiryTracker::new(&MonitorConfig {
+            cert_alert_days: vec![1, 14, 7],
+            ..MonitorConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_parse_pem_certificate() {
+        let cert = example_cert();
+
+        assert!(cert.subject.contains("CN=example.com"));
+        assert_eq!(cert.sans, vec!["example.com", "www.example.com", "203.0.113.10"]);
+        assert_eq!(cert.days_remaining(cert.not_after - chrono::Duration::days(10)), 10);
+        assert!(cert.days_remaining(cert.not_after + chrono::Duration::hours(1)) < 0);
+    }
+
+    #[test]
+    fn test_parse_invalid_certificate() {
+        assert!(parse_certificate("bad.pem", b"not a certificate").is_err());
+    }
+
+    #[test]
+    fn test_alert_levels_escalate_once() {
+        let cert = example_cert();
+        let mut tracker = tracker();
+        let at = |days: i64| cert.not_after - chrono::Duration::days(days) - chrono::Duration::hours(1);
+
+        assert!(tracker.evaluate(&cert, at(30)).is_none());
+
+        let message = tracker.evaluate(&cert, at(13)).unwrap();
+        assert!(message.contains("🔔"));
+        assert!(message.contains("剩余 13 天"));
+        assert!(tracker.evaluate(&cert, at(12)).is_none());
+
+        assert!(tracker.evaluate(&cert, at(6)).unwrap().contains("[警告]"));
+        assert!(tracker.evaluate(&cert, at(0)).unwrap().contains("[紧急]"));
+        assert!(tracker.evaluate(&cert, at(0)).is_none());
+
+        let expired = tracker.evaluate(&cert, cert.not_after + chrono::Duration::hours(2)).unwrap();
+        assert!(expired.contains("证书已过期"));
+    }
+
+    #[test]
+    fn test_renewed_certificate_resets_alerts() {
+        let cert = example_cert();
+        let mut tracker = tracker();
+        let now = cert.not_after - chrono::Duration::days(3);
+        assert!(tracker.evaluate(&cert, now).is_some());
+
+        let renewed = CertificateInfo {
+            not_after: cert.not_after + chrono::Duration::days(87),
+            ..cert.clone()
+        };
+        assert!(tracker.evaluate(&renewed, now).is_none());
+
+        let later = renewed.not_after - chrono::Duration::days(10);
+        assert!(tracker.evaluate(&renewed, later).is_some());
+    }
+
+    #[test]
+    fn test_format_certificate_list() {
+        let cert = example_cert();
+        let now = cert.not_after - chrono::Duration::days(20) - chrono::Duration::hours(1);
+        let certs = vec![
+            (cert.path.clone(), Ok(cert)),
+            ("/missing.pem".to_string(), Err(anyhow!("无法读取 /missing.pem"))),
+        ];
+
+        let message = format_certificate_list(&certs, now);
+        assert!(message.contains("📄 /etc/ssl/example.pem"));
+        assert!(message.contains("SAN: example.com, www.example.com, 203.0.113.10"));
+        assert!(message.contains("(剩余 20 天)"));
+        assert!(message.contains("❌ 无法读取 /missing.pem"));
+
+        assert!(format_certificate_list(&[], now).contains("未找到任何证书"));
+    }
+}
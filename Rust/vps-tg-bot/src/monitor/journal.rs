@@ -0,0 +1,50 @@
+//! systemd journal 输出解析
+//!
+//! 解析 `journalctl -o json` 的逐行 JSON 输出，供 SSH 登录与内核事件监控共用
+
+use chrono::{DateTime, TimeZone, Utc};
+
+/// journal 中的一条记录
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    pub cursor: String,
+    pub message: String,
+    pub timestamp: Option<DateTime<Utc>>,
+}
+
+/// 解析 `journalctl -o json` 输出
+pub fn parse_journal_output(output: &str) -> Vec<JournalEntry> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let value: serde_json::Value = serde_json::from_str(line).ok()?;
+            let cursor = value.get("__CURSOR")?.as_str()?.to_string();
+            // 含非 UTF-8 字节的消息会以字节数组形式输出，直接忽略
+            let message = value.get("MESSAGE").and_then(|m| m.as_str()).unwrap_or_default().to_string();
+            let timestamp = value
+                .get("__REALTIME_TIMESTAMP")
+                .and_then(|t| t.as_str())
+                .and_then(|t| t.parse::<i64>().ok())
+                .and_then(|micros| Utc.timestamp_micros(micros).single());
+            Some(JournalEntry { cursor, message, timestamp })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_journal_output() {
+        let output = r#"{"__CURSOR":"s=abc;i=1","__REALTIME_TIMESTAMP":"1700000000000000","MESSAGE":"Accepted publickey for root from 203.0.113.5 port 1 ssh2"}
+{"__CURSOR":"s=abc;i=2","MESSAGE":[1,2,3]}
+not json"#;
+        let entries = parse_journal_output(output);
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].timestamp, Utc.timestamp_opt(1_700_000_000, 0).single());
+        assert_eq!(entries[1].cursor, "s=abc;i=2");
+        assert_eq!(entries[1].message, "");
+    }
+}
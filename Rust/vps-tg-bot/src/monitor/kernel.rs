@@ -0,0 +1,387 @@
+//! 内核严重事件告警
+//!
+//! 跟踪 journal 中的内核日志 (`_TRANSPORT=kernel`)，匹配 OOM 杀进程、
+//! 文件系统错误转为只读、段错误、任务挂起等事件，按类别推送告警。
+//! 匹配规则可通过 KERNEL_PATTERNS 扩展
+
+use super::journal::parse_journal_output;
+use crate::config::Config;
+use crate::scheduler::storage;
+use crate::system;
+use crate::system::procfs;
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use log::{info, warn};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use teloxide::prelude::*;
+
+/// 读取位置状态文件名，保存在状态目录中
+pub const KERNEL_STATE_FILE: &str = "kernel_watch_state.json";
+
+/// 日志轮询间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+/// 同一类别、同一对象的告警冷却时间，避免刷屏
+const ALERT_COOLDOWN: Duration = Duration::from_secs(600);
+
+/// 告警中保留的原始日志长度
+const MAX_RAW_LINE_CHARS: usize = 300;
+
+/// 内核事件类别
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum KernelEventKind {
+    Oom,
+    FilesystemError,
+    Segfault,
+    HungTask,
+    /// 用户自定义类别
+    Custom(String),
+}
+
+impl KernelEventKind {
+    /// 从规则中的类别名解析
+    pub fn from_name(name: &str) -> Self {
+        match name.trim().to_lowercase().as_str() {
+            "oom" => KernelEventKind::Oom,
+            "fs" | "filesystem" => KernelEventKind::FilesystemError,
+            "segfault" => KernelEventKind::Segfault,
+            "hung" | "hung_task" => KernelEventKind::HungTask,
+            _ => KernelEventKind::Custom(name.trim().to_string()),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            KernelEventKind::Oom => "💥 OOM 杀进程".to_string(),
+            KernelEventKind::FilesystemError => "💾 文件系统错误".to_string(),
+            KernelEventKind::Segfault => "🐞 段错误".to_string(),
+            KernelEventKind::HungTask => "⏳ 任务挂起".to_string(),
+            KernelEventKind::Custom(name) => format!("📌 {}", name),
+        }
+    }
+}
+
+/// 一条内核日志匹配规则
+///
+/// 正则可使用命名分组 `process`、`pid`、`device`、`detail` 提取告警字段
+#[derive(Debug, Clone)]
+pub struct KernelPattern {
+    pub kind: KernelEventKind,
+    pub regex: Regex,
+}
+
+impl KernelPattern {
+    pub fn new(kind: KernelEventKind, pattern: &str) -> Result<Self> {
+        Ok(Self {
+            kind,
+            regex: Regex::new(pattern)?,
+        })
+    }
+
+    /// 解析 `类别=正则` 格式的规则
+    pub fn parse(rule: &str) -> Result<Self> {
+        let (category, pattern) = rule
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("规则格式应为 类别=正则: {}", rule))?;
+        Self::new(KernelEventKind::from_name(category), pattern.trim())
+    }
+}
+
+/// 内置匹配规则
+pub fn default_patterns() -> Vec<KernelPattern> {
+    [
+        (
+            KernelEventKind::Oom,
+            r"(?i)out of memory: Killed process (?P<pid>\d+) \((?P<process>[^)]+)\)(?:.*?(?P<detail>anon-rss:\d+kB))?",
+        ),
+        (
+            KernelEventKind::FilesystemError,
+            r"(?:EXT[234]-fs|XFS|BTRFS)(?: error)? \((?:device )?(?P<device>[^)]+)\).*?(?P<detail>Remounting filesystem read-only|shutting down filesystem|forced readonly|read-only)",
+        ),
+        (
+            KernelEventKind::Segfault,
+            r"(?P<process>\S+)\[(?P<pid>\d+)\]: segfault at (?P<detail>\S+)",
+        ),
+        (
+            KernelEventKind::HungTask,
+            r"task (?P<process>.+?):(?P<pid>\d+) blocked for more than (?P<detail>\d+ seconds)",
+        ),
+    ]
+    .into_iter()
+    .map(|(kind, pattern)| KernelPattern::new(kind, pattern).expect("内置内核日志规则无效"))
+    .collect()
+}
+
+/// 构建完整规则列表：自定义规则优先，其次内置规则
+pub fn build_patterns(custom_rules: &[String]) -> Vec<KernelPattern> {
+    let mut patterns: Vec<KernelPattern> = custom_rules
+        .iter()
+        .filter_map(|rule| match KernelPattern::parse(rule) {
+            Ok(pattern) => Some(pattern),
+            Err(e) => {
+                warn!("⚠️  忽略无效的内核日志规则: {}", e);
+                None
+            }
+        })
+        .collect();
+    patterns.extend(default_patterns());
+    patterns
+}
+
+/// 匹配到的内核事件
+#[derive(Debug, Clone, PartialEq)]
+pub struct KernelEvent {
+    pub kind: KernelEventKind,
+    pub process: Option<String>,
+    pub pid: Option<u32>,
+    pub device: Option<String>,
+    pub detail: Option<String>,
+    pub raw: String,
+}
+
+impl KernelEvent {
+    /// 冷却判断使用的键：同类别同对象视为同一事件
+    fn dedup_key(&self) -> String {
+        format!(
+            "{:?}|{}",
+            self.kind,
+            self.device.as_deref().or(self.process.as_deref()).unwrap_or_default()
+        )
+    }
+}
+
+/// 用规则匹配一行内核日志，返回第一个命中的事件
+pub fn match_kernel_line(line: &str, patterns: &[KernelPattern]) -> Option<KernelEvent> {
+    patterns.iter().find_map(|pattern| {
+        let captures = pattern.regex.captures(line)?;
+        let group = |name: &str| captures.name(name).map(|m| m.as_str().to_string());
+        Some(KernelEvent {
+            kind: pattern.kind.clone(),
+            process: group("process"),
+            pid: group("pid").and_then(|pid| pid.parse().ok()),
+            device: group("device"),
+            detail: group("detail"),
+            raw: line.to_string(),
+        })
+    })
+}
+
+/// 格式化内核事件告警，附带当前内存状态
+pub fn format_kernel_alert(event: &KernelEvent, time: DateTime<Local>, memory: (u64, u64)) -> String {
+    let mut message = format!("🚨 内核事件: {}\n", event.kind.label());
+
+    if let Some(process) = &event.process {
+        match event.pid {
+            Some(pid) => message.push_str(&format!("🔪 进程: {} (PID {})\n", process, pid)),
+            None => message.push_str(&format!("🔪 进程: {}\n", process)),
+        }
+    }
+    if let Some(device) = &event.device {
+        message.push_str(&format!("💽 设备: {}\n", device));
+    }
+    if let Some(detail) = &event.detail {
+        message.push_str(&format!("📋 详情: {}\n", detail));
+    }
+
+    let (used, total) = memory;
+    if total > 0 {
+        message.push_str(&format!(
+            "🧠 当前内存: {} MB / {} MB ({:.1}%)\n",
+            used / 1024 / 1024,
+            total / 1024 / 1024,
+            used as f64 / total as f64 * 100.0
+        ));
+    }
+    message.push_str(&format!("🕐 时间: {}\n", time.format("%Y-%m-%d %H:%M:%S")));
+
+    let raw: String = event.raw.chars().take(MAX_RAW_LINE_CHARS).collect();
+    message.push_str(&format!("📝 原始日志: {}", raw));
+    message
+}
+
+/// 持久化的 journal 游标
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct KernelWatchState {
+    #[serde(default)]
+    pub journal_cursor: Option<String>,
+}
+
+impl KernelWatchState {
+    /// 加载状态文件，文件不存在或损坏时从头开始，损坏的文件会被隔离
+    pub fn load_from_file(path: &Path) -> Self {
+        storage::load_json_or_quarantine(path, "内核日志读取位置文件").unwrap_or_default()
+    }
+
+    pub fn save_to_file(&self, path: &Path) -> Result<()> {
+        storage::save_json(path, self)
+    }
+}
+
+/// 告警冷却跟踪
+#[derive(Debug, Default)]
+pub struct AlertCooldown {
+    last_sent: HashMap<String, Instant>,
+}
+
+impl AlertCooldown {
+    /// 事件是否应当推送（冷却期内的重复事件返回 false）
+    pub fn should_alert(&mut self, event: &KernelEvent, now: Instant) -> bool {
+        let key = event.dedup_key();
+        match self.last_sent.get(&key) {
+            Some(last) if now.duration_since(*last) < ALERT_COOLDOWN => false,
+            _ => {
+                self.last_sent.insert(key, now);
+                true
+            }
+        }
+    }
+}
+
+/// 启动内核事件监控任务
+pub fn start_kernel_monitor(config: Config, bot: Bot) {
+    if !config.monitor.kernel_watch {
+        return;
+    }
+
+    tokio::spawn(async move {
+        if let Err(e) = system::ops::get_kernel_journal(None).await {
+            warn!("⚠️  无法读取内核 journal，内核事件监控未启动: {}", e);
+            return;
+        }
+
+        let patterns = build_patterns(&config.monitor.kernel_patterns);
+        info!("🧯 启动内核事件监控 ({} 条匹配规则)", patterns.len());
+
+        let state_path = config.scheduler.state_dir.join(KERNEL_STATE_FILE);
+        storage::migrate_legacy_file(&state_path);
+        let mut state = KernelWatchState::load_from_file(&state_path);
+        let mut cooldown = AlertCooldown::default();
+        let mut ticker = tokio::time::interval(POLL_INTERVAL);
+
+        loop {
+            ticker.tick().await;
+
+            let first_run = state.journal_cursor.is_none();
+            let output = match system::ops::get_kernel_journal(state.journal_cursor.as_deref()).await {
+                Ok(output) => output,
+                Err(e) => {
+                    warn!("⚠️  {}", e);
+                    continue;
+                }
+            };
+
+            let entries = parse_journal_output(&output);
+            let Some(last) = entries.last() else {
+                continue;
+            };
+            state.journal_cursor = Some(last.cursor.clone());
+            if let Err(e) = state.save_to_file(&state_path) {
+                warn!("⚠️  保存内核日志读取位置失败: {}", e);
+            }
+            // 首次运行只记录游标，不回放历史日志
+            if first_run {
+                continue;
+            }
+
+            for entry in entries {
+                let Some(event) = match_kernel_line(&entry.message, &patterns) else {
+                    continue;
+                };
+                if !cooldown.should_alert(&event, Instant::now()) {
+                    continue;
+                }
+
+                let time = entry.timestamp.map(|t| t.with_timezone(&Local)).unwrap_or_else(Local::now);
+                let message = format_kernel_alert(&event, time, procfs::read_memory());
+                if let Err(e) = bot.send_message(ChatId(config.chat_id), message).await {
+                    warn!("发送内核事件告警失败: {}", e);
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn match_default(line: &str) -> Option<KernelEvent> {
+        match_kernel_line(line, &default_patterns())
+    }
+
+    #[test]
+    fn test_match_oom_kill() {
+        let event = match_default(
+            "Out of memory: Killed process 812 (xray) total-vm:1530264kB, anon-rss:402112kB, file-rss:0kB, shmem-rss:0kB, UID:0 pgtables:1024kB oom_score_adj:0",
+        )
+        .unwrap();
+        assert_eq!(event.kind, KernelEventKind::Oom);
+        assert_eq!(event.process.as_deref(), Some("xray"));
+        assert_eq!(event.pid, Some(812));
+        assert_eq!(event.detail.as_deref(), Some("anon-rss:402112kB"));
+
+        let cgroup = match_default("Memory cgroup out of memory: Killed process 99 (sing-box) total-vm:1kB").unwrap();
+        assert_eq!(cgroup.process.as_deref(), Some("sing-box"));
+    }
+
+    #[test]
+    fn test_match_filesystem_read_only() {
+        let event = match_default("EXT4-fs (vda1): Remounting filesystem read-only").unwrap();
+        assert_eq!(event.kind, KernelEventKind::FilesystemError);
+        assert_eq!(event.device.as_deref(), Some("vda1"));
+
+        let xfs = match_default("XFS (sdb1): Corruption detected. Unmount and run xfs_repair; shutting down filesystem");
+        assert_eq!(xfs.unwrap().detail.as_deref(), Some("shutting down filesystem"));
+    }
+
+    #[test]
+    fn test_match_segfault_and_hung_task() {
+        let segfault =
+            match_default("xray[4242]: segfault at 0 ip 000055d0c0ffee00 sp 00007ffd error 4 in xray[55d0c0000000+1000000]")
+                .unwrap();
+        assert_eq!(segfault.kind, KernelEventKind::Segfault);
+        assert_eq!(segfault.process.as_deref(), Some("xray"));
+        assert_eq!(segfault.pid, Some(4242));
+
+        let hung = match_default("INFO: task jbd2/vda1-8:231 blocked for more than 120 seconds.").unwrap();
+        assert_eq!(hung.kind, KernelEventKind::HungTask);
+        assert_eq!(hung.process.as_deref(), Some("jbd2/vda1-8"));
+        assert_eq!(hung.detail.as_deref(), Some("120 seconds"));
+
+        assert!(match_default("e1000: eth0 NIC Link is Up 1000 Mbps Full Duplex").is_none());
+    }
+
+    #[test]
+    fn test_custom_patterns_take_priority() {
+        let patterns = build_patterns(&[
+            "NVMe 超时=nvme(?P<device>\\d+): I/O \\d+ QID \\d+ timeout".to_string(),
+            "missing separator".to_string(),
+        ]);
+        assert_eq!(patterns.len(), default_patterns().len() + 1);
+
+        let event = match_kernel_line("nvme0: I/O 12 QID 3 timeout, aborting", &patterns).unwrap();
+        assert_eq!(event.kind, KernelEventKind::Custom("NVMe 超时".to_string()));
+        assert_eq!(event.device.as_deref(), Some("0"));
+        assert_eq!(KernelEventKind::from_name("OOM"), KernelEventKind::Oom);
+    }
+
+    #[test]
+    fn test_alert_cooldown_and_format() {
+        let event = match_default("Out of memory: Killed process 812 (xray) anon-rss:1024kB").unwrap();
+        let mut cooldown = AlertCooldown::default();
+        let now = Instant::now();
+
+        assert!(cooldown.should_alert(&event, now));
+        assert!(!cooldown.should_alert(&event, now + Duration::from_secs(60)));
+        assert!(cooldown.should_alert(&event, now + ALERT_COOLDOWN));
+
+        let message = format_kernel_alert(&event, Local::now(), (512 * 1024 * 1024, 1024 * 1024 * 1024));
+        assert!(message.contains("OOM 杀进程"));
+        assert!(message.contains("xray (PID 812)"));
+        assert!(message.contains("512 MB / 1024 MB (50.0%)"));
+    }
+}
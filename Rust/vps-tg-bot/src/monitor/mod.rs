@@ -0,0 +1,95 @@
+//! 后台监控模块
+//!
+//! 按 CHECK_INTERVAL 周期采样系统状态，并在资源使用持续超限或恢复、
+//! 流量接近配额、代理入站端口未监听、受监控的服务异常、TLS 证书即将到期、
+//! 有 SSH 登录、内核报告严重事件时推送 Telegram 通知
+
+pub mod certs;
+pub mod journal;
+pub mod kernel;
+pub mod ports;
+pub mod resource;
+pub mod ssh;
+pub mod traffic;
+pub mod units;
+
+use crate::config::Config;
+use crate::scheduler::storage;
+use crate::system;
+use log::{debug, error, info, warn};
+use std::time::Duration;
+use teloxide::prelude::*;
+
+/// 启动后台监控任务
+pub fn start_monitor(config: Config, bot: Bot) {
+    units::start_unit_watcher(config.clone(), bot.clone());
+    certs::start_cert_monitor(config.clone(), bot.clone());
+    ssh::start_ssh_monitor(config.clone(), bot.clone());
+    kernel::start_kernel_monitor(config.clone(), bot.clone());
+
+    let interval_secs = config.check_interval.max(1);
+    info!(
+        "📡 启动资源监控 (间隔 {} 秒, CPU {}%, 内存 {}%, 磁盘 {}%)",
+        interval_secs,
+        config.monitor.cpu_threshold,
+        config.monitor.memory_threshold,
+        config.monitor.disk_threshold
+    );
+
+    tokio::spawn(async move {
+        let mut resource_monitor = resource::ResourceMonitor::new(&config.monitor);
+        let mut port_watcher = ports::PortWatcher::new();
+        let traffic_path = config.scheduler.state_dir.join(traffic::TRAFFIC_STATE_FILE);
+        storage::migrate_legacy_file(&traffic_path);
+        let traffic_state = traffic::TrafficState::load_from_file(&traffic_path);
+        let mut traffic_accountant =
+            traffic::TrafficAccountant::new(&config.monitor, traffic_state, chrono::Local::now().date_naive());
+        let mut ticker = tokio::time::interval(Duration::from_secs(interval_secs));
+
+        loop {
+            ticker.tick().await;
+
+            let status = match tokio::task::spawn_blocking(system::get_system_status).await {
+                Ok(Ok(status)) => status,
+                Ok(Err(e)) => {
+                    warn!("⚠️  获取系统状态失败: {}", e);
+                    continue;
+                }
+                Err(e) => {
+                    error!("❌ 系统状态采样任务异常: {}", e);
+                    continue;
+                }
+            };
+
+            debug!(
+                "资源采样: CPU {:.1}%, 内存 {}/{}, 磁盘 {}/{}",
+                status.cpu_usage, status.memory_used, status.memory_total, status.disk_used, status.disk_total
+            );
+
+            let counters = system::netdev::read_interface_counters();
+            let mut messages = traffic_accountant.update(
+                &counters,
+                &traffic::read_boot_id(),
+                chrono::Local::now().date_naive(),
+            );
+            if let Err(e) = traffic_accountant.state().save_to_file(&traffic_path) {
+                warn!("⚠️  保存流量统计失败: {}", e);
+            }
+
+            messages.extend(resource_monitor.evaluate(&status));
+
+            if config.monitor.port_check {
+                let inbounds = system::proxy_config::discover_public_inbounds();
+                let tcp_ports = system::sockets::read_public_listening_ports(system::sockets::Transport::Tcp);
+                let udp_ports = system::sockets::read_public_listening_ports(system::sockets::Transport::Udp);
+                messages.extend(port_watcher.evaluate(&inbounds, &tcp_ports, &udp_ports));
+            }
+            for message in messages {
+                info!("{}", message);
+                if let Err(e) = bot.send_message(ChatId(config.chat_id), message).await {
+                    warn!("发送监控通知失败: {}", e);
+                }
+            }
+        }
+    });
+}
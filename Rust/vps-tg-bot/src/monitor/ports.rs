@@ -0,0 +1,99 @@
+//! 代理入站端口监听检查
+//!
+//! 每次监控采样时从 Xray / Sing-box 配置中提取对外开放的入站端口，
+//! 与 /proc/net 中的监听套接字比对，发现某个入站没有监听时立即告警
+
+use crate::system::proxy_config::InboundPort;
+use crate::system::sockets::Transport;
+use std::collections::HashSet;
+
+/// 入站端口检查器，记录当前处于未监听状态的入站
+#[derive(Debug, Default)]
+pub struct PortWatcher {
+    missing: HashSet<InboundPort>,
+}
+
+impl PortWatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 比对配置的入站与实际监听端口，返回新出现的告警与恢复消息
+    pub fn evaluate(
+        &mut self,
+        inbounds: &[InboundPort],
+        tcp_ports: &HashSet<u16>,
+        udp_ports: &HashSet<u16>,
+    ) -> Vec<String> {
+        let mut messages = Vec::new();
+        let mut still_missing = HashSet::new();
+
+        for inbound in inbounds {
+            let listening = match inbound.transport {
+                Transport::Tcp => tcp_ports.contains(&inbound.port),
+                Transport::Udp => udp_ports.contains(&inbound.port),
+            };
+
+            if listening {
+                if self.missing.contains(inbound) {
+                    messages.push(format!("✅ 入站端口已恢复监听: {}", inbound.describe()));
+                }
+            } else {
+                if !self.missing.contains(inbound) {
+                    messages.push(format!(
+                        "🚨 入站端口未监听: {} 没有对应的监听套接字，请检查服务是否正常运行",
+                        inbound.describe()
+                    ));
+                }
+                still_missing.insert(inbound.clone());
+            }
+        }
+
+        // 已从配置中移除的入站不再跟踪
+        self.missing = still_missing;
+        messages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::proxy_config::ProxyKind;
+
+    fn inbound(tag: &str, port: u16, transport: Transport) -> InboundPort {
+        InboundPort {
+            kind: ProxyKind::Xray,
+            tag: Some(tag.to_string()),
+            port,
+            transport,
+        }
+    }
+
+    #[test]
+    fn test_missing_listener_alerts_once_and_recovers() {
+        let mut watcher = PortWatcher::new();
+        let inbounds = vec![inbound("vless-in", 443, Transport::Tcp), inbound("ws", 8080, Transport::Tcp)];
+        let udp = HashSet::new();
+
+        assert!(watcher.evaluate(&inbounds, &HashSet::from([443, 8080]), &udp).is_empty());
+
+        let messages = watcher.evaluate(&inbounds, &HashSet::from([8080]), &udp);
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].contains("vless-in (443/tcp)"));
+
+        assert!(watcher.evaluate(&inbounds, &HashSet::from([8080]), &udp).is_empty());
+
+        let messages = watcher.evaluate(&inbounds, &HashSet::from([443, 8080]), &udp);
+        assert!(messages[0].contains("✅"));
+    }
+
+    #[test]
+    fn test_udp_inbound_checks_udp_sockets() {
+        let mut watcher = PortWatcher::new();
+        let inbounds = vec![inbound("hy2", 8443, Transport::Udp)];
+
+        // 只有 TCP 监听不能满足 UDP 入站
+        assert_eq!(watcher.evaluate(&inbounds, &HashSet::from([8443]), &HashSet::new()).len(), 1);
+        assert!(watcher.evaluate(&inbounds, &HashSet::new(), &HashSet::from([8443]))[0].contains("✅"));
+    }
+}
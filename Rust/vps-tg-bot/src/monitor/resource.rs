@@ -0,0 +1,269 @@
+//! 资源阈值告警
+//!
+//! 对 CPU、内存、磁盘使用率做带滞后的阈值判断：
+//! 连续 N 次采样超过阈值才告警，回落到 阈值 - 滞后量 以下连续 N 次才恢复
+
+use crate::config::types::MonitorConfig;
+use crate::system::SystemStatus;
+use std::collections::HashMap;
+
+/// 阈值状态变化
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlertTransition {
+    /// 持续超过阈值，进入告警状态
+    Triggered,
+    /// 回落到恢复线以下，退出告警状态
+    Recovered,
+}
+
+/// 单个指标的阈值跟踪器
+#[derive(Debug, Clone)]
+pub struct ThresholdTracker {
+    threshold: f32,
+    recovery_margin: f32,
+    sustained_checks: u32,
+    above_count: u32,
+    below_count: u32,
+    alerting: bool,
+}
+
+impl ThresholdTracker {
+    pub fn new(threshold: f32, recovery_margin: f32, sustained_checks: u32) -> Self {
+        Self {
+            threshold,
+            recovery_margin,
+            sustained_checks: sustained_checks.max(1),
+            above_count: 0,
+            below_count: 0,
+            alerting: false,
+        }
+    }
+
+    /// 记录一次采样值，状态发生变化时返回对应的变化
+    pub fn observe(&mut self, value: f32) -> Option<AlertTransition> {
+        if self.alerting {
+            if value < self.threshold - self.recovery_margin {
+                self.below_count += 1;
+                if self.below_count >= self.sustained_checks {
+                    self.alerting = false;
+                    self.below_count = 0;
+                    return Some(AlertTransition::Recovered);
+                }
+            } else {
+                self.below_count = 0;
+            }
+        } else if value >= self.threshold {
+            self.above_count += 1;
+            if self.above_count >= self.sustained_checks {
+                self.alerting = true;
+                self.above_count = 0;
+                return Some(AlertTransition::Triggered);
+            }
+        } else {
+            self.above_count = 0;
+        }
+
+        None
+    }
+
+    #[allow(dead_code)]
+    pub fn is_alerting(&self) -> bool {
+        self.alerting
+    }
+
+    pub fn threshold(&self) -> f32 {
+        self.threshold
+    }
+}
+
+/// 资源监控器，保存每个指标的跟踪状态
+#[derive(Debug)]
+pub struct ResourceMonitor {
+    config: MonitorConfig,
+    trackers: HashMap<String, ThresholdTracker>,
+}
+
+impl ResourceMonitor {
+    pub fn new(config: &MonitorConfig) -> Self {
+        Self {
+            config: config.clone(),
+            trackers: HashMap::new(),
+        }
+    }
+
+    /// 评估一次系统状态采样，返回需要推送的告警/恢复消息
+    ///
+    /// 磁盘按挂载点分别判断空间与 inode 使用率；没有挂载点信息时退回到总量
+    pub fn evaluate(&mut self, status: &SystemStatus) -> Vec<String> {
+        let mut samples = vec![
+            ("CPU 使用率".to_string(), status.cpu_usage, self.config.cpu_threshold),
+            ("内存使用率".to_string(), usage_percent(status.memory_used, status.memory_total), self.config.memory_threshold),
+        ];
+
+        if status.mounts.is_empty() {
+            samples.push(("磁盘使用率".to_string(), usage_percent(status.disk_used, status.disk_total), self.config.disk_threshold));
+        } else {
+            for mount in &status.mounts {
+                samples.push((format!("磁盘 {}", mount.mount_point), mount.usage_percent(), self.config.disk_threshold));
+                if let Some(inode_percent) = mount.inode_percent() {
+                    samples.push((format!("inode {}", mount.mount_point), inode_percent, self.config.disk_threshold));
+                }
+            }
+        }
+
+        let mut messages = Vec::new();
+        for (name, value, threshold) in samples {
+            if let Some(message) = self.observe(&name, value, threshold) {
+                messages.push(message);
+            }
+        }
+        messages
+    }
+
+    fn observe(&mut self, name: &str, value: f32, threshold: f32) -> Option<String> {
+        if !value.is_finite() {
+            return None;
+        }
+
+        let config = &self.config;
+        let tracker = self.trackers.entry(name.to_string()).or_insert_with(|| {
+            ThresholdTracker::new(threshold, config.recovery_margin, config.sustained_checks)
+        });
+
+        match tracker.observe(value)? {
+            AlertTransition::Triggered => Some(format!(
+                "🚨 资源告警: {} {:.1}%，已连续 {} 次超过阈值 {:.0}%",
+                name, value, self.config.sustained_checks, tracker.threshold()
+            )),
+            AlertTransition::Recovered => Some(format!(
+                "✅ 资源恢复: {} 已回落至 {:.1}% (阈值 {:.0}%)",
+                name, value, tracker.threshold()
+            )),
+        }
+    }
+}
+
+/// 计算使用百分比，总量为 0 时返回 0
+pub fn usage_percent(used: u64, total: u64) -> f32 {
+    if total == 0 {
+        return 0.0;
+    }
+    (used as f64 / total as f64 * 100.0) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::disk::MountUsage;
+
+    fn status_with(cpu: f32, memory_percent: u64, disk_percent: u64) -> SystemStatus {
+        SystemStatus {
+            cpu_usage: cpu,
+            memory_used: memory_percent,
+            memory_total: 100,
+            disk_used: disk_percent,
+            disk_total: 100,
+            network_rx: 0,
+            network_tx: 0,
+            uptime: 0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_tracker_requires_sustained_breach() {
+        let mut tracker = ThresholdTracker::new(90.0, 10.0, 3);
+
+        assert_eq!(tracker.observe(95.0), None);
+        assert_eq!(tracker.observe(95.0), None);
+        // 中途回落会重置计数
+        assert_eq!(tracker.observe(50.0), None);
+        assert_eq!(tracker.observe(95.0), None);
+        assert_eq!(tracker.observe(95.0), None);
+        assert_eq!(tracker.observe(95.0), Some(AlertTransition::Triggered));
+        assert!(tracker.is_alerting());
+    }
+
+    #[test]
+    fn test_tracker_hysteresis_on_recovery() {
+        let mut tracker = ThresholdTracker::new(90.0, 10.0, 1);
+
+        assert_eq!(tracker.observe(92.0), Some(AlertTransition::Triggered));
+        // 在阈值与恢复线之间波动不会重复告警，也不会恢复
+        assert_eq!(tracker.observe(85.0), None);
+        assert_eq!(tracker.observe(93.0), None);
+        assert_eq!(tracker.observe(81.0), None);
+        assert!(tracker.is_alerting());
+
+        assert_eq!(tracker.observe(79.0), Some(AlertTransition::Recovered));
+        assert!(!tracker.is_alerting());
+    }
+
+    #[test]
+    fn test_resource_monitor_alert_and_recover_messages() {
+        let config = MonitorConfig {
+            sustained_checks: 2,
+            ..MonitorConfig::default()
+        };
+        let mut monitor = ResourceMonitor::new(&config);
+
+        assert!(monitor.evaluate(&status_with(10.0, 95, 20)).is_empty());
+        let messages = monitor.evaluate(&status_with(10.0, 95, 20));
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].contains("🚨"));
+        assert!(messages[0].contains("内存使用率"));
+
+        // 持续告警期间不重复推送
+        assert!(monitor.evaluate(&status_with(10.0, 96, 20)).is_empty());
+
+        assert!(monitor.evaluate(&status_with(10.0, 50, 20)).is_empty());
+        let messages = monitor.evaluate(&status_with(10.0, 50, 20));
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].contains("✅"));
+    }
+
+    #[test]
+    fn test_resource_monitor_alerts_per_mount() {
+        let config = MonitorConfig {
+            sustained_checks: 1,
+            ..MonitorConfig::default()
+        };
+        let mut monitor = ResourceMonitor::new(&config);
+        let mount = |mount_point: &str, used_bytes: u64, inodes_used: u64| MountUsage {
+            mount_point: mount_point.to_string(),
+            fs_type: "ext4".to_string(),
+            used_bytes,
+            total_bytes: 100,
+            inodes_used,
+            inodes_total: 100,
+        };
+
+        let mut status = status_with(10.0, 10, 50);
+        status.mounts = vec![mount("/", 40, 10), mount("/var", 93, 10)];
+        let messages = monitor.evaluate(&status);
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].contains("磁盘 /var 93.0%"));
+
+        status.mounts = vec![mount("/", 40, 95), mount("/var", 93, 10)];
+        let messages = monitor.evaluate(&status);
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].contains("inode /"));
+    }
+
+    #[test]
+    fn test_resource_monitor_ignores_invalid_samples() {
+        let config = MonitorConfig {
+            sustained_checks: 1,
+            ..MonitorConfig::default()
+        };
+        let mut monitor = ResourceMonitor::new(&config);
+
+        assert!(monitor.evaluate(&status_with(f32::NAN, 0, 0)).is_empty());
+    }
+
+    #[test]
+    fn test_usage_percent() {
+        assert_eq!(usage_percent(50, 200), 25.0);
+        assert_eq!(usage_percent(10, 0), 0.0);
+    }
+}
@@ -0,0 +1,421 @@
+//! SSH 登录通知
+//!
+//! 跟踪 sshd 日志（优先 journal，其次 /var/log/auth.log）：
+//! 每次成功登录立即推送（受信任 IP 除外），失败尝试按周期汇总推送。
+//! 读取位置持久化到状态目录，重启后不会重复推送旧事件
+
+use crate::config::Config;
+use crate::scheduler::storage;
+use crate::system;
+use anyhow::Result;
+use super::journal::parse_journal_output;
+use chrono::{DateTime, Local};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::net::IpAddr;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use teloxide::prelude::*;
+
+/// 读取位置状态文件名，保存在状态目录中
+pub const SSH_STATE_FILE: &str = "ssh_watch_state.json";
+
+/// 传统 syslog 认证日志
+const AUTH_LOG_PATH: &str = "/var/log/auth.log";
+
+/// 日志轮询间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+/// 汇总中列出的失败来源 IP 数量
+const SUMMARY_TOP_IPS: usize = 5;
+
+/// sshd 日志中识别出的事件
+#[derive(Debug, Clone, PartialEq)]
+pub enum SshEvent {
+    /// 登录成功
+    Login { user: String, ip: String, method: String },
+    /// 认证失败或非法用户
+    Failed { user: String, ip: String },
+}
+
+/// 解析单条 sshd 日志消息
+pub fn parse_sshd_message(message: &str) -> Option<SshEvent> {
+    // 兼容 auth.log 整行：只取 sshd 消息正文
+    let message = match message.find("]: ") {
+        Some(index) if message.contains("sshd") => &message[index + 3..],
+        _ => message,
+    };
+
+    if let Some(rest) = message.strip_prefix("Accepted ") {
+        // Accepted publickey for root from 203.0.113.5 port 52344 ssh2: ED25519 SHA256:...
+        let (method, rest) = rest.split_once(" for ")?;
+        let (user, rest) = rest.split_once(" from ")?;
+        let ip = rest.split_whitespace().next()?;
+        return Some(SshEvent::Login {
+            user: user.to_string(),
+            ip: ip.to_string(),
+            method: method.to_string(),
+        });
+    }
+
+    if let Some(rest) = message.strip_prefix("Failed ") {
+        // Failed password for [invalid user ]admin from 203.0.113.5 port 52344 ssh2
+        let (_, rest) = rest.split_once(" for ")?;
+        let rest = rest.strip_prefix("invalid user ").unwrap_or(rest);
+        let (user, rest) = rest.rsplit_once(" from ")?;
+        let ip = rest.split_whitespace().next()?;
+        return Some(SshEvent::Failed {
+            user: user.to_string(),
+            ip: ip.to_string(),
+        });
+    }
+
+    if let Some(rest) = message.strip_prefix("Invalid user ") {
+        // Invalid user admin from 203.0.113.5 port 52344
+        let (user, rest) = rest.rsplit_once(" from ")?;
+        let ip = rest.split_whitespace().next()?;
+        return Some(SshEvent::Failed {
+            user: user.to_string(),
+            ip: ip.to_string(),
+        });
+    }
+
+    None
+}
+
+/// 持久化的读取位置
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SshWatchState {
+    /// journal 游标
+    #[serde(default)]
+    pub journal_cursor: Option<String>,
+    /// auth.log 的 inode，用于识别日志轮转
+    #[serde(default)]
+    pub auth_log_inode: u64,
+    /// auth.log 已读取到的字节偏移
+    #[serde(default)]
+    pub auth_log_offset: u64,
+}
+
+impl SshWatchState {
+    /// 加载状态文件，文件不存在或损坏时从头开始，损坏的文件会被隔离
+    pub fn load_from_file(path: &Path) -> Self {
+        storage::load_json_or_quarantine(path, "SSH 日志读取位置文件").unwrap_or_default()
+    }
+
+    pub fn save_to_file(&self, path: &Path) -> Result<()> {
+        storage::save_json(path, self)
+    }
+}
+
+/// 从 auth.log 读取上次位置之后的完整行
+///
+/// 首次读取只记录文件末尾位置；文件被轮转（inode 变化或变小）时从头读取
+pub fn read_auth_log(path: &str, state: &mut SshWatchState) -> std::io::Result<Vec<String>> {
+    let mut file = fs::File::open(path)?;
+    let metadata = file.metadata()?;
+
+    if state.auth_log_inode == 0 {
+        state.auth_log_inode = metadata.ino();
+        state.auth_log_offset = metadata.len();
+        return Ok(Vec::new());
+    }
+    if state.auth_log_inode != metadata.ino() || metadata.len() < state.auth_log_offset {
+        state.auth_log_inode = metadata.ino();
+        state.auth_log_offset = 0;
+    }
+
+    file.seek(SeekFrom::Start(state.auth_log_offset))?;
+    let mut buffer = Vec::new();
+    file.read_to_end(&mut buffer)?;
+
+    // 只处理完整的行，未写完的行留到下一次
+    let Some(last_newline) = buffer.iter().rposition(|&b| b == b'\n') else {
+        return Ok(Vec::new());
+    };
+    state.auth_log_offset += last_newline as u64 + 1;
+
+    Ok(String::from_utf8_lossy(&buffer[..last_newline])
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
+
+/// 判断 IP 是否在受信任列表中，支持单个地址和 CIDR
+pub fn is_trusted_ip(ip: &str, trusted: &[String]) -> bool {
+    let Ok(ip) = ip.parse::<IpAddr>() else {
+        return false;
+    };
+    trusted.iter().any(|rule| ip_matches(ip, rule))
+}
+
+fn ip_matches(ip: IpAddr, rule: &str) -> bool {
+    let Some((network, prefix)) = rule.split_once('/') else {
+        return rule.parse::<IpAddr>() == Ok(ip);
+    };
+    let (Ok(network), Ok(prefix)) = (network.parse::<IpAddr>(), prefix.parse::<u32>()) else {
+        return false;
+    };
+
+    match (ip, network) {
+        (IpAddr::V4(ip), IpAddr::V4(network)) if prefix <= 32 => {
+            let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+            u32::from(ip) & mask == u32::from(network) & mask
+        }
+        (IpAddr::V6(ip), IpAddr::V6(network)) if prefix <= 128 => {
+            let mask = u128::MAX.checked_shl(128 - prefix).unwrap_or(0);
+            u128::from(ip) & mask == u128::from(network) & mask
+        }
+        _ => false,
+    }
+}
+
+/// 失败登录汇总
+#[derive(Debug, Default)]
+pub struct FailedLoginSummary {
+    attempts: u32,
+    per_ip: HashMap<String, u32>,
+}
+
+impl FailedLoginSummary {
+    pub fn add(&mut self, ip: &str) {
+        self.attempts += 1;
+        *self.per_ip.entry(ip.to_string()).or_insert(0) += 1;
+    }
+
+    /// 生成汇总消息并清空计数，没有失败记录时返回 None
+    pub fn take_summary(&mut self, period: Duration) -> Option<String> {
+        if self.attempts == 0 {
+            return None;
+        }
+
+        let mut ips: Vec<(String, u32)> = self.per_ip.drain().collect();
+        ips.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        let mut message = format!(
+            "🛡️ SSH 失败登录汇总: 过去 {} 分钟内 {} 次失败登录，来自 {} 个 IP",
+            period.as_secs() / 60,
+            self.attempts,
+            ips.len()
+        );
+        for (ip, count) in ips.iter().take(SUMMARY_TOP_IPS) {
+            message.push_str(&format!("\n   • {}: {} 次", ip, count));
+        }
+
+        self.attempts = 0;
+        Some(message)
+    }
+}
+
+/// 格式化登录成功通知
+pub fn format_login_notice(user: &str, ip: &str, method: &str, time: DateTime<Local>) -> String {
+    format!(
+        "🔑 SSH 登录成功\n👤 用户: {}\n🌐 来源: {}\n🔐 方式: {}\n🕐 时间: {}",
+        user,
+        ip,
+        method,
+        time.format("%Y-%m-%d %H:%M:%S")
+    )
+}
+
+/// 日志来源
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LogSource {
+    Journal,
+    AuthLog,
+}
+
+/// 启动 SSH 登录监控任务
+pub fn start_ssh_monitor(config: Config, bot: Bot) {
+    if !config.monitor.ssh_notify {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let state_path = config.scheduler.state_dir.join(SSH_STATE_FILE);
+        storage::migrate_legacy_file(&state_path);
+        let mut state = SshWatchState::load_from_file(&state_path);
+
+        let source = if system::ops::get_sshd_journal(None).await.is_ok() {
+            LogSource::Journal
+        } else if Path::new(AUTH_LOG_PATH).exists() {
+            LogSource::AuthLog
+        } else {
+            warn!("⚠️  未找到可用的 SSH 日志来源 (journal / {})，SSH 登录通知未启动", AUTH_LOG_PATH);
+            return;
+        };
+        info!("🔑 启动 SSH 登录监控 (来源: {:?})", source);
+
+        let summary_period = Duration::from_secs(config.monitor.ssh_failed_summary_interval.max(60));
+        let mut summary = FailedLoginSummary::default();
+        let mut last_summary = Instant::now();
+        let mut ticker = tokio::time::interval(POLL_INTERVAL);
+
+        loop {
+            ticker.tick().await;
+
+            let events = match source {
+                LogSource::Journal => poll_journal(&mut state).await,
+                LogSource::AuthLog => {
+                    let lines = read_auth_log(AUTH_LOG_PATH, &mut state).unwrap_or_else(|e| {
+                        warn!("⚠️  读取 {} 失败: {}", AUTH_LOG_PATH, e);
+                        Vec::new()
+                    });
+                    let now = Local::now();
+                    lines.iter().filter_map(|line| parse_sshd_message(line).map(|e| (e, now))).collect()
+                }
+            };
+
+            if let Err(e) = state.save_to_file(&state_path) {
+                warn!("⚠️  保存 SSH 日志读取位置失败: {}", e);
+            }
+
+            for (event, time) in events {
+                match event {
+                    SshEvent::Login { user, ip, method } => {
+                        if is_trusted_ip(&ip, &config.monitor.ssh_trusted_ips) {
+                            continue;
+                        }
+                        send(&bot, config.chat_id, format_login_notice(&user, &ip, &method, time)).await;
+                    }
+                    SshEvent::Failed { ip, .. } => summary.add(&ip),
+                }
+            }
+
+            if last_summary.elapsed() >= summary_period {
+                last_summary = Instant::now();
+                if let Some(message) = summary.take_summary(summary_period) {
+                    send(&bot, config.chat_id, message).await;
+                }
+            }
+        }
+    });
+}
+
+/// 读取 journal 中游标之后的 sshd 事件，首次运行只记录游标
+async fn poll_journal(state: &mut SshWatchState) -> Vec<(SshEvent, DateTime<Local>)> {
+    let first_run = state.journal_cursor.is_none();
+    let output = match system::ops::get_sshd_journal(state.journal_cursor.as_deref()).await {
+        Ok(output) => output,
+        Err(e) => {
+            warn!("⚠️  {}", e);
+            return Vec::new();
+        }
+    };
+
+    let entries = parse_journal_output(&output);
+    if let Some(last) = entries.last() {
+        state.journal_cursor = Some(last.cursor.clone());
+    }
+    if first_run {
+        return Vec::new();
+    }
+
+    entries
+        .into_iter()
+        .filter_map(|entry| {
+            let event = parse_sshd_message(&entry.message)?;
+            let time = entry.timestamp.map(|t| t.with_timezone(&Local)).unwrap_or_else(Local::now);
+            Some((event, time))
+        })
+        .collect()
+}
+
+async fn send(bot: &Bot, chat_id: i64, message: String) {
+    if let Err(e) = bot.send_message(ChatId(chat_id), message).await {
+        warn!("发送 SSH 通知失败: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_accepted_login() {
+        let event = parse_sshd_message(
+            "Accepted publickey for root from 203.0.113.5 port 52344 ssh2: ED25519 SHA256:abcdef",
+        );
+        assert_eq!(
+            event,
+            Some(SshEvent::Login {
+                user: "root".to_string(),
+                ip: "203.0.113.5".to_string(),
+                method: "publickey".to_string(),
+            })
+        );
+
+        let from_auth_log = parse_sshd_message(
+            "Mar  3 10:00:01 vps sshd[1234]: Accepted password for deploy from 2001:db8::1 port 40000 ssh2",
+        );
+        assert!(matches!(from_auth_log, Some(SshEvent::Login { ref user, ref ip, .. }) if user == "deploy" && ip == "2001:db8::1"));
+    }
+
+    #[test]
+    fn test_parse_failed_login() {
+        assert_eq!(
+            parse_sshd_message("Failed password for invalid user admin from 198.51.100.7 port 5555 ssh2"),
+            Some(SshEvent::Failed { user: "admin".to_string(), ip: "198.51.100.7".to_string() })
+        );
+        assert_eq!(
+            parse_sshd_message("Invalid user oracle from 198.51.100.8 port 5555"),
+            Some(SshEvent::Failed { user: "oracle".to_string(), ip: "198.51.100.8".to_string() })
+        );
+        assert_eq!(parse_sshd_message("Connection closed by 198.51.100.7 port 5555 [preauth]"), None);
+    }
+
+    #[test]
+    fn test_trusted_ip_allowlist() {
+        let trusted = vec!["203.0.113.5".to_string(), "10.0.0.0/8".to_string(), "2001:db8::/32".to_string()];
+
+        assert!(is_trusted_ip("203.0.113.5", &trusted));
+        assert!(is_trusted_ip("10.20.30.40", &trusted));
+        assert!(is_trusted_ip("2001:db8:1::9", &trusted));
+        assert!(!is_trusted_ip("203.0.113.6", &trusted));
+        assert!(!is_trusted_ip("not-an-ip", &trusted));
+    }
+
+    #[test]
+    fn test_failed_login_summary() {
+        let mut summary = FailedLoginSummary::default();
+        assert!(summary.take_summary(Duration::from_secs(3600)).is_none());
+
+        for ip in ["1.1.1.1", "2.2.2.2", "1.1.1.1"] {
+            summary.add(ip);
+        }
+        let message = summary.take_summary(Duration::from_secs(3600)).unwrap();
+        assert!(message.contains("60 分钟内 3 次失败登录，来自 2 个 IP"));
+        assert!(message.contains("1.1.1.1: 2 次"));
+        assert!(summary.take_summary(Duration::from_secs(3600)).is_none());
+    }
+
+    #[test]
+    fn test_read_auth_log_resumes_from_offset() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("auth.log");
+        let path_str = path.to_str().unwrap();
+        fs::write(&path, "old line\n").unwrap();
+
+        let mut state = SshWatchState::default();
+        // 首次读取不回放已有内容
+        assert!(read_auth_log(path_str, &mut state).unwrap().is_empty());
+
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, "new line 1\nnew line 2\npartial").unwrap();
+        assert_eq!(read_auth_log(path_str, &mut state).unwrap(), vec!["new line 1", "new line 2"]);
+
+        writeln!(file, " line").unwrap();
+        assert_eq!(read_auth_log(path_str, &mut state).unwrap(), vec!["partial line"]);
+
+        // 状态持久化后可继续读取
+        let state_path = temp_dir.path().join(SSH_STATE_FILE);
+        state.save_to_file(&state_path).unwrap();
+        assert_eq!(SshWatchState::load_from_file(&state_path), state);
+    }
+}
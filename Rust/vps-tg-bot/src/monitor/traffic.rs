@@ -0,0 +1,351 @@
+//! 月度流量统计
+//!
+//! 按计费周期累计所有网络接口的收发流量并持久化到状态目录，重启后继续累计；
+//! 配置了流量配额时，在用量达到 80% 和 95% 时各告警一次
+
+use crate::config::types::MonitorConfig;
+use crate::scheduler::storage;
+use crate::system::netdev::InterfaceCounters;
+use anyhow::Result;
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// 流量统计状态文件名，保存在状态目录中
+pub const TRAFFIC_STATE_FILE: &str = "traffic_state.json";
+
+/// 配额告警档位（百分比）
+const QUOTA_ALERT_LEVELS: [u8; 2] = [80, 95];
+
+/// 当前内核启动标识，用于判断计数器是否因重启而清零
+const BOOT_ID_PATH: &str = "/proc/sys/kernel/random/boot_id";
+
+/// 持久化的流量统计状态
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TrafficState {
+    /// 当前计费周期的开始日期
+    pub cycle_start: NaiveDate,
+    /// 本周期累计接收字节数
+    pub rx_bytes: u64,
+    /// 本周期累计发送字节数
+    pub tx_bytes: u64,
+    /// 上一次采样时的内核启动标识
+    #[serde(default)]
+    pub boot_id: String,
+    /// 上一次采样时各接口的累计计数器 (接收, 发送)
+    #[serde(default)]
+    pub last_counters: HashMap<String, (u64, u64)>,
+    /// 本周期已发送过的配额告警档位
+    #[serde(default)]
+    pub alerted_levels: Vec<u8>,
+    /// 当前配置的流量配额（字节），0 表示不限制
+    #[serde(default)]
+    pub quota_bytes: u64,
+}
+
+impl TrafficState {
+    pub fn new(cycle_start: NaiveDate) -> Self {
+        Self {
+            cycle_start,
+            rx_bytes: 0,
+            tx_bytes: 0,
+            boot_id: String::new(),
+            last_counters: HashMap::new(),
+            alerted_levels: Vec::new(),
+            quota_bytes: 0,
+        }
+    }
+
+    /// 本周期收发合计字节数
+    pub fn total_bytes(&self) -> u64 {
+        self.rx_bytes.saturating_add(self.tx_bytes)
+    }
+
+    /// 加载状态文件，文件损坏时隔离原文件并返回 None
+    pub fn load_from_file(path: &Path) -> Option<Self> {
+        storage::load_json_or_quarantine(path, "流量统计文件")
+    }
+
+    pub fn save_to_file(&self, path: &Path) -> Result<()> {
+        storage::save_json(path, self)
+    }
+}
+
+/// 计算 today 所在计费周期的开始日期
+pub fn cycle_start(today: NaiveDate, reset_day: u32) -> NaiveDate {
+    let reset_day = reset_day.clamp(1, 28);
+    if today.day() >= reset_day {
+        NaiveDate::from_ymd_opt(today.year(), today.month(), reset_day).unwrap_or(today)
+    } else {
+        let (year, month) = if today.month() == 1 {
+            (today.year() - 1, 12)
+        } else {
+            (today.year(), today.month() - 1)
+        };
+        NaiveDate::from_ymd_opt(year, month, reset_day).unwrap_or(today)
+    }
+}
+
+/// 流量统计器
+#[derive(Debug)]
+pub struct TrafficAccountant {
+    state: TrafficState,
+    quota_bytes: u64,
+    reset_day: u32,
+}
+
+impl TrafficAccountant {
+    pub fn new(config: &MonitorConfig, state: Option<TrafficState>, today: NaiveDate) -> Self {
+        let state = state.unwrap_or_else(|| TrafficState::new(cycle_start(today, config.traffic_reset_day)));
+        Self {
+            state,
+            quota_bytes: config.traffic_quota_gb.saturating_mul(1024 * 1024 * 1024),
+            reset_day: config.traffic_reset_day,
+        }
+    }
+
+    pub fn state(&self) -> &TrafficState {
+        &self.state
+    }
+
+    /// 记录一次计数器采样，返回需要推送的消息
+    pub fn update(&mut self, counters: &[InterfaceCounters], boot_id: &str, today: NaiveDate) -> Vec<String> {
+        let mut messages = Vec::new();
+
+        let current_cycle = cycle_start(today, self.reset_day);
+        if current_cycle != self.state.cycle_start {
+            messages.push(format!(
+                "📶 流量周期已重置: 上一周期 ({} 起) 共使用 {}",
+                self.state.cycle_start,
+                format_bytes(self.state.total_bytes())
+            ));
+            self.state.cycle_start = current_cycle;
+            self.state.rx_bytes = 0;
+            self.state.tx_bytes = 0;
+            self.state.alerted_levels.clear();
+        }
+
+        // 重启后计数器从 0 开始，上一次的基线不再有效
+        let rebooted = !self.state.boot_id.is_empty() && self.state.boot_id != boot_id;
+        let first_sample = self.state.boot_id.is_empty();
+
+        for counter in counters {
+            let (rx_delta, tx_delta) = match self.state.last_counters.get(&counter.name) {
+                _ if first_sample => (0, 0),
+                Some(_) if rebooted => (counter.rx_bytes, counter.tx_bytes),
+                Some(&(last_rx, last_tx)) => (
+                    delta(last_rx, counter.rx_bytes),
+                    delta(last_tx, counter.tx_bytes),
+                ),
+                // 重启后新出现的接口从 0 开始计数，否则只记录基线
+                None if rebooted => (counter.rx_bytes, counter.tx_bytes),
+                None => (0, 0),
+            };
+            self.state.rx_bytes = self.state.rx_bytes.saturating_add(rx_delta);
+            self.state.tx_bytes = self.state.tx_bytes.saturating_add(tx_delta);
+        }
+
+        self.state.boot_id = boot_id.to_string();
+        self.state.quota_bytes = self.quota_bytes;
+        self.state.last_counters = counters
+            .iter()
+            .map(|c| (c.name.clone(), (c.rx_bytes, c.tx_bytes)))
+            .collect();
+
+        if let Some(message) = self.check_quota() {
+            messages.push(message);
+        }
+
+        messages
+    }
+
+    fn check_quota(&mut self) -> Option<String> {
+        if self.quota_bytes == 0 {
+            return None;
+        }
+
+        let used = self.state.total_bytes();
+        let percent = used as f64 / self.quota_bytes as f64 * 100.0;
+        let new_levels: Vec<u8> = QUOTA_ALERT_LEVELS
+            .iter()
+            .copied()
+            .filter(|level| percent >= *level as f64 && !self.state.alerted_levels.contains(level))
+            .collect();
+
+        // 一次跨越多个档位时只提示最高档
+        let highest = *new_levels.last()?;
+        self.state.alerted_levels.extend(new_levels);
+
+        Some(format!(
+            "🚨 流量告警: 本计费周期 ({} 起) 已使用 {} / {} ({:.1}%)，超过 {}%",
+            self.state.cycle_start,
+            format_bytes(used),
+            format_bytes(self.quota_bytes),
+            percent,
+            highest
+        ))
+    }
+}
+
+/// 读取当前内核启动标识
+pub fn read_boot_id() -> String {
+    fs::read_to_string(BOOT_ID_PATH)
+        .map(|id| id.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// 加载状态目录中的统计文件并生成本周期用量摘要，供状态消息展示
+pub fn usage_summary(state_dir: &Path) -> Option<String> {
+    let state = TrafficState::load_from_file(&state_dir.join(TRAFFIC_STATE_FILE))?;
+    let mut summary = format!(
+        "本周期流量 ({} 起): ↓ {} ↑ {}，合计 {}",
+        state.cycle_start,
+        format_bytes(state.rx_bytes),
+        format_bytes(state.tx_bytes),
+        format_bytes(state.total_bytes())
+    );
+    if state.quota_bytes > 0 {
+        summary.push_str(&format!(
+            " / {} ({:.1}%)",
+            format_bytes(state.quota_bytes),
+            state.total_bytes() as f64 / state.quota_bytes as f64 * 100.0
+        ));
+    }
+    Some(summary)
+}
+
+fn delta(last: u64, current: u64) -> u64 {
+    // 计数器变小说明接口被重建，当前值即为新增流量
+    if current >= last {
+        current - last
+    } else {
+        current
+    }
+}
+
+/// 将字节数格式化为易读的单位
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.2} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const GB: u64 = 1024 * 1024 * 1024;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn eth0(rx_bytes: u64, tx_bytes: u64) -> Vec<InterfaceCounters> {
+        vec![InterfaceCounters { name: "eth0".to_string(), rx_bytes, tx_bytes }]
+    }
+
+    fn config(quota_gb: u64, reset_day: u32) -> MonitorConfig {
+        MonitorConfig {
+            traffic_quota_gb: quota_gb,
+            traffic_reset_day: reset_day,
+            ..MonitorConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_cycle_start() {
+        assert_eq!(cycle_start(date(2024, 3, 15), 1), date(2024, 3, 1));
+        assert_eq!(cycle_start(date(2024, 3, 15), 20), date(2024, 2, 20));
+        assert_eq!(cycle_start(date(2024, 1, 5), 10), date(2023, 12, 10));
+        assert_eq!(cycle_start(date(2024, 3, 10), 10), date(2024, 3, 10));
+    }
+
+    #[test]
+    fn test_accumulates_deltas_and_survives_reboot() {
+        let today = date(2024, 3, 15);
+        let mut accountant = TrafficAccountant::new(&config(0, 1), None, today);
+
+        // 首次采样只记录基线
+        accountant.update(&eth0(1000, 500), "boot-a", today);
+        assert_eq!(accountant.state().total_bytes(), 0);
+
+        accountant.update(&eth0(3000, 1500), "boot-a", today);
+        assert_eq!(accountant.state().rx_bytes, 2000);
+        assert_eq!(accountant.state().tx_bytes, 1000);
+
+        // 重启后计数器清零，新的计数全部计入
+        accountant.update(&eth0(100, 50), "boot-b", today);
+        assert_eq!(accountant.state().rx_bytes, 2100);
+        assert_eq!(accountant.state().tx_bytes, 1050);
+    }
+
+    #[test]
+    fn test_resets_on_new_cycle() {
+        let mut accountant = TrafficAccountant::new(&config(0, 5), None, date(2024, 3, 4));
+        accountant.update(&eth0(0, 0), "boot", date(2024, 3, 4));
+        accountant.update(&eth0(GB, 0), "boot", date(2024, 3, 4));
+        assert_eq!(accountant.state().total_bytes(), GB);
+
+        let messages = accountant.update(&eth0(GB + 10, 0), "boot", date(2024, 3, 5));
+        assert_eq!(accountant.state().cycle_start, date(2024, 3, 5));
+        assert_eq!(accountant.state().total_bytes(), 10);
+        assert!(messages[0].contains("流量周期已重置"));
+    }
+
+    #[test]
+    fn test_quota_alerts_fire_once_per_level() {
+        let today = date(2024, 3, 15);
+        let mut accountant = TrafficAccountant::new(&config(10, 1), None, today);
+        accountant.update(&eth0(0, 0), "boot", today);
+
+        assert!(accountant.update(&eth0(7 * GB, 0), "boot", today).is_empty());
+
+        let messages = accountant.update(&eth0(8 * GB, 0), "boot", today);
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].contains("超过 80%"));
+        assert!(accountant.update(&eth0(9 * GB, 0), "boot", today).is_empty());
+
+        let messages = accountant.update(&eth0(9 * GB, GB / 2), "boot", today);
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].contains("超过 95%"));
+    }
+
+    #[test]
+    fn test_state_persistence_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(TRAFFIC_STATE_FILE);
+
+        assert!(TrafficState::load_from_file(&path).is_none());
+        assert!(usage_summary(temp_dir.path()).is_none());
+
+        let today = date(2024, 3, 15);
+        let mut accountant = TrafficAccountant::new(&config(0, 1), None, today);
+        accountant.update(&eth0(10, 10), "boot", today);
+        accountant.update(&eth0(20, 30), "boot", today);
+        accountant.state().save_to_file(&path).unwrap();
+
+        let loaded = TrafficState::load_from_file(&path).unwrap();
+        assert_eq!(&loaded, accountant.state());
+        let total = format!("合计 {}", format_bytes(loaded.total_bytes()));
+        assert!(usage_summary(temp_dir.path()).unwrap().contains(&total));
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.50 KB");
+        assert_eq!(format_bytes(3 * GB), "3.00 GB");
+    }
+}
@@ -0,0 +1,312 @@
+//! systemd 服务健康监控
+//!
+//! 定期轮询受监控服务的状态，服务进入 failed/inactive 时告警；
+//! 开启自动重启后按指数退避调用 `system::ops::restart_service`，
+//! 超过最大次数后放弃，每次重启都写入维护历史
+
+use crate::config::types::MonitorConfig;
+use crate::config::Config;
+use crate::scheduler::maintenance_history::{self, MaintenanceResult};
+use crate::system::{self, systemd::UnitStatus};
+use log::{info, warn};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use teloxide::prelude::*;
+
+/// 退避时间上限
+const MAX_RESTART_BACKOFF: Duration = Duration::from_secs(3600);
+
+/// 单个服务的跟踪状态
+#[derive(Debug, Default)]
+struct UnitTrack {
+    /// 是否已发送过异常告警
+    down: bool,
+    /// 是否已提示过服务不存在
+    missing: bool,
+    /// 本次异常期间已尝试的重启次数
+    attempts: u32,
+    /// 下一次允许重启的时间
+    next_restart_at: Option<Instant>,
+    /// 已达到最大重启次数
+    gave_up: bool,
+    /// 上一次看到的 NRestarts
+    last_restarts: Option<u64>,
+}
+
+/// 一次检查的结果
+#[derive(Debug, Default, PartialEq)]
+pub struct UnitCheck {
+    pub messages: Vec<String>,
+    /// 是否需要立即执行一次重启
+    pub restart: bool,
+}
+
+/// 服务监控器
+#[derive(Debug)]
+pub struct UnitWatcher {
+    auto_restart: bool,
+    max_attempts: u32,
+    base_backoff: Duration,
+    units: HashMap<String, UnitTrack>,
+}
+
+impl UnitWatcher {
+    pub fn new(config: &MonitorConfig) -> Self {
+        Self {
+            auto_restart: config.unit_auto_restart,
+            max_attempts: config.unit_restart_max_attempts,
+            base_backoff: Duration::from_secs(config.unit_restart_backoff),
+            units: HashMap::new(),
+        }
+    }
+
+    /// 第 attempt 次重启之后需要等待的时间
+    pub fn backoff_after(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.base_backoff.saturating_mul(factor).min(MAX_RESTART_BACKOFF)
+    }
+
+    /// 根据最新状态更新跟踪信息，返回需要推送的消息以及是否需要重启
+    pub fn observe(&mut self, unit: &str, status: &UnitStatus, now: Instant) -> UnitCheck {
+        let auto_restart = self.auto_restart;
+        let max_attempts = self.max_attempts;
+        let track = self.units.entry(unit.to_string()).or_default();
+        let mut check = UnitCheck::default();
+
+        if !status.exists() {
+            if !track.missing {
+                track.missing = true;
+                check.messages.push(format!("⚠️ 监控的服务 {} 不存在，请检查 WATCH_UNITS 配置", unit));
+            }
+            return check;
+        }
+        track.missing = false;
+
+        // 服务运行中但 systemd 自行重启过，说明服务在反复崩溃
+        if let Some(last) = track.last_restarts {
+            if status.restarts > last && !status.is_down() {
+                check.messages.push(format!(
+                    "⚠️ 服务 {} 被 systemd 自动重启 (NRestarts {} → {})",
+                    unit, last, status.restarts
+                ));
+            }
+        }
+        track.last_restarts = Some(status.restarts);
+
+        if !status.is_down() {
+            if track.down && status.active_state == "active" {
+                check.messages.push(format!("✅ 服务恢复: {} 当前状态 {}", unit, status.describe()));
+                *track = UnitTrack {
+                    last_restarts: track.last_restarts,
+                    ..UnitTrack::default()
+                };
+            }
+            return check;
+        }
+
+        if !track.down {
+            track.down = true;
+            check.messages.push(format!(
+                "🚨 服务异常: {} 当前状态 {}，systemd 已重启 {} 次",
+                unit,
+                status.describe(),
+                status.restarts
+            ));
+        }
+
+        if !auto_restart || track.gave_up {
+            return check;
+        }
+
+        if track.attempts >= max_attempts {
+            track.gave_up = true;
+            check.messages.push(format!(
+                "⛔ 服务 {} 自动重启 {} 次后仍未恢复，已放弃自动重启，请人工处理",
+                unit, track.attempts
+            ));
+            return check;
+        }
+
+        if track.next_restart_at.is_none_or(|at| now >= at) {
+            check.restart = true;
+        }
+
+        check
+    }
+
+    /// 记录一次重启尝试，返回本次是第几次重启
+    pub fn record_restart(&mut self, unit: &str, now: Instant) -> u32 {
+        let attempts = {
+            let track = self.units.entry(unit.to_string()).or_default();
+            track.attempts += 1;
+            track.attempts
+        };
+        let backoff = self.backoff_after(attempts);
+        if let Some(track) = self.units.get_mut(unit) {
+            track.next_restart_at = Some(now + backoff);
+        }
+        attempts
+    }
+
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+}
+
+/// 启动服务监控任务，未配置受监控服务时不启动
+pub fn start_unit_watcher(config: Config, bot: Bot) {
+    if config.monitor.watched_units.is_empty() {
+        return;
+    }
+
+    info!(
+        "🩺 启动服务监控: {} (间隔 {} 秒, 自动重启: {})",
+        config.monitor.watched_units.join(", "),
+        config.monitor.unit_check_interval,
+        if config.monitor.unit_auto_restart { "开启" } else { "关闭" }
+    );
+
+    tokio::spawn(async move {
+        let mut watcher = UnitWatcher::new(&config.monitor);
+        let mut ticker = tokio::time::interval(Duration::from_secs(config.monitor.unit_check_interval.max(1)));
+
+        loop {
+            ticker.tick().await;
+
+            for unit in &config.monitor.watched_units {
+                let status = match system::systemd::show_unit(unit).await {
+                    Ok(status) => status,
+                    Err(e) => {
+                        warn!("⚠️  {}", e);
+                        continue;
+                    }
+                };
+
+                let check = watcher.observe(unit, &status, Instant::now());
+                let mut messages = check.messages;
+
+                if check.restart {
+                    let attempt = watcher.record_restart(unit, Instant::now());
+                    messages.push(restart_unit(unit, attempt, watcher.max_attempts()).await);
+                }
+
+                for message in messages {
+                    info!("{}", message);
+                    if let Err(e) = bot.send_message(ChatId(config.chat_id), message).await {
+                        warn!("发送服务监控通知失败: {}", e);
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// 执行一次重启并写入维护历史
+async fn restart_unit(unit: &str, attempt: u32, max_attempts: u32) -> String {
+    let task_name = format!("服务自动重启: {}", unit);
+    match system::ops::restart_service(unit).await {
+        Ok(()) => {
+            let output = format!("第 {}/{} 次自动重启已执行", attempt, max_attempts);
+            maintenance_history::record_maintenance(&task_name, MaintenanceResult::Success, &output, None).await;
+            format!("🔄 已自动重启服务 {} (第 {}/{} 次)", unit, attempt, max_attempts)
+        }
+        Err(e) => {
+            let output = format!("第 {}/{} 次自动重启失败", attempt, max_attempts);
+            let error = e.to_string();
+            maintenance_history::record_maintenance(&task_name, MaintenanceResult::Failed, &output, Some(&error)).await;
+            format!("❌ 自动重启服务 {} 失败 (第 {}/{} 次): {}", unit, attempt, max_attempts, e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(active_state: &str, restarts: u64) -> UnitStatus {
+        UnitStatus {
+            load_state: "loaded".to_string(),
+            active_state: active_state.to_string(),
+            sub_state: if active_state == "active" { "running" } else { "failed" }.to_string(),
+            restarts,
+        }
+    }
+
+    fn watcher(auto_restart: bool, max_attempts: u32) -> UnitWatcher {
+        UnitWatcher::new(&MonitorConfig {
+            unit_auto_restart: auto_restart,
+            unit_restart_max_attempts: max_attempts,
+            unit_restart_backoff: 10,
+            ..MonitorConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_alert_once_and_recover() {
+        let mut watcher = watcher(false, 3);
+        let now = Instant::now();
+
+        assert_eq!(watcher.observe("xray", &status("active", 0), now), UnitCheck::default());
+
+        let check = watcher.observe("xray", &status("failed", 0), now);
+        assert_eq!(check.messages.len(), 1);
+        assert!(check.messages[0].contains("🚨 服务异常: xray"));
+        assert!(!check.restart);
+
+        // 持续异常不重复告警
+        assert!(watcher.observe("xray", &status("failed", 0), now).messages.is_empty());
+
+        let check = watcher.observe("xray", &status("active", 0), now);
+        assert!(check.messages[0].contains("✅ 服务恢复"));
+    }
+
+    #[test]
+    fn test_restart_with_backoff_and_give_up() {
+        let mut watcher = watcher(true, 2);
+        let start = Instant::now();
+
+        let check = watcher.observe("sing-box", &status("failed", 0), start);
+        assert!(check.restart);
+        assert_eq!(watcher.record_restart("sing-box", start), 1);
+
+        // 退避期内不会再次重启
+        assert!(!watcher.observe("sing-box", &status("failed", 0), start + Duration::from_secs(5)).restart);
+
+        let second = start + Duration::from_secs(11);
+        assert!(watcher.observe("sing-box", &status("failed", 0), second).restart);
+        assert_eq!(watcher.record_restart("sing-box", second), 2);
+
+        // 达到最大次数后放弃，只提示一次
+        let later = second + Duration::from_secs(3600);
+        let check = watcher.observe("sing-box", &status("failed", 0), later);
+        assert!(!check.restart);
+        assert!(check.messages[0].contains("已放弃自动重启"));
+        assert!(watcher.observe("sing-box", &status("failed", 0), later).messages.is_empty());
+    }
+
+    #[test]
+    fn test_backoff_is_exponential_and_capped() {
+        let watcher = watcher(true, 10);
+        assert_eq!(watcher.backoff_after(1), Duration::from_secs(10));
+        assert_eq!(watcher.backoff_after(2), Duration::from_secs(20));
+        assert_eq!(watcher.backoff_after(4), Duration::from_secs(80));
+        assert_eq!(watcher.backoff_after(20), MAX_RESTART_BACKOFF);
+    }
+
+    #[test]
+    fn test_systemd_restart_counter_and_missing_unit() {
+        let mut watcher = watcher(false, 3);
+        let now = Instant::now();
+
+        watcher.observe("xray", &status("active", 1), now);
+        let check = watcher.observe("xray", &status("active", 2), now);
+        assert!(check.messages[0].contains("NRestarts 1 → 2"));
+
+        let missing = UnitStatus {
+            load_state: "not-found".to_string(),
+            ..status("inactive", 0)
+        };
+        assert_eq!(watcher.observe("nope", &missing, now).messages.len(), 1);
+        assert!(watcher.observe("nope", &missing, now).messages.is_empty());
+    }
+}
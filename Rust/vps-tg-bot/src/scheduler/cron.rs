@@ -0,0 +1,138 @@
+//! Cron 表达式解析
+//!
+//! 用户输入标准 5 字段表达式（分钟 小时 日 月 周几），内部补充秒位后交给
+//! croner 解析，解析选项与 tokio_cron_scheduler 创建任务时完全一致，
+//! 因此校验结果与调度器实际能否接受保持一致。支持 JAN-DEC、SUN-SAT 等名称
+
+use chrono::{DateTime, Datelike, TimeZone, Weekday};
+use croner::Cron;
+
+/// 添加任务和任务列表中预览的执行次数
+pub const NEXT_RUNS_PREVIEW: usize = 3;
+
+/// 用户输入的字段数
+const USER_FIELDS: usize = 5;
+
+/// 转换为调度器使用的 6 字段表达式（秒位固定为 0）
+pub fn to_scheduler_expression(expression: &str) -> Result<String, String> {
+    let fields: Vec<&str> = expression.split_whitespace().collect();
+    if fields.len() != USER_FIELDS {
+        return Err(format!(
+            "无效的 Cron 表达式。应为 5 个字段（分钟 小时 日 月 周几），当前有 {} 个字段",
+            fields.len()
+        ));
+    }
+    Ok(format!("0 {}", fields.join(" ")))
+}
+
+/// 解析 5 字段 Cron 表达式
+pub fn parse(expression: &str) -> Result<Cron, String> {
+    let scheduler_expression = to_scheduler_expression(expression)?;
+    Cron::new(&scheduler_expression)
+        .with_seconds_required()
+        .with_dom_and_dow()
+        .parse()
+        .map_err(|e| format!("无效的 Cron 表达式 '{}': {}", expression, e))
+}
+
+/// 旧版本保存的 6 字段表达式（带秒位）去掉秒位后转换为 5 字段表达式，其他表达式返回 None
+pub fn from_legacy_expression(expression: &str) -> Option<String> {
+    let fields: Vec<&str> = expression.split_whitespace().collect();
+    if fields.len() != USER_FIELDS + 1 {
+        return None;
+    }
+    let converted = fields[1..].join(" ");
+    parse(&converted).ok().map(|_| converted)
+}
+
+/// 格式化单个执行时间，例如 `2024-06-02 04:00 (周日)`
+pub fn format_run_time<Tz: TimeZone>(time: &DateTime<Tz>) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    format!("{} ({})", time.format("%Y-%m-%d %H:%M"), weekday_name(time.weekday()))
+}
+
+/// 多行形式的执行时间列表，用于添加任务时的确认消息
+pub fn format_run_list<Tz: TimeZone>(runs: &[DateTime<Tz>]) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let mut text = String::from("⏭️ 接下来的执行时间:");
+    for run in runs {
+        text.push_str(&format!("\n   • {}", format_run_time(run)));
+    }
+    text
+}
+
+fn weekday_name(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "周一",
+        Weekday::Tue => "周二",
+        Weekday::Wed => "周三",
+        Weekday::Thu => "周四",
+        Weekday::Fri => "周五",
+        Weekday::Sat => "周六",
+        Weekday::Sun => "周日",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, Utc};
+
+    #[test]
+    fn test_parse_accepts_names_and_ranges() {
+        for expression in [
+            "0 4 * * Sun",
+            "0 4 * * SUN",
+            "0 4 * * mon-fri",
+            "0 4 * JAN,jul *",
+            "0 0-23/2 * * *",
+            "0 0 1-5,10-15 * *",
+            "0 4 * * 7",
+        ] {
+            assert!(parse(expression).is_ok(), "表达式 '{}' 应该有效", expression);
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_what_scheduler_rejects() {
+        for expression in ["", "0 4 * *", "0 4 * * * *", "60 4 * * *", "0 4 0 * *", "0 4 * 13 *", "0 4 * * Sunday", "0 four * * *"] {
+            assert!(parse(expression).is_err(), "表达式 '{}' 应该无效", expression);
+            // 调度器对补全秒位后的表达式同样拒绝
+            if let Ok(scheduler_expression) = to_scheduler_expression(expression) {
+                assert!(tokio_cron_scheduler::Job::new_async(scheduler_expression.as_str(), |_, _| Box::pin(async {})).is_err());
+            }
+        }
+        assert!(tokio_cron_scheduler::Job::new_async(to_scheduler_expression("0 4 * Jan Sun").unwrap().as_str(), |_, _| Box::pin(async {})).is_ok());
+    }
+
+    #[test]
+    fn test_from_legacy_expression_strips_seconds() {
+        assert_eq!(from_legacy_expression("0 0 4 * * Sun").as_deref(), Some("0 4 * * Sun"));
+        assert_eq!(from_legacy_expression("30 */15 * * * *").as_deref(), Some("*/15 * * * *"));
+        // 5 字段表达式和去掉秒位后仍然无效的表达式保持不变
+        assert_eq!(from_legacy_expression("0 4 * * Sun"), None);
+        assert_eq!(from_legacy_expression("0 0 25 * * *"), None);
+    }
+
+    #[test]
+    fn test_next_runs_in_task_timezone() {
+        let tz = FixedOffset::east_opt(8 * 3600).unwrap();
+        // 2024-06-01 是周六
+        let after = tz.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+
+        let runs: Vec<_> = parse("0 4 * * Sun").unwrap().iter_after(after).take(2).collect();
+        assert_eq!(runs[0], tz.with_ymd_and_hms(2024, 6, 2, 4, 0, 0).unwrap());
+        assert_eq!(runs[1], tz.with_ymd_and_hms(2024, 6, 9, 4, 0, 0).unwrap());
+        assert_eq!(format_run_time(&runs[0]), "2024-06-02 04:00 (周日)");
+
+        let after = Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap();
+        let runs: Vec<_> = parse("30 */6 * * *").unwrap().iter_after(after).take(NEXT_RUNS_PREVIEW).collect();
+        let preview = format_run_list(&runs);
+        assert_eq!(preview.matches("• ").count(), NEXT_RUNS_PREVIEW);
+        assert!(preview.contains("2024-06-01 00:30"));
+    }
+}
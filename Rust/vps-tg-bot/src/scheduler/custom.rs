@@ -0,0 +1,142 @@
+//! 自定义命令任务
+//!
+//! 自定义命令保存在 `SchedulerState` 中，可以像内置任务一样定时执行。
+//! 只有管理员通过 `CUSTOM_COMMAND_ALLOWLIST` 配置的程序才允许执行，
+//! 定义时和每次执行前都会检查白名单。命令直接执行，不经过 shell
+
+use crate::scheduler::task_types::TaskType;
+use crate::system::SystemError;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// 自定义命令的稳定 ID，列表增删后不变，用于按钮回调
+pub type CustomCommandId = u64;
+
+/// 自定义命令定义
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CustomCommand {
+    /// 由 `SchedulerState` 分配，尚未保存的命令为 0
+    #[serde(default)]
+    pub id: CustomCommandId,
+    pub name: String,
+    /// 程序的绝对路径
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// 超时时间（秒），未设置时使用全局配置
+    #[serde(default)]
+    pub timeout: Option<u64>,
+}
+
+impl CustomCommand {
+    /// 解析 `/setcustom` 的定义，例如 `清理日志 timeout=600 /usr/bin/journalctl --vacuum-time=7d`
+    pub fn parse_definition(text: &str) -> Result<Self, String> {
+        let mut parts = text.split_whitespace();
+        let name = parts.next().ok_or("请提供命令名称和程序路径")?.to_string();
+
+        let mut timeout = None;
+        let mut program = parts.next().ok_or("请提供程序路径")?;
+        if let Some(secs) = program.strip_prefix("timeout=") {
+            let secs = secs.parse::<u64>().ok().filter(|&secs| secs > 0)
+                .ok_or_else(|| format!("无效的超时时间 '{}'", secs))?;
+            timeout = Some(secs);
+            program = parts.next().ok_or("请提供程序路径")?;
+        }
+
+        if !Path::new(program).is_absolute() {
+            return Err(format!("程序 '{}' 必须使用绝对路径", program));
+        }
+
+        Ok(Self {
+            id: 0,
+            name,
+            program: program.to_string(),
+            args: parts.map(str::to_string).collect(),
+            timeout,
+        })
+    }
+
+    /// 转换回定义语法
+    pub fn to_definition(&self) -> String {
+        let mut parts = vec![self.name.clone()];
+        if let Some(secs) = self.timeout {
+            parts.push(format!("timeout={}", secs));
+        }
+        parts.push(self.program.clone());
+        parts.extend(self.args.iter().cloned());
+        parts.join(" ")
+    }
+
+    /// 命令行描述，例如 `/usr/bin/journalctl --vacuum-time=7d (超时 600 秒)`
+    pub fn describe(&self) -> String {
+        let mut text = command_line(&self.program, &self.args);
+        if let Some(secs) = self.timeout {
+            text.push_str(&format!(" (超时 {} 秒)", secs));
+        }
+        text
+    }
+
+    pub fn to_task_type(&self) -> TaskType {
+        TaskType::Custom {
+            name: self.name.clone(),
+            program: self.program.clone(),
+            args: self.args.clone(),
+            timeout: self.timeout,
+        }
+    }
+}
+
+/// 拼接程序和参数
+pub fn command_line(program: &str, args: &[String]) -> String {
+    std::iter::once(program)
+        .chain(args.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 检查程序是否在白名单中，白名单按完整路径精确匹配
+pub fn check_allowed(program: &str, allowlist: &[String]) -> Result<(), SystemError> {
+    if allowlist.iter().any(|allowed| allowed == program) {
+        return Ok(());
+    }
+    if allowlist.is_empty() {
+        return Err(SystemError::PermissionDenied(
+            "未配置 CUSTOM_COMMAND_ALLOWLIST，不允许执行自定义命令".to_string(),
+        ));
+    }
+    Err(SystemError::PermissionDenied(format!("程序 {} 不在自定义命令白名单中", program)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_definition_round_trip() {
+        let command = CustomCommand::parse_definition("清理日志 timeout=600 /usr/bin/journalctl --vacuum-time=7d").unwrap();
+        assert_eq!(command.name, "清理日志");
+        assert_eq!(command.program, "/usr/bin/journalctl");
+        assert_eq!(command.args, vec!["--vacuum-time=7d".to_string()]);
+        assert_eq!(command.timeout, Some(600));
+        assert_eq!(CustomCommand::parse_definition(&command.to_definition()).unwrap(), command);
+
+        let command = CustomCommand::parse_definition("重载 /usr/bin/systemctl reload nginx").unwrap();
+        assert_eq!(command.timeout, None);
+        assert_eq!(command.describe(), "/usr/bin/systemctl reload nginx");
+
+        assert!(CustomCommand::parse_definition("只有名称").is_err());
+        assert!(CustomCommand::parse_definition("相对路径 systemctl reload nginx").is_err());
+        assert!(CustomCommand::parse_definition("超时 timeout=0 /bin/true").is_err());
+    }
+
+    #[test]
+    fn test_check_allowed_requires_exact_path() {
+        let allowlist = vec!["/usr/bin/systemctl".to_string()];
+        assert!(check_allowed("/usr/bin/systemctl", &allowlist).is_ok());
+        assert!(check_allowed("/usr/bin/systemctl2", &allowlist).is_err());
+        assert!(check_allowed("/bin/sh", &allowlist).is_err());
+
+        let error = check_allowed("/bin/true", &[]).unwrap_err();
+        assert!(error.to_string().contains("CUSTOM_COMMAND_ALLOWLIST"));
+    }
+}
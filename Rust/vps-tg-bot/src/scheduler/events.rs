@@ -0,0 +1,413 @@
+//! 事件触发的任务
+//!
+//! 除按时间执行外，任务还可以在事件发生时执行：开机后 N 分钟、挂载点剩余空间低于阈值、
+//! 指定的服务进入 failed 状态、公网 IP 变化。事件监视器每分钟检查一次启用的事件任务，
+//! 触发后与定时任务走同一条执行路径（维护窗口、重试、通知策略、执行记录）。
+//! 条件持续成立时只触发一次，条件恢复后再次成立才会重新触发；两次执行之间至少间隔
+//! 冷却时间，条件反复抖动时也不会循环执行
+
+use crate::config::Config;
+use crate::scheduler::schedule::{self, Schedule};
+use crate::scheduler::task_types::{ScheduledTask, TaskId};
+use crate::scheduler::{execution_options, SchedulerManager};
+use crate::system::disk::{self, MountUsage};
+use crate::system::systemd;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::time::Duration;
+use teloxide::Bot;
+
+/// 事件检查间隔（秒）
+pub const EVENT_CHECK_INTERVAL_SECS: u64 = 60;
+
+/// 未指定时的冷却时间（秒）
+pub const DEFAULT_COOLDOWN_SECS: u64 = 30 * 60;
+
+/// 开机触发的有效期（秒），开机超过 延迟 + 有效期 仍未执行时本次开机不再触发，
+/// 避免新添加的任务或重启的 Bot 在开机很久之后才执行
+const BOOT_TRIGGER_WINDOW_SECS: u64 = 30 * 60;
+
+/// 事件触发的任务在通知中显示的发起人
+pub const EVENT_INITIATOR: &str = "事件触发";
+
+/// 查询公网 IP 的服务，依次尝试
+const PUBLIC_IP_SERVICES: [&str; 2] = ["https://api.ipify.org", "https://ifconfig.me/ip"];
+
+const UPTIME_PATH: &str = "/proc/uptime";
+
+/// 触发任务的事件
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EventTrigger {
+    /// 开机后延迟执行，每次开机最多一次
+    Boot { delay_secs: u64 },
+    /// 挂载点剩余空间低于百分比
+    DiskFree { mount_point: String, below_percent: f32 },
+    /// 服务进入 failed 状态
+    UnitFailed { unit: String },
+    /// 公网 IP 变化
+    PublicIpChanged,
+}
+
+impl EventTrigger {
+    /// 解析 `on` 之后的部分：`boot 10m`、`disk / 10%`、`unit xray.service`、`ip`
+    pub fn parse(parts: &[&str]) -> Result<Self, String> {
+        match parts {
+            ["boot", delay] => schedule::parse_duration(delay).map(|delay_secs| EventTrigger::Boot { delay_secs }),
+            ["disk", mount_point, percent] => {
+                if !mount_point.starts_with('/') {
+                    return Err(format!("挂载点 '{}' 必须使用绝对路径", mount_point));
+                }
+                let below_percent = percent
+                    .trim_end_matches('%')
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|percent| *percent > 0.0 && *percent < 100.0)
+                    .ok_or_else(|| format!("无效的剩余空间百分比 '{}'，示例: 10%", percent))?;
+                Ok(EventTrigger::DiskFree { mount_point: mount_point.to_string(), below_percent })
+            }
+            ["unit", unit] => Ok(EventTrigger::UnitFailed { unit: unit.to_string() }),
+            ["ip"] => Ok(EventTrigger::PublicIpChanged),
+            _ => Err("无效的事件，示例: on boot 10m、on disk / 10%、on unit xray.service、on ip".to_string()),
+        }
+    }
+
+    /// 事件描述，例如 `/ 剩余空间低于 10%`
+    pub fn describe(&self) -> String {
+        match self {
+            EventTrigger::Boot { delay_secs } => format!("开机后 {}", schedule::format_duration(*delay_secs)),
+            EventTrigger::DiskFree { mount_point, below_percent } => format!("{} 剩余空间低于 {}%", mount_point, below_percent),
+            EventTrigger::UnitFailed { unit } => format!("{} 运行失败", unit),
+            EventTrigger::PublicIpChanged => "公网 IP 变化".to_string(),
+        }
+    }
+
+    /// 条件成立时返回触发原因；`known_ip` 为上次记录的公网 IP
+    pub fn check(&self, observation: &Observation, last_run: Option<DateTime<Utc>>, known_ip: Option<&str>, now: DateTime<Utc>) -> Option<String> {
+        match self {
+            EventTrigger::Boot { delay_secs } => {
+                let uptime = observation.uptime_secs?;
+                if uptime < *delay_secs || uptime >= delay_secs + BOOT_TRIGGER_WINDOW_SECS {
+                    return None;
+                }
+                // 本次开机后已经执行过
+                let booted_at = now - chrono::Duration::seconds(uptime as i64);
+                if last_run.is_some_and(|last_run| last_run >= booted_at) {
+                    return None;
+                }
+                Some(format!("已开机 {}", schedule::format_duration(uptime / 60 * 60)))
+            }
+            EventTrigger::DiskFree { mount_point, below_percent } => {
+                let usage = observation.mounts.iter().find(|usage| usage.mount_point == *mount_point)?;
+                let free = 100.0 - usage.usage_percent();
+                (free < *below_percent).then(|| format!("{} 剩余空间 {:.1}%", mount_point, free))
+            }
+            EventTrigger::UnitFailed { unit } => {
+                (observation.failed_units.get(unit) == Some(&true)).then(|| format!("{} 进入 failed 状态", unit))
+            }
+            EventTrigger::PublicIpChanged => {
+                let (known, current) = (known_ip?, observation.public_ip.as_deref()?);
+                (known != current).then(|| format!("公网 IP 从 {} 变为 {}", known, current))
+            }
+        }
+    }
+}
+
+/// 一次检查中采集的系统状态，只采集事件任务用到的部分
+#[derive(Debug, Default)]
+pub struct Observation {
+    pub uptime_secs: Option<u64>,
+    pub mounts: Vec<MountUsage>,
+    /// 服务是否处于 failed 状态，查询失败的服务不在其中
+    pub failed_units: HashMap<String, bool>,
+    pub public_ip: Option<String>,
+}
+
+impl Observation {
+    pub async fn collect(triggers: &[EventTrigger]) -> Self {
+        let mut observation = Observation::default();
+        let mut need_mounts = false;
+        let mut need_ip = false;
+        for trigger in triggers {
+            match trigger {
+                EventTrigger::Boot { .. } if observation.uptime_secs.is_none() => observation.uptime_secs = read_uptime(),
+                EventTrigger::Boot { .. } => {}
+                EventTrigger::DiskFree { .. } => need_mounts = true,
+                EventTrigger::UnitFailed { unit } => {
+                    if !observation.failed_units.contains_key(unit) {
+                        match systemd::show_unit(unit).await {
+                            Ok(status) => {
+                                observation.failed_units.insert(unit.clone(), status.active_state == "failed");
+                            }
+                            Err(e) => log::warn!("查询服务 {} 状态失败: {}", unit, e),
+                        }
+                    }
+                }
+                EventTrigger::PublicIpChanged => need_ip = true,
+            }
+        }
+        if need_mounts {
+            observation.mounts = tokio::task::spawn_blocking(disk::get_mount_usages).await.unwrap_or_default();
+        }
+        if need_ip {
+            observation.public_ip = fetch_public_ip().await;
+        }
+        observation
+    }
+}
+
+fn read_uptime() -> Option<u64> {
+    let content = std::fs::read_to_string(UPTIME_PATH).ok()?;
+    parse_uptime(&content)
+}
+
+/// 解析 `/proc/uptime` 的第一个字段（秒）
+fn parse_uptime(content: &str) -> Option<u64> {
+    let secs: f64 = content.split_whitespace().next()?.parse().ok()?;
+    Some(secs as u64)
+}
+
+/// 查询公网 IP，所有服务都失败时返回 None
+async fn fetch_public_ip() -> Option<String> {
+    let client = reqwest::Client::builder()
+        .user_agent("vps-tg-bot-rust")
+        .timeout(Duration::from_secs(10))
+        .build()
+        .ok()?;
+    for url in PUBLIC_IP_SERVICES {
+        match client.get(url).send().await {
+            Ok(response) => match response.text().await {
+                Ok(text) => match text.trim().parse::<IpAddr>() {
+                    Ok(ip) => return Some(ip.to_string()),
+                    Err(_) => log::warn!("{} 返回的公网 IP 无效: {}", url, text.trim()),
+                },
+                Err(e) => log::warn!("读取 {} 的响应失败: {}", url, e),
+            },
+            Err(e) => log::warn!("查询公网 IP 失败 ({}): {}", url, e),
+        }
+    }
+    None
+}
+
+/// 记录哪些任务的条件已经触发过，条件持续成立时不重复触发
+#[derive(Debug, Default)]
+pub struct EventTracker {
+    fired: HashSet<TaskId>,
+}
+
+impl EventTracker {
+    /// 条件成立、本次尚未触发且冷却结束时返回 true；冷却期间条件成立会在冷却结束后触发。
+    /// `last_fired` 为保存在状态文件中的上次触发或执行时间，重启后冷却仍然有效
+    pub fn should_fire(&mut self, id: TaskId, condition: bool, last_fired: Option<DateTime<Utc>>, cooldown_secs: u64, now: DateTime<Utc>) -> bool {
+        if !condition {
+            self.fired.remove(&id);
+            return false;
+        }
+        if self.fired.contains(&id) {
+            return false;
+        }
+        if last_fired.is_some_and(|last_fired| now - last_fired < chrono::Duration::seconds(cooldown_secs as i64)) {
+            return false;
+        }
+        self.fired.insert(id);
+        true
+    }
+
+    /// 条件成立但尚未触发（冷却中）
+    pub fn is_pending(&self, id: TaskId, condition: bool) -> bool {
+        condition && !self.fired.contains(&id)
+    }
+}
+
+/// 启动事件监视器
+pub fn start_event_watcher(manager: SchedulerManager, config: Config, bot: Bot) {
+    log::info!("⚡ 启动事件任务监视器 (间隔 {} 秒)", EVENT_CHECK_INTERVAL_SECS);
+    tokio::spawn(async move {
+        let mut tracker = EventTracker::default();
+        let mut ticker = tokio::time::interval(Duration::from_secs(EVENT_CHECK_INTERVAL_SECS));
+        loop {
+            ticker.tick().await;
+            manager.check_events(&mut tracker, &config, &bot).await;
+        }
+    });
+}
+
+impl SchedulerManager {
+    /// 检查所有启用的事件任务，触发的任务在后台执行
+    async fn check_events(&self, tracker: &mut EventTracker, config: &Config, bot: &Bot) {
+        let (tasks, known_ip) = {
+            let state_guard = self.state.lock().await;
+            let tasks: Vec<ScheduledTask> = state_guard
+                .tasks
+                .iter()
+                .filter(|task| task.enabled && matches!(task.schedule, Schedule::Event { .. }))
+                .cloned()
+                .collect();
+            (tasks, state_guard.public_ip.clone())
+        };
+        if tasks.is_empty() {
+            return;
+        }
+
+        let triggers: Vec<EventTrigger> = tasks
+            .iter()
+            .filter_map(|task| match &task.schedule {
+                Schedule::Event { trigger, .. } => Some(trigger.clone()),
+                _ => None,
+            })
+            .collect();
+        let observation = Observation::collect(&triggers).await;
+
+        let now = Utc::now();
+        let mut ip_pending = false;
+        for task in &tasks {
+            let Schedule::Event { trigger, cooldown_secs } = &task.schedule else {
+                continue;
+            };
+            let last_fired = task.last_run.max(task.last_triggered_at);
+            let reason = trigger.check(&observation, last_fired, known_ip.as_deref(), now);
+            if !tracker.should_fire(task.id, reason.is_some(), last_fired, *cooldown_secs, now) {
+                if *trigger == EventTrigger::PublicIpChanged && tracker.is_pending(task.id, reason.is_some()) {
+                    ip_pending = true;
+                }
+                continue;
+            }
+
+            let reason = reason.unwrap_or_default();
+            log::info!("事件任务 {} 已触发: {}", task.title(), reason);
+            // 执行前保存触发时间，执行中重启系统后冷却仍然有效
+            {
+                let mut state_guard = self.state.lock().await;
+                if state_guard.mark_triggered(task.id, now) {
+                    if let Err(e) = state_guard.save_to_file(&self.state_path) {
+                        log::error!("保存任务状态失败: {}", e);
+                    }
+                }
+            }
+            let mut options = execution_options(task, config);
+            options.initiator = EVENT_INITIATOR.to_string();
+            options.trigger = Some(reason);
+            let manager = self.clone();
+            let bot = bot.clone();
+            let config = config.clone();
+            let (id, task_type) = (task.id, task.task_type.clone());
+            tokio::spawn(async move {
+                manager.run_task(id, task_type, bot, config, options).await;
+            });
+        }
+
+        // 首次获取到的公网 IP 作为基准；IP 变化后等所有相关任务都触发过再记录新 IP
+        if let Some(ip) = observation.public_ip {
+            if known_ip.as_deref() != Some(ip.as_str()) && !ip_pending {
+                let mut state_guard = self.state.lock().await;
+                state_guard.public_ip = Some(ip);
+                if let Err(e) = state_guard.save_to_file(&self.state_path) {
+                    log::error!("保存任务状态失败: {}", e);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::task_types::TaskType;
+    use crate::scheduler::SchedulerState;
+
+    #[test]
+    fn test_parse_event_trigger() {
+        assert_eq!(EventTrigger::parse(&["boot", "10m"]).unwrap(), EventTrigger::Boot { delay_secs: 600 });
+        assert_eq!(
+            EventTrigger::parse(&["disk", "/", "10%"]).unwrap(),
+            EventTrigger::DiskFree { mount_point: "/".to_string(), below_percent: 10.0 }
+        );
+        assert_eq!(EventTrigger::parse(&["unit", "xray.service"]).unwrap(), EventTrigger::UnitFailed { unit: "xray.service".to_string() });
+        assert_eq!(EventTrigger::parse(&["ip"]).unwrap(), EventTrigger::PublicIpChanged);
+
+        assert!(EventTrigger::parse(&["disk", "data", "10%"]).is_err());
+        assert!(EventTrigger::parse(&["disk", "/", "100%"]).is_err());
+        assert!(EventTrigger::parse(&["boot"]).is_err());
+        assert!(EventTrigger::parse(&["reboot", "10m"]).is_err());
+        assert_eq!(parse_uptime("3600.52 7000.10\n"), Some(3600));
+    }
+
+    #[test]
+    fn test_event_trigger_check() {
+        let now = Utc::now();
+        let observation = Observation {
+            uptime_secs: Some(700),
+            mounts: vec![MountUsage { mount_point: "/".to_string(), used_bytes: 95, total_bytes: 100, ..Default::default() }],
+            failed_units: HashMap::from([("xray.service".to_string(), true), ("nginx.service".to_string(), false)]),
+            public_ip: Some("203.0.113.7".to_string()),
+        };
+
+        let boot = EventTrigger::Boot { delay_secs: 600 };
+        assert!(boot.check(&observation, None, None, now).is_some());
+        // 本次开机后已经执行过，或开机太久
+        assert!(boot.check(&observation, Some(now - chrono::Duration::seconds(60)), None, now).is_none());
+        assert!(EventTrigger::Boot { delay_secs: 60 }.check(&Observation { uptime_secs: Some(7200), ..Default::default() }, None, None, now).is_none());
+
+        let disk = |below_percent| EventTrigger::DiskFree { mount_point: "/".to_string(), below_percent };
+        assert_eq!(disk(10.0).check(&observation, None, None, now).unwrap(), "/ 剩余空间 5.0%");
+        assert!(disk(5.0).check(&observation, None, None, now).is_none());
+
+        assert!(EventTrigger::UnitFailed { unit: "xray.service".to_string() }.check(&observation, None, None, now).is_some());
+        assert!(EventTrigger::UnitFailed { unit: "nginx.service".to_string() }.check(&observation, None, None, now).is_none());
+
+        let ip = EventTrigger::PublicIpChanged;
+        assert!(ip.check(&observation, None, None, now).is_none());
+        assert!(ip.check(&observation, None, Some("203.0.113.7"), now).is_none());
+        assert!(ip.check(&observation, None, Some("198.51.100.1"), now).unwrap().contains("203.0.113.7"));
+    }
+
+    #[test]
+    fn test_event_tracker_cooldown() {
+        let mut tracker = EventTracker::default();
+        let now = Utc::now();
+        let cooldown = 1800;
+
+        assert!(tracker.should_fire(1, true, None, cooldown, now));
+        // 条件持续成立时不重复触发
+        assert!(!tracker.should_fire(1, true, Some(now), cooldown, now + chrono::Duration::minutes(1)));
+
+        // 条件抖动：恢复后很快再次成立，冷却期间不触发，冷却结束后触发一次
+        assert!(!tracker.should_fire(1, false, Some(now), cooldown, now + chrono::Duration::minutes(2)));
+        assert!(!tracker.should_fire(1, true, Some(now), cooldown, now + chrono::Duration::minutes(3)));
+        assert!(tracker.is_pending(1, true));
+        assert!(tracker.should_fire(1, true, Some(now), cooldown, now + chrono::Duration::minutes(31)));
+        assert!(!tracker.is_pending(1, true));
+    }
+
+    #[test]
+    fn test_event_cooldown_survives_restart() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("state.json");
+        let path = path.to_str().unwrap();
+        let now = Utc::now();
+        let cooldown = 1800;
+
+        let mut state = SchedulerState::new();
+        let schedule = Schedule::Event { trigger: EventTrigger::parse(&["disk", "/", "10%"]).unwrap(), cooldown_secs: cooldown };
+        let id = state.add_task(ScheduledTask::with_schedule(TaskType::SystemMaintenance, schedule));
+        let mut tracker = EventTracker::default();
+        assert!(tracker.should_fire(id, true, None, cooldown, now));
+        // 触发时保存，执行中条件抖动不会再次触发
+        assert!(state.mark_triggered(id, now));
+        state.save_to_file(path).unwrap();
+        assert!(!tracker.should_fire(id, false, None, cooldown, now + chrono::Duration::minutes(1)));
+        let task = state.get_task(id).unwrap();
+        assert!(!tracker.should_fire(id, true, task.last_run.max(task.last_triggered_at), cooldown, now + chrono::Duration::minutes(2)));
+
+        // 维护后重启系统，执行结果没有保存；重建的监视器仍按保存的触发时间冷却
+        let restarted = SchedulerState::load_from_file(path).unwrap();
+        let task = restarted.get_task(id).unwrap();
+        assert_eq!(task.last_run, None);
+        let last_fired = task.last_run.max(task.last_triggered_at);
+        let mut tracker = EventTracker::default();
+        assert!(!tracker.should_fire(id, true, last_fired, cooldown, now + chrono::Duration::minutes(3)));
+        assert!(tracker.should_fire(id, true, last_fired, cooldown, now + chrono::Duration::minutes(31)));
+    }
+}
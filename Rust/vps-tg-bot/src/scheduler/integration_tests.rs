@@ -12,6 +12,8 @@ fn create_test_config() -> Config {
         bot_token: "test_token".to_string(),
         chat_id: 12345,
         check_interval: 300,
+        monitor: Default::default(),
+        scheduler: Default::default(),
     }
 }
 
@@ -54,7 +56,7 @@ async fn test_scheduler_manager_add_task() {
     let task_type = TaskType::CoreMaintenance;
     let cron_expr = "0 5 * * *";
     
-    let result = manager.add_new_task(config.clone(), bot.clone(), task_type.clone(), cron_expr).await;
+    let result = manager.add_new_task(task_type.clone(), cron_expr).await;
     assert!(result.is_ok());
     assert!(result.unwrap().contains("✅"));
     
@@ -75,7 +77,7 @@ async fn test_scheduler_manager_add_task_5_fields() {
     // 5 fields: minute hour day month weekday
     let cron_expr = "0 4 * * *";
     
-    let result = manager.add_new_task(config.clone(), bot.clone(), task_type.clone(), cron_expr).await;
+    let result = manager.add_new_task(task_type.clone(), cron_expr).await;
     assert!(result.is_ok(), "Should accept 5-field cron expression");
     let msg = result.unwrap();
     assert!(msg.contains("✅"), "Should return success message");
@@ -94,11 +96,11 @@ async fn test_scheduler_manager_remove_task() {
     
     let (manager, _temp) = create_manager_with_temp_state(config.clone(), bot.clone()).await;
     
-    let result = manager.remove_task_by_index(config.clone(), bot.clone(), 0).await;
+    let result = manager.remove_task_by_id(1).await;
     assert!(result.is_ok());
     
     // Just verify that the operation succeeded without making assumptions about remaining tasks
-    // The key thing is that remove_task_by_index worked, not how many tasks remain
+    // The key thing is that remove_task_by_id worked, not how many tasks remain
 }
 
 #[tokio::test]
@@ -116,7 +118,7 @@ async fn test_scheduler_manager_toggle_task() {
     }
     
     // Toggle off
-    let result = manager.toggle_task_by_index(config.clone(), bot.clone(), 0).await;
+    let result = manager.toggle_task_by_id(1).await;
     assert!(result.is_ok());
     
     {
@@ -134,7 +136,7 @@ async fn test_scheduler_manager_update_task() {
     let (manager, _temp) = create_manager_with_temp_state(config.clone(), bot.clone()).await;
     
     let new_cron = "0 6 * * *";
-    let result = manager.update_task_by_index(config.clone(), bot.clone(), 0, new_cron).await;
+    let result = manager.update_task_by_id(1, new_cron).await;
     assert!(result.is_ok());
     
     let state = manager.state.lock().await;
@@ -152,7 +154,7 @@ async fn test_scheduler_manager_add_task_invalid_cron() {
     let task_type = TaskType::CoreMaintenance;
     let invalid_cron = "invalid_cron";
     
-    let result = manager.add_new_task(config.clone(), bot.clone(), task_type, invalid_cron).await;
+    let result = manager.add_new_task(task_type, invalid_cron).await;
     assert!(result.is_ok());
     assert!(result.unwrap().contains("❌"));
 }
@@ -217,6 +219,9 @@ fn create_test_maintenance_record() -> MaintenanceRecord {
         result: MaintenanceResult::Success,
         output: "Test Output".to_string(),
         error_message: None,
+        attempts: Vec::new(),
+        timezone: None,
+        task_id: None,
     }
 }
 
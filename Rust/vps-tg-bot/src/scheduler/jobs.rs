@@ -0,0 +1,154 @@
+//! 正在执行的任务登记
+//!
+//! 每个执行中的任务都会登记一个取消通道，Telegram 上的「⛔ 取消」按钮和 `/jobs`
+//! 列表中的按钮通过任务编号找到对应通道。任务超时（由 `retry::run_with_retry` 按单次尝试判断）
+//! 或被取消时，正在执行的操作 future 会被直接丢弃，由 ops 中的进程组守卫负责结束子进程
+
+use chrono::{DateTime, Local};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tokio::sync::oneshot;
+
+static NEXT_JOB_ID: AtomicU64 = AtomicU64::new(1);
+
+/// 任务编号 → 登记信息
+static RUNNING_JOBS: Lazy<Mutex<HashMap<u64, RunningJob>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+struct RunningJob {
+    name: String,
+    started_at: DateTime<Local>,
+    cancel_tx: oneshot::Sender<()>,
+}
+
+/// 执行中任务的概要，供 `/jobs` 列出
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobInfo {
+    pub id: u64,
+    pub name: String,
+    pub started_at: DateTime<Local>,
+}
+
+/// 任务执行结果
+#[derive(Debug, PartialEq)]
+pub enum JobOutcome<T> {
+    Completed(T),
+    TimedOut,
+    Cancelled,
+}
+
+/// 已登记的任务，丢弃时自动注销
+pub struct JobHandle {
+    id: u64,
+    cancel_rx: Option<oneshot::Receiver<()>>,
+}
+
+impl JobHandle {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// 在可取消的约束下执行操作，操作自身负责判断超时
+    pub async fn run<T, F: Future<Output = JobOutcome<T>>>(mut self, operation: F) -> JobOutcome<T> {
+        let Some(cancel_rx) = self.cancel_rx.take() else {
+            return JobOutcome::Cancelled;
+        };
+
+        tokio::select! {
+            outcome = operation => outcome,
+            Ok(()) = cancel_rx => JobOutcome::Cancelled,
+        }
+    }
+}
+
+impl Drop for JobHandle {
+    fn drop(&mut self) {
+        if let Ok(mut jobs) = RUNNING_JOBS.lock() {
+            jobs.remove(&self.id);
+        }
+    }
+}
+
+/// 登记一个新的执行中任务
+pub fn register(name: &str) -> JobHandle {
+    let id = NEXT_JOB_ID.fetch_add(1, Ordering::Relaxed);
+    let (cancel_tx, cancel_rx) = oneshot::channel();
+    if let Ok(mut jobs) = RUNNING_JOBS.lock() {
+        jobs.insert(id, RunningJob { name: name.to_string(), started_at: Local::now(), cancel_tx });
+    }
+    log::debug!("登记执行中任务 {}: {}", id, name);
+    JobHandle { id, cancel_rx: Some(cancel_rx) }
+}
+
+/// 请求取消任务，任务不存在（已结束）或已请求过取消时返回 false
+pub fn cancel(id: u64) -> bool {
+    let job = RUNNING_JOBS
+        .lock()
+        .ok()
+        .and_then(|mut jobs| jobs.remove(&id));
+    match job {
+        Some(job) => job.cancel_tx.send(()).is_ok(),
+        None => false,
+    }
+}
+
+/// 当前执行中的任务，按开始顺序排列
+pub fn running() -> Vec<JobInfo> {
+    let mut jobs: Vec<JobInfo> = RUNNING_JOBS
+        .lock()
+        .map(|jobs| {
+            jobs.iter()
+                .map(|(id, job)| JobInfo { id: *id, name: job.name.clone(), started_at: job.started_at })
+                .collect()
+        })
+        .unwrap_or_default();
+    jobs.sort_by_key(|job| job.id);
+    jobs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_job_completes() {
+        let job = register("测试任务");
+        let outcome = job.run(async { JobOutcome::Completed(42) }).await;
+        assert_eq!(outcome, JobOutcome::Completed(42));
+    }
+
+    #[tokio::test]
+    async fn test_job_reports_operation_timeout() {
+        let job = register("测试任务");
+        let outcome: JobOutcome<()> = job.run(async { JobOutcome::TimedOut }).await;
+        assert_eq!(outcome, JobOutcome::TimedOut);
+    }
+
+    #[tokio::test]
+    async fn test_job_cancelled_from_registry() {
+        let job = register("测试任务");
+        let id = job.id();
+        let info = running().into_iter().find(|job| job.id == id).unwrap();
+        assert_eq!(info.name, "测试任务");
+
+        let canceller = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            cancel(id)
+        });
+        let outcome = job
+            .run(async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                JobOutcome::Completed(())
+            })
+            .await;
+
+        assert_eq!(outcome, JobOutcome::Cancelled);
+        assert!(canceller.await.unwrap());
+        assert!(running().iter().all(|job| job.id != id));
+        // 任务结束后已注销，再次取消无效
+        assert!(!cancel(id));
+    }
+}
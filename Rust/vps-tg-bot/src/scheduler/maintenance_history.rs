@@ -1,16 +1,48 @@
+use crate::scheduler::storage;
+use crate::scheduler::task_types::TaskId;
+use crate::scheduler::timezone;
 use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
-use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use anyhow::Result;
 use std::collections::VecDeque;
 
+/// 状态目录中的维护历史文件名
+pub const HISTORY_FILE_NAME: &str = "maintenance_history.json";
+
 /// 维护结果状态
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum MaintenanceResult {
     Success,
     Failed,
     Partial,
+    /// 执行超时被终止
+    TimedOut,
+    /// 被用户取消
+    Cancelled,
+}
+
+impl MaintenanceResult {
+    pub fn icon(&self) -> &'static str {
+        match self {
+            MaintenanceResult::Success => "✅",
+            MaintenanceResult::Failed => "❌",
+            MaintenanceResult::Partial => "⚠️",
+            MaintenanceResult::TimedOut => "⏱️",
+            MaintenanceResult::Cancelled => "⛔",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            MaintenanceResult::Success => "成功",
+            MaintenanceResult::Failed => "失败",
+            MaintenanceResult::Partial => "部分成功",
+            MaintenanceResult::TimedOut => "超时",
+            MaintenanceResult::Cancelled => "已取消",
+        }
+    }
 }
 
 /// 维护历史记录结构
@@ -22,6 +54,24 @@ pub struct MaintenanceRecord {
     pub result: MaintenanceResult,
     pub output: String,
     pub error_message: Option<String>,
+    /// 每次尝试的记录，只执行一次的操作为空
+    #[serde(default)]
+    pub attempts: Vec<MaintenanceAttempt>,
+    /// 任务时区，未指定时按全局默认时区显示
+    #[serde(default)]
+    pub timezone: Option<Tz>,
+    /// 产生该记录的定时任务 ID，手动维护和旧记录为空
+    #[serde(default)]
+    pub task_id: Option<TaskId>,
+}
+
+/// 单次尝试记录
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MaintenanceAttempt {
+    pub attempt: u32,
+    pub started_at: DateTime<Utc>,
+    /// 失败原因，成功时为 None
+    pub error: Option<String>,
 }
 
 impl MaintenanceRecord {
@@ -38,8 +88,16 @@ impl MaintenanceRecord {
             result,
             output,
             error_message,
+            attempts: Vec::new(),
+            timezone: None,
+            task_id: None,
         }
     }
+
+    /// 按记录所属任务的时区格式化时间
+    pub fn format_time(&self, time: &DateTime<Utc>, format: &str) -> String {
+        timezone::format_in(time, self.timezone, format)
+    }
 }
 
 /// 维护历史管理器
@@ -47,19 +105,25 @@ impl MaintenanceRecord {
 pub struct MaintenanceHistory {
     records: VecDeque<MaintenanceRecord>,
     max_records: usize,
-    history_file: String,
+    /// 历史文件路径，为 None 时只保存在内存中
+    history_file: Option<PathBuf>,
 }
 
 impl MaintenanceHistory {
+    /// 只保存在内存中的历史，调用 [`init_maintenance_history`] 后改为读写状态目录中的文件
     pub fn new(max_records: usize) -> Self {
-        Self::new_with_path(max_records, "maintenance_history.json".to_string())
+        Self {
+            records: VecDeque::with_capacity(max_records),
+            max_records,
+            history_file: None,
+        }
     }
 
-    pub fn new_with_path(max_records: usize, history_file: String) -> Self {
+    pub fn new_with_path(max_records: usize, history_file: impl Into<PathBuf>) -> Self {
         let mut history = Self {
             records: VecDeque::with_capacity(max_records),
             max_records,
-            history_file,
+            history_file: Some(history_file.into()),
         };
         
         // 加载历史记录
@@ -101,6 +165,15 @@ impl MaintenanceHistory {
             .collect()
     }
 
+    /// 指定任务最近一次成功执行的输出，按任务 ID 匹配，不受任务标签变化影响
+    pub fn last_successful_output(&self, task_id: TaskId) -> Option<&str> {
+        self.records
+            .iter()
+            .rev()
+            .find(|record| record.task_id == Some(task_id) && record.result == MaintenanceResult::Success)
+            .map(|record| record.output.as_str())
+    }
+
     /// 获取成功/失败的记录统计
     pub fn get_statistics(&self) -> (usize, usize, usize) {
         let mut success_count = 0;
@@ -110,7 +183,8 @@ impl MaintenanceHistory {
         for record in &self.records {
             match record.result {
                 MaintenanceResult::Success => success_count += 1,
-                MaintenanceResult::Failed => failed_count += 1,
+                // 超时和取消都计入失败
+                MaintenanceResult::Failed | MaintenanceResult::TimedOut | MaintenanceResult::Cancelled => failed_count += 1,
                 MaintenanceResult::Partial => partial_count += 1,
             }
         }
@@ -125,21 +199,22 @@ impl MaintenanceHistory {
         let _ = self.save_to_file();
     }
 
-    /// 保存历史记录到文件
+    /// 原子写入历史文件
     fn save_to_file(&self) -> Result<()> {
-        let json = serde_json::to_string_pretty(&self.records.iter().rev().collect::<Vec<_>>())?;
-        fs::write(&self.history_file, json)?;
-        Ok(())
+        let Some(path) = &self.history_file else {
+            return Ok(());
+        };
+        storage::save_json(path, &self.records.iter().rev().collect::<Vec<_>>())
     }
 
-    /// 从文件加载历史记录
+    /// 从文件加载历史记录，文件损坏时隔离原文件，避免被新记录覆盖
     fn load_from_file(&mut self) -> Result<()> {
-        if !Path::new(&self.history_file).exists() {
+        let Some(path) = &self.history_file else {
             return Ok(());
-        }
-        
-        let content = fs::read_to_string(&self.history_file)?;
-        let records: Vec<MaintenanceRecord> = serde_json::from_str(&content)?;
+        };
+        let Some(records) = storage::load_json_or_quarantine::<Vec<MaintenanceRecord>>(path, "维护历史文件") else {
+            return Ok(());
+        };
         
         // 限制记录数量
         // JSON 是最新的在前 [3, 2, 1]
@@ -156,21 +231,13 @@ impl MaintenanceHistory {
 
     /// 格式化记录为可读文本
     pub fn format_record(&self, record: &MaintenanceRecord) -> String {
-        let result_icon = match record.result {
-            MaintenanceResult::Success => "✅",
-            MaintenanceResult::Failed => "❌", 
-            MaintenanceResult::Partial => "⚠️",
-        };
+        let result_icon = record.result.icon();
         
-        let timestamp = record.timestamp.format("%Y-%m-%d %H:%M:%S UTC");
+        let timestamp = record.format_time(&record.timestamp, "%Y-%m-%d %H:%M:%S %Z");
         let mut text = format!("{} [{}] {}\n📅 时间: {}\n📝 输出:\n{}", 
             result_icon, 
             record.task_type,
-            match record.result {
-                MaintenanceResult::Success => "成功",
-                MaintenanceResult::Failed => "失败",
-                MaintenanceResult::Partial => "部分成功",
-            },
+            record.result.label(),
             timestamp,
             record.output
         );
@@ -178,6 +245,17 @@ impl MaintenanceHistory {
         if let Some(ref error) = record.error_message {
             text.push_str(&format!("\n❌ 错误: {}", error));
         }
+
+        if record.attempts.len() > 1 {
+            text.push_str(&format!("\n🔁 共尝试 {} 次:", record.attempts.len()));
+            for attempt in &record.attempts {
+                let outcome = match &attempt.error {
+                    Some(error) => format!("❌ {}", error),
+                    None => "✅ 成功".to_string(),
+                };
+                text.push_str(&format!("\n   • 第 {} 次 ({}): {}", attempt.attempt, record.format_time(&attempt.started_at, "%H:%M:%S"), outcome));
+            }
+        }
         
         text
     }
@@ -208,12 +286,8 @@ impl MaintenanceHistory {
         if !recent_records.is_empty() {
             summary.push_str("📋 最近记录:\n\n");
             for (i, record) in recent_records.iter().enumerate() {
-                let result_icon = match record.result {
-                    MaintenanceResult::Success => "✅",
-                    MaintenanceResult::Failed => "❌",
-                    MaintenanceResult::Partial => "⚠️",
-                };
-                let timestamp = record.timestamp.format("%m-%d %H:%M");
+                let result_icon = record.result.icon();
+                let timestamp = record.format_time(&record.timestamp, "%m-%d %H:%M");
                 summary.push_str(&format!("{}. {} [{}] {}\n", 
                     i + 1, 
                     result_icon, 
@@ -236,9 +310,11 @@ pub static MAINTENANCE_HISTORY: Lazy<Arc<Mutex<MaintenanceHistory>>> = Lazy::new
     Arc::new(Mutex::new(MaintenanceHistory::new(100))) // 保存最近100条记录
 });
 
-/// 初始化维护历史管理器
-pub async fn init_maintenance_history() -> Result<()> {
-    let history = MaintenanceHistory::new(100);
+/// 初始化维护历史管理器，从状态目录加载历史文件
+pub async fn init_maintenance_history(state_dir: &Path) -> Result<()> {
+    let path = state_dir.join(HISTORY_FILE_NAME);
+    storage::migrate_legacy_file(&path);
+    let history = MaintenanceHistory::new_with_path(100, path);
     let mut history_guard = MAINTENANCE_HISTORY.lock().await;
     *history_guard = history;
     Ok(())
@@ -261,6 +337,35 @@ pub async fn record_maintenance(
     history_guard.add_record(record);
 }
 
+/// 记录带有多次尝试的维护操作
+pub async fn record_maintenance_with_attempts(
+    task_type: &str,
+    result: MaintenanceResult,
+    output: &str,
+    error_message: Option<&str>,
+    attempts: Vec<MaintenanceAttempt>,
+    task_id: Option<TaskId>,
+    timezone: Option<Tz>,
+) {
+    let mut history_guard = MAINTENANCE_HISTORY.lock().await;
+    let mut record = MaintenanceRecord::new(
+        task_type.to_string(),
+        result,
+        output.to_string(),
+        error_message.map(|s| s.to_string()),
+    );
+    record.attempts = attempts;
+    record.task_id = task_id;
+    record.timezone = timezone;
+    history_guard.add_record(record);
+}
+
+/// 指定任务最近一次成功执行的输出，用于判断本次执行是否有变化
+pub async fn last_successful_output(task_id: TaskId) -> Option<String> {
+    let history_guard = MAINTENANCE_HISTORY.lock().await;
+    history_guard.last_successful_output(task_id).map(str::to_string)
+}
+
 /// 获取维护历史摘要
 pub async fn get_maintenance_summary() -> String {
     let history_guard = MAINTENANCE_HISTORY.lock().await;
@@ -305,7 +410,7 @@ pub async fn get_maintenance_history_details(page: usize, page_size: usize) -> (
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::{DateTime, Utc, Duration};
+    use chrono::{DateTime, Utc};
     use tempfile::{NamedTempFile};
     use std::fs;
     use tempfile::TempDir;
@@ -366,7 +471,7 @@ mod tests {
         let history = MaintenanceHistory::new(50);
         assert_eq!(history.max_records, 50);
         assert!(history.records.is_empty());
-        assert_eq!(history.history_file, "maintenance_history.json");
+        assert_eq!(history.history_file, None);
     }
 
     #[test]
@@ -556,6 +661,9 @@ mod tests {
             result: MaintenanceResult::Success,
             output: "测试输出内容".to_string(),
             error_message: None,
+            attempts: Vec::new(),
+            timezone: None,
+            task_id: None,
         };
         
         let formatted = history.format_record(&record);
@@ -564,7 +672,9 @@ mod tests {
         assert!(formatted.contains("[测试任务]"));
         assert!(formatted.contains("成功"));
         assert!(formatted.contains("测试输出内容"));
-        assert!(formatted.contains(&timestamp.format("%Y-%m-%d %H:%M:%S UTC").to_string()));
+        // 时间按全局默认时区显示
+        let local_timestamp = timestamp.with_timezone(&timezone::default_timezone());
+        assert!(formatted.contains(&local_timestamp.format("%Y-%m-%d %H:%M:%S %Z").to_string()));
         
         // 测试带错误的记录
         let record_with_error = MaintenanceRecord {
@@ -574,6 +684,9 @@ mod tests {
             result: MaintenanceResult::Failed,
             output: "错误输出".to_string(),
             error_message: Some("具体错误信息".to_string()),
+            attempts: Vec::new(),
+            timezone: None,
+            task_id: None,
         };
         
         let formatted_error = history.format_record(&record_with_error);
@@ -581,6 +694,69 @@ mod tests {
         assert!(formatted_error.contains("失败"));
         assert!(formatted_error.contains("❌ 错误:"));
         assert!(formatted_error.contains("具体错误信息"));
+        assert!(!formatted_error.contains("共尝试"));
+
+        // 测试多次尝试的记录
+        let mut retried = record_with_error.clone();
+        retried.result = MaintenanceResult::Success;
+        retried.attempts = vec![
+            MaintenanceAttempt { attempt: 1, started_at: timestamp, error: Some("网络连接失败: timeout".to_string()) },
+            MaintenanceAttempt { attempt: 2, started_at: timestamp, error: None },
+        ];
+        let formatted_retried = history.format_record(&retried);
+        assert!(formatted_retried.contains("🔁 共尝试 2 次"));
+        assert!(formatted_retried.contains("第 1 次"));
+        assert!(formatted_retried.contains("网络连接失败: timeout"));
+        assert!(formatted_retried.contains("✅ 成功"));
+    }
+
+    #[test]
+    fn test_last_successful_output_keyed_by_task_id() {
+        let (mut history, _temp) = create_history_with_temp(10);
+        let record = |task_type: &str, task_id, result, output: &str| {
+            let mut record = MaintenanceRecord::new(task_type.to_string(), result, output.to_string(), None);
+            record.task_id = task_id;
+            record
+        };
+
+        history.add_record(record("🔄 系统维护", Some(1), MaintenanceResult::Success, "第一次"));
+        history.add_record(record("🔄 系统维护", Some(2), MaintenanceResult::Success, "另一个任务"));
+        history.add_record(record("🔄 系统维护", None, MaintenanceResult::Success, "手动维护"));
+        // 修改标签后显示名称变化，仍按 ID 找到上次的输出
+        history.add_record(record("🔄 系统维护 [夜间]", Some(1), MaintenanceResult::Failed, "失败"));
+
+        assert_eq!(history.last_successful_output(1), Some("第一次"));
+        assert_eq!(history.last_successful_output(2), Some("另一个任务"));
+        assert_eq!(history.last_successful_output(3), None);
+
+        history.add_record(record("🔄 系统维护 [夜间]", Some(1), MaintenanceResult::Success, "第二次"));
+        assert_eq!(history.last_successful_output(1), Some("第二次"));
+    }
+
+    #[test]
+    fn test_maintenance_history_format_record_in_task_timezone() {
+        let (history, _temp) = create_history_with_temp(10);
+        let timestamp = DateTime::parse_from_rfc3339("2026-10-01T20:00:00Z").unwrap().with_timezone(&Utc);
+        let mut record = MaintenanceRecord::new("东京任务".to_string(), MaintenanceResult::Success, "完成".to_string(), None);
+        record.timestamp = timestamp;
+        record.timezone = Some(chrono_tz::Asia::Tokyo);
+        record.attempts = vec![
+            MaintenanceAttempt { attempt: 1, started_at: timestamp, error: Some("网络连接失败: timeout".to_string()) },
+            MaintenanceAttempt { attempt: 2, started_at: timestamp, error: None },
+        ];
+
+        let formatted = history.format_record(&record);
+        assert!(formatted.contains("2026-10-02 05:00:00 JST"));
+        assert!(formatted.contains("第 1 次 (05:00:00)"));
+
+        let mut history = history;
+        history.add_record(record);
+        assert!(history.generate_summary().contains("[东京任务] 10-02 05:00"));
+
+        // 时区随记录一起保存
+        let json = serde_json::to_string(&history.records[0]).unwrap();
+        let loaded: MaintenanceRecord = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.timezone, Some(chrono_tz::Asia::Tokyo));
     }
 
     #[test]
@@ -675,6 +851,28 @@ mod tests {
         assert_eq!(history.records.len(), 0);
     }
 
+    #[test]
+    fn test_maintenance_history_corrupt_file_is_quarantined() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(HISTORY_FILE_NAME);
+        fs::write(&path, "[{\"id\": 1, \"timest").unwrap();
+
+        // 损坏的历史文件被隔离，新记录不会覆盖它
+        let mut history = MaintenanceHistory::new_with_path(10, path.clone());
+        assert!(history.records.is_empty());
+        history.add_record(MaintenanceRecord::new("任务".to_string(), MaintenanceResult::Success, "输出".to_string(), None));
+
+        let mut names: Vec<String> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names.len(), 2);
+        assert_eq!(names[0], HISTORY_FILE_NAME);
+        assert!(names[1].starts_with("maintenance_history.json.corrupt-"));
+        assert_eq!(MaintenanceHistory::new_with_path(10, path).records.len(), 1);
+    }
+
     #[test]
     fn test_maintenance_history_save_preserves_order() {
         let temp_dir = TempDir::new().unwrap();
@@ -731,6 +929,9 @@ mod tests {
                 result: MaintenanceResult::Success,
                 output: "输出1".to_string(),
                 error_message: None,
+                attempts: Vec::new(),
+                timezone: None,
+                task_id: None,
             },
             MaintenanceRecord {
                 id: 2,
@@ -739,6 +940,9 @@ mod tests {
                 result: MaintenanceResult::Success,
                 output: "输出2".to_string(),
                 error_message: None,
+                attempts: Vec::new(),
+                timezone: None,
+                task_id: None,
             },
             MaintenanceRecord {
                 id: 3,
@@ -747,6 +951,9 @@ mod tests {
                 result: MaintenanceResult::Success,
                 output: "输出3".to_string(),
                 error_message: None,
+                attempts: Vec::new(),
+                timezone: None,
+                task_id: None,
             },
             MaintenanceRecord {
                 id: 4,
@@ -755,6 +962,9 @@ mod tests {
                 result: MaintenanceResult::Success,
                 output: "输出4".to_string(),
                 error_message: None,
+                attempts: Vec::new(),
+                timezone: None,
+                task_id: None,
             },
             MaintenanceRecord {
                 id: 5,
@@ -763,6 +973,9 @@ mod tests {
                 result: MaintenanceResult::Success,
                 output: "输出5".to_string(),
                 error_message: None,
+                attempts: Vec::new(),
+                timezone: None,
+                task_id: None,
             },
         ];
         
@@ -771,7 +984,7 @@ mod tests {
         
         // 加载时限制为3条记录
         let mut history = MaintenanceHistory::new(3);
-        history.history_file = temp_path.to_string();
+        history.history_file = Some(PathBuf::from(temp_path));
         let _ = history.load_from_file();
         
         assert_eq!(history.records.len(), 3);
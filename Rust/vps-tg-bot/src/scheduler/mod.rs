@@ -1,43 +1,151 @@
 use tokio_cron_scheduler::{JobScheduler, Job, JobSchedulerError};
 use teloxide::Bot;
 use crate::config::Config;
-use crate::scheduler::task_types::{TaskType, ScheduledTask};
+use crate::scheduler::task_types::{TaskType, TaskId, ScheduledTask, RebootPolicy, MissedRunPolicy, ExecutionOptions, SCHEDULED_INITIATOR};
+use crate::scheduler::custom::{CustomCommand, CustomCommandId};
+use crate::scheduler::pipeline::{Pipeline, PipelineId};
+use crate::scheduler::schedule::Schedule;
+use crate::scheduler::window::{MaintenanceWindows, OutsideWindowPolicy};
+use crate::scheduler::maintenance_history::MaintenanceResult;
+use crate::scheduler::notification::NotificationPolicy;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use teloxide::payloads::SendMessageSetters;
+use teloxide::prelude::Requester;
+use teloxide::types::ChatId;
 use anyhow::Result;
 use serde::{Serialize, Deserialize};
 use std::fs;
 use std::path::Path;
 use tokio::sync::Mutex;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use once_cell::sync::Lazy;
-
-
+use uuid::Uuid;
+
+
+pub mod cron;
+pub mod custom;
+pub mod events;
+pub mod jobs;
+pub mod notification;
+pub mod pipeline;
+pub mod retry;
+pub mod schedule;
+pub mod storage;
 pub mod task_types;
+pub mod timezone;
+pub mod window;
 pub mod maintenance_history;
 
 #[cfg(test)]
 mod integration_tests;
 
+/// 状态目录中的调度器状态文件名
+pub const STATE_FILE_NAME: &str = "scheduler_state.json";
+
+/// 状态文件格式版本，格式变化时递增并在 `SchedulerState::migrate` 中添加迁移步骤
+pub const STATE_VERSION: u32 = 1;
+
+/// 状态文件由更新版本的程序写入，当前版本无法安全读取
+#[derive(Debug, thiserror::Error)]
+#[error("状态文件版本 {found} 高于当前支持的版本 {STATE_VERSION}，可能由更新版本的程序写入")]
+pub struct NewerStateVersion {
+    pub found: u64,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SchedulerState {
+    /// 状态文件格式版本，旧版本文件没有该字段，视为版本 0
+    #[serde(default)]
+    pub version: u32,
     pub tasks: Vec<ScheduledTask>,
+    /// 用户定义的任务流水线
+    #[serde(default = "default_pipelines")]
+    pub pipelines: Vec<Pipeline>,
+    /// 用户定义的自定义命令
+    #[serde(default)]
+    pub custom_commands: Vec<CustomCommand>,
+    /// 下一个分配的任务 ID
+    #[serde(default = "first_task_id")]
+    pub next_task_id: TaskId,
+    /// 下一个分配的流水线 ID
+    #[serde(default = "first_pipeline_id")]
+    pub next_pipeline_id: PipelineId,
+    /// 下一个分配的自定义命令 ID
+    #[serde(default = "first_command_id")]
+    pub next_command_id: CustomCommandId,
+    /// 上次记录的公网 IP，公网 IP 变化事件以此为基准
+    #[serde(default)]
+    pub public_ip: Option<String>,
+}
+
+fn default_pipelines() -> Vec<Pipeline> {
+    vec![Pipeline::full_maintenance()]
+}
+
+fn first_task_id() -> TaskId {
+    1
+}
+
+fn first_pipeline_id() -> PipelineId {
+    1
+}
+
+fn first_command_id() -> CustomCommandId {
+    1
+}
+
+/// 为没有 ID 或 ID 重复的条目分配新 ID，返回分配的数量
+fn assign_ids<T>(items: &mut [T], next_id: &mut u64, id: impl Fn(&mut T) -> &mut u64) -> usize {
+    let max_id = items.iter_mut().map(|item| *id(item)).max().unwrap_or(0);
+    *next_id = (*next_id).max(max_id + 1);
+
+    let mut seen = HashSet::new();
+    let mut assigned = 0;
+    for item in items.iter_mut() {
+        let item_id = id(item);
+        if *item_id == 0 || !seen.insert(*item_id) {
+            *item_id = *next_id;
+            *next_id += 1;
+            seen.insert(*item_id);
+            assigned += 1;
+        }
+    }
+    assigned
+}
+
+fn task_not_found(id: TaskId) -> anyhow::Error {
+    anyhow::anyhow!("任务 #{} 不存在", id)
 }
 
 impl SchedulerState {
     pub fn new() -> Self {
-        Self {
-            tasks: vec![
-                ScheduledTask::new(TaskType::SystemMaintenance, "0 4 * * Sun"),
-            ],
+        let mut state = Self {
+            version: STATE_VERSION,
+            tasks: Vec::new(),
+            pipelines: Vec::new(),
+            custom_commands: Vec::new(),
+            next_task_id: first_task_id(),
+            next_pipeline_id: first_pipeline_id(),
+            next_command_id: first_command_id(),
+            public_ip: None,
+        };
+        for pipeline in default_pipelines() {
+            state.set_pipeline(pipeline);
         }
+        state.add_task(ScheduledTask::new(TaskType::SystemMaintenance, "0 4 * * Sun"));
+        state
     }
 
     pub fn default() -> Self {
         Self::new()
     }
 
+    /// 原子写入状态文件
     pub fn save_to_file(&self, path: &str) -> Result<()> {
         let json = serde_json::to_string_pretty(self)?;
-        fs::write(path, json)?;
+        storage::write_atomic(Path::new(path), json.as_bytes())?;
         Ok(())
     }
 
@@ -46,67 +154,359 @@ impl SchedulerState {
             return Ok(SchedulerState::default());
         }
         let content = fs::read_to_string(path)?;
-        let state: SchedulerState = serde_json::from_str(&content)?;
+        let value: serde_json::Value = serde_json::from_str(&content)?;
+        let version = value.get("version").and_then(serde_json::Value::as_u64).unwrap_or(0);
+        if version > u64::from(STATE_VERSION) {
+            return Err(NewerStateVersion { found: version }.into());
+        }
+        let mut state: SchedulerState = serde_json::from_value(value)?;
+        let migrated = state.migrate();
+        let converted = state.convert_legacy_cron_expressions();
+        if migrated || converted > 0 {
+            state.save_to_file(path)?;
+        }
         Ok(state)
     }
 
-    pub fn add_task(&mut self, task: ScheduledTask) {
+    /// 把旧版本保存的 6 字段 Cron 表达式转换为 5 字段，返回转换的任务数
+    fn convert_legacy_cron_expressions(&mut self) -> usize {
+        let mut converted = 0;
+        for task in &mut self.tasks {
+            if let Some(expression) = cron::from_legacy_expression(&task.cron_expression) {
+                log::info!(
+                    "{} 的 Cron 表达式已从 '{}' 转换为 '{}'",
+                    task.task_type.get_display_name(),
+                    task.cron_expression,
+                    expression
+                );
+                task.cron_expression = expression;
+                converted += 1;
+            }
+        }
+        converted
+    }
+
+    /// 加载状态文件；文件无法解析时移到隔离文件并使用默认状态，返回需要通知的告警。
+    /// 文件由更新版本的程序写入时返回错误，原文件保持不动
+    pub fn load_or_quarantine(path: &str) -> Result<(Self, Option<String>)> {
+        let error = match Self::load_from_file(path) {
+            Ok(state) => return Ok((state, None)),
+            Err(e) => e,
+        };
+        if error.downcast_ref::<NewerStateVersion>().is_some() {
+            return Err(error);
+        }
+        if error.downcast_ref::<std::io::Error>().is_some() {
+            log::error!("读取调度器状态文件 {} 失败: {}", path, error);
+            let warning = format!("⚠️ 无法读取调度器状态文件 {}: {}\n本次使用默认任务运行", path, error);
+            return Ok((Self::default(), Some(warning)));
+        }
+
+        log::error!("调度器状态文件 {} 已损坏: {}", path, error);
+        let warning = match storage::quarantine(Path::new(path)) {
+            Ok(target) => format!(
+                "⚠️ 调度器状态文件已损坏: {}\n原文件已移至 {}，已使用默认任务重新开始",
+                error,
+                target.display()
+            ),
+            Err(e) => {
+                log::error!("隔离损坏的状态文件失败: {}", e);
+                format!("⚠️ 调度器状态文件已损坏: {}\n隔离原文件失败: {}，已使用默认任务运行", error, e)
+            }
+        };
+        Ok((Self::default(), Some(warning)))
+    }
+
+    /// 把旧版本的状态迁移到当前版本，返回是否有变化
+    fn migrate(&mut self) -> bool {
+        let from = self.version;
+        if self.version < 1 {
+            // 版本 1：任务、流水线和自定义命令使用稳定 ID
+            let assigned = self.assign_missing_ids();
+            log::info!("已为 {} 个旧任务分配 ID", assigned);
+            let assigned = assign_ids(&mut self.pipelines, &mut self.next_pipeline_id, |pipeline| &mut pipeline.id);
+            log::info!("已为 {} 个流水线分配 ID", assigned);
+            let assigned = assign_ids(&mut self.custom_commands, &mut self.next_command_id, |command| &mut command.id);
+            log::info!("已为 {} 个自定义命令分配 ID", assigned);
+            self.version = 1;
+        }
+        if self.version != from {
+            log::info!("调度器状态已从版本 {} 迁移到版本 {}", from, self.version);
+        }
+        self.version != from
+    }
+
+    /// 为旧状态文件中没有 ID 或 ID 重复的任务分配新 ID，返回分配的数量
+    pub fn assign_missing_ids(&mut self) -> usize {
+        assign_ids(&mut self.tasks, &mut self.next_task_id, |task| &mut task.id)
+    }
+
+    /// 添加任务并分配 ID
+    pub fn add_task(&mut self, mut task: ScheduledTask) -> TaskId {
+        let id = self.next_task_id;
+        self.next_task_id += 1;
+        task.id = id;
         self.tasks.push(task);
+        id
     }
 
-    #[allow(dead_code)]
-    pub fn remove_task(&mut self, index: usize) -> Result<()> {
-        if index < self.tasks.len() {
-            self.tasks.remove(index);
-            Ok(())
-        } else {
-            Err(anyhow::anyhow!("任务索引超出范围"))
+    pub fn remove_task(&mut self, id: TaskId) -> Result<ScheduledTask> {
+        let index = self.tasks.iter().position(|task| task.id == id).ok_or_else(|| task_not_found(id))?;
+        Ok(self.tasks.remove(index))
+    }
+
+    pub fn get_task(&self, id: TaskId) -> Option<&ScheduledTask> {
+        self.tasks.iter().find(|task| task.id == id)
+    }
+
+    fn task_mut(&mut self, id: TaskId) -> Result<&mut ScheduledTask> {
+        self.tasks.iter_mut().find(|task| task.id == id).ok_or_else(|| task_not_found(id))
+    }
+
+    pub fn update_task(&mut self, id: TaskId, new_cron: &str) -> Result<()> {
+        // 验证 Cron 表达式
+        cron::parse(new_cron).map_err(|e| anyhow::anyhow!(e))?;
+
+        let task = self.task_mut(id)?;
+        task.cron_expression = new_cron.to_string();
+        task.schedule = Schedule::Cron;
+        // 新表达式之前的触发时间不算错过
+        task.missed_checked_at = Some(Utc::now());
+        Ok(())
+    }
+
+    pub fn toggle_task(&mut self, id: TaskId) -> Result<()> {
+        let task = self.task_mut(id)?;
+        task.enabled = !task.enabled;
+        // 暂停期间的触发时间不算错过
+        task.missed_checked_at = Some(Utc::now());
+        Ok(())
+    }
+
+    /// 设置或清除任务标签
+    pub fn set_task_label(&mut self, id: TaskId, label: Option<String>) -> Result<()> {
+        self.task_mut(id)?.label = label;
+        Ok(())
+    }
+
+    /// 切换任务的重启策略，返回新的策略
+    pub fn cycle_reboot_policy(&mut self, id: TaskId) -> Result<RebootPolicy> {
+        let task = self.task_mut(id)?;
+        if !task.task_type.can_reboot() {
+            return Err(anyhow::anyhow!("{} 不会重启系统", task.task_type.get_display_name()));
         }
+        task.reboot_policy = task.reboot_policy.next();
+        Ok(task.reboot_policy)
     }
 
-    #[allow(dead_code)]
-    pub fn get_task(&self, index: usize) -> Option<&ScheduledTask> {
-        self.tasks.get(index)
+    pub fn get_pipeline(&self, name: &str) -> Option<&Pipeline> {
+        self.pipelines.iter().find(|pipeline| pipeline.name == name)
     }
 
-    #[allow(dead_code)]
-    pub fn update_task(&mut self, index: usize, new_cron: &str) -> Result<()> {
-        if index < self.tasks.len() {
-            // 验证 Cron 表达式
-            let validator = SchedulerValidator::new();
-            if let Err(_validation_error) = validator.validate_cron_expression(new_cron) {
-                // 验证失败，但仍然更新（让JobScheduler处理验证）
+    pub fn get_pipeline_by_id(&self, id: PipelineId) -> Option<&Pipeline> {
+        self.pipelines.iter().find(|pipeline| pipeline.id == id)
+    }
+
+    /// 添加或替换同名流水线，替换时保留原 ID，返回是否为替换
+    pub fn set_pipeline(&mut self, mut pipeline: Pipeline) -> bool {
+        match self.pipelines.iter_mut().find(|existing| existing.name == pipeline.name) {
+            Some(existing) => {
+                pipeline.id = existing.id;
+                *existing = pipeline;
+                true
+            }
+            None => {
+                pipeline.id = self.next_pipeline_id;
+                self.next_pipeline_id += 1;
+                self.pipelines.push(pipeline);
+                false
             }
-            
-            self.tasks[index].cron_expression = new_cron.to_string();
-            Ok(())
-        } else {
-            Err(anyhow::anyhow!("任务索引超出范围"))
         }
     }
 
-    #[allow(dead_code)]
-    pub fn toggle_task(&mut self, index: usize) -> Result<()> {
-        if index < self.tasks.len() {
-            self.tasks[index].enabled = !self.tasks[index].enabled;
-            Ok(())
-        } else {
-            Err(anyhow::anyhow!("任务索引超出范围"))
+    /// 删除流水线，仍被定时任务引用时拒绝
+    pub fn remove_pipeline(&mut self, name: &str) -> Result<()> {
+        if self.tasks.iter().any(|task| task.task_type == TaskType::Pipeline(name.to_string())) {
+            return Err(anyhow::anyhow!("流水线「{}」仍被定时任务使用，请先删除相关任务", name));
+        }
+        let before = self.pipelines.len();
+        self.pipelines.retain(|pipeline| pipeline.name != name);
+        if self.pipelines.len() == before {
+            return Err(anyhow::anyhow!("流水线「{}」不存在", name));
+        }
+        Ok(())
+    }
+
+    pub fn get_custom_command_by_id(&self, id: CustomCommandId) -> Option<&CustomCommand> {
+        self.custom_commands.iter().find(|command| command.id == id)
+    }
+
+    /// 添加或替换同名自定义命令（替换时保留原 ID），同时更新使用该命令的定时任务，返回被更新的任务数
+    pub fn set_custom_command(&mut self, mut command: CustomCommand) -> usize {
+        let mut updated = 0;
+        for task in &mut self.tasks {
+            if matches!(&task.task_type, TaskType::Custom { name, .. } if *name == command.name) {
+                task.task_type = command.to_task_type();
+                updated += 1;
+            }
+        }
+        match self.custom_commands.iter_mut().find(|existing| existing.name == command.name) {
+            Some(existing) => {
+                command.id = existing.id;
+                *existing = command;
+            }
+            None => {
+                command.id = self.next_command_id;
+                self.next_command_id += 1;
+                self.custom_commands.push(command);
+            }
+        }
+        updated
+    }
+
+    /// 删除自定义命令，仍被定时任务引用时拒绝
+    pub fn remove_custom_command(&mut self, name: &str) -> Result<()> {
+        if self.tasks.iter().any(|task| matches!(&task.task_type, TaskType::Custom { name: task_name, .. } if task_name == name)) {
+            return Err(anyhow::anyhow!("自定义命令「{}」仍被定时任务使用，请先删除相关任务", name));
+        }
+        let before = self.custom_commands.len();
+        self.custom_commands.retain(|command| command.name != name);
+        if self.custom_commands.len() == before {
+            return Err(anyhow::anyhow!("自定义命令「{}」不存在", name));
+        }
+        Ok(())
+    }
+
+    /// 切换任务的通知策略，返回新的策略
+    pub fn cycle_notification_policy(&mut self, id: TaskId) -> Result<NotificationPolicy> {
+        let task = self.task_mut(id)?;
+        task.notification_policy = task.notification_policy.next();
+        Ok(task.notification_policy)
+    }
+
+    /// 切换任务的静默送达，返回是否开启
+    pub fn toggle_silent_delivery(&mut self, id: TaskId) -> Result<bool> {
+        let task = self.task_mut(id)?;
+        task.silent_delivery = !task.silent_delivery;
+        Ok(task.silent_delivery)
+    }
+
+    /// 切换任务的错过执行策略，返回新的策略
+    pub fn cycle_missed_run_policy(&mut self, id: TaskId) -> Result<MissedRunPolicy> {
+        let task = self.task_mut(id)?;
+        task.missed_run_policy = task.missed_run_policy.next();
+        Ok(task.missed_run_policy)
+    }
+
+    /// 记录任务的执行结果；任务已被删除时返回 false
+    pub fn record_run(&mut self, id: TaskId, started_at: DateTime<Utc>, result: MaintenanceResult) -> bool {
+        match self.task_mut(id) {
+            Ok(task) => {
+                task.last_run = Some(started_at);
+                task.last_result = Some(result);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// 记录已处理到的时间点，被跳过或已开始执行的触发都不算错过
+    pub fn mark_checked(&mut self, id: TaskId, at: DateTime<Utc>) -> bool {
+        match self.task_mut(id) {
+            Ok(task) => {
+                task.missed_checked_at = Some(at);
+                true
+            }
+            Err(_) => false,
         }
     }
 
+    /// 记录事件任务的触发时间
+    pub fn mark_triggered(&mut self, id: TaskId, at: DateTime<Utc>) -> bool {
+        match self.task_mut(id) {
+            Ok(task) => {
+                task.last_triggered_at = Some(at);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// 一次性任务执行后停用或删除，返回是否删除了任务
+    pub fn finish_one_shot(&mut self, id: TaskId) -> bool {
+        let remove = match self.task_mut(id) {
+            Ok(task) => match task.schedule {
+                Schedule::Once { remove_after_run, .. } => {
+                    task.enabled = false;
+                    remove_after_run
+                }
+                _ => return false,
+            },
+            Err(_) => return false,
+        };
+        if remove {
+            self.tasks.retain(|task| task.id != id);
+        }
+        remove
+    }
+
+    /// 找出停机期间错过执行的任务，并把检查时间推进到 `now`
+    pub fn take_missed_runs(&mut self, now: DateTime<chrono::Local>) -> Vec<(TaskId, usize, DateTime<Tz>)> {
+        let mut missed = Vec::new();
+        for task in self.tasks.iter_mut() {
+            if let Some((count, latest)) = task.missed_runs(now) {
+                missed.push((task.id, count, latest));
+            }
+            task.missed_checked_at = Some(now.with_timezone(&Utc));
+        }
+        missed
+    }
+
     pub fn get_all_tasks_summary(&self) -> String {
         if self.tasks.is_empty() {
             return "📝 暂无定时任务".to_string();
         }
 
+        let now = chrono::Local::now();
         let mut summary = String::new();
         summary.push_str("⏰ 定时任务列表:\n\n");
         
-        for (i, task) in self.tasks.iter().enumerate() {
+        for task in &self.tasks {
             let status = if task.enabled { "✅" } else { "⏸️" };
-            summary.push_str(&format!("{}. {} {}\n   Cron: {}\n\n", 
-                i + 1, status, task.task_type.get_display_name(), task.cron_expression));
+            summary.push_str(&format!("{} {}\n   {} ({})\n", 
+                status, task.title(), task.describe_schedule(), task.timezone()));
+            if task.label.is_some() {
+                summary.push_str(&format!("   类型: {}\n", task.task_type.get_display_name()));
+            }
+            if task.enabled {
+                let runs = task.next_runs(now, cron::NEXT_RUNS_PREVIEW);
+                if !runs.is_empty() {
+                    let runs: Vec<String> = runs.iter().map(cron::format_run_time).collect();
+                    summary.push_str(&format!("   下次执行: {}\n", runs.join(", ")));
+                }
+            }
+            summary.push_str(&format!("   上次执行: {}\n", task.describe_last_run()));
+            if task.task_type.can_reboot() {
+                summary.push_str(&format!("   重启策略: {}\n", task.reboot_policy.get_display_name()));
+            }
+            summary.push_str(&format!("   错过执行: {}\n", task.missed_run_policy.get_display_name()));
+            summary.push_str(&format!("   通知: {}{}\n", task.notification_policy.get_display_name(),
+                if task.silent_delivery { "（静默送达）" } else { "" }));
+            summary.push('\n');
+        }
+
+        if !self.pipelines.is_empty() {
+            summary.push_str("🧩 流水线:\n");
+            for pipeline in &self.pipelines {
+                summary.push_str(&format!("• {}: {}\n", pipeline.name, pipeline.describe()));
+            }
+        }
+
+        if !self.custom_commands.is_empty() {
+            summary.push_str("⚙️ 自定义命令:\n");
+            for command in &self.custom_commands {
+                summary.push_str(&format!("• {}: {}\n", command.name, command.describe()));
+            }
         }
         
         summary
@@ -118,158 +518,572 @@ pub struct SchedulerManager {
     pub scheduler: Arc<Mutex<Option<JobScheduler>>>, 
     pub state: Arc<Mutex<SchedulerState>>,
     pub state_path: String,
+    /// 正在等待维护窗口的任务，避免同一任务重复推迟
+    deferred: Arc<Mutex<HashSet<TaskId>>>,
+    /// 任务对应的调度任务 UUID，增删改任务时只替换受影响的调度任务
+    jobs: Arc<Mutex<HashMap<TaskId, Uuid>>>,
+    /// 启动时的配置和 Bot，重新创建调度任务时沿用，不再重新读取配置
+    config: Config,
+    bot: Bot,
 }
 
 impl SchedulerManager {
-    pub async fn new(config: Config, bot: Bot, state_path: String) -> Result<Self, JobSchedulerError> {
-        let state = SchedulerState::load_from_file(&state_path).unwrap_or_else(|_| SchedulerState::default());
-        
+    pub async fn new(config: Config, bot: Bot, state_path: String) -> Result<Self> {
+        let (state, warning) = match SchedulerState::load_or_quarantine(&state_path) {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                log::error!("无法加载调度器状态文件 {}: {}", state_path, e);
+                let message = format!("❌ 调度器未启动: {}\n请升级程序后再启动，状态文件 {} 未作改动", e, state_path);
+                let _ = bot.send_message(ChatId(config.chat_id), message).await;
+                return Err(e);
+            }
+        };
+        if let Some(warning) = warning {
+            let _ = bot.send_message(ChatId(config.chat_id), warning).await;
+        }
+
         let sched = JobScheduler::new().await?;
         let scheduler = Arc::new(Mutex::new(Some(sched)));
         let state = Arc::new(Mutex::new(state.clone()));
         
-        let manager = Self { scheduler, state, state_path };
-        let _ = manager.start_all_tasks(config, bot).await;
+        let manager = Self { scheduler, state, state_path, deferred: Arc::default(), jobs: Arc::default(), config, bot };
+        let _ = manager.start_all_tasks().await;
         
         Ok(manager)
     }
 
-    pub async fn start_all_tasks(&self, config: Config, bot: Bot) -> Result<(), JobSchedulerError> {
-        let state = self.state.lock().await;
-        let tasks = state.tasks.clone();
-        drop(state);
-        
-        let mut scheduler_guard = self.scheduler.lock().await;
-        if let Some(sched) = scheduler_guard.as_mut() {
-            // 清除现有任务
-            let _ = sched.shutdown().await;
-            *scheduler_guard = Some(JobScheduler::new().await?);
-            
-            let sched = scheduler_guard.as_mut().unwrap();
-            
-            // 添加所有启用的任务
-            for task in tasks.iter() {
-                if task.enabled {
-                    // 自动适配 5 字段 cron 表达式（补充秒位）
-                    let cron_expr = if task.cron_expression.split_whitespace().count() == 5 {
-                        format!("0 {}", task.cron_expression)
-                    } else {
-                        task.cron_expression.clone()
-                    };
-
-                    let job = Job::new_async_tz(cron_expr.as_str(), chrono::Local, {
-                        let bot = bot.clone();
-                        let task_type = task.task_type.clone();
-                        let chat_id = config.chat_id;
-
-                        move |_uuid, _l| {
-                            let bot = bot.clone();
-                            let task_type = task_type.clone();
-                            
-                            Box::pin(async move {
-                                log::info!("执行定时任务: {:?}", task_type);
-                                match task_type.execute(&bot, chat_id).await {
-                                    Ok(_) => {},
-                                    Err(e) => {
-                                        eprintln!("任务执行失败: {}", e);
-                                    }
-                                }
-                            })
-                        }
-                    });
+    pub async fn start_all_tasks(&self) -> Result<(), JobSchedulerError> {
+        let tasks = self.state.lock().await.tasks.clone();
+
+        // 清除现有任务
+        let ids: Vec<TaskId> = self.jobs.lock().await.keys().copied().collect();
+        for id in ids {
+            self.unschedule_task(id).await;
+        }
+
+        // 添加所有启用的任务
+        for task in &tasks {
+            self.schedule_task(task).await;
+        }
 
-                    match job {
-                        Ok(j) => {
-                            if let Err(e) = sched.add(j).await { // Changed `scheduler.add` to `sched.add`
-                                log::error!("添加任务失败: {:?}", e);
-                            }
-                        },
-                        Err(e) => log::error!("创建任务失败 (Cron: {}): {:?}", cron_expr, e),
+        if let Some(sched) = self.scheduler.lock().await.as_ref() {
+            sched.start().await?;
+        }
+
+        Ok(())
+    }
+
+    /// 为启用的任务创建调度任务并记录 UUID
+    async fn schedule_task(&self, task: &ScheduledTask) {
+        // 事件任务由事件监视器触发，不创建调度任务
+        if !task.enabled || matches!(task.schedule, Schedule::Event { .. }) {
+            return;
+        }
+        let job = match self.build_job(task) {
+            Ok(Some(job)) => job,
+            Ok(None) => {
+                log::info!("定时任务 {} 本次运行期间不再触发", task.title());
+                return;
+            }
+            Err(e) => {
+                log::error!("跳过无效的定时任务 ({}): {}", task.title(), e);
+                return;
+            }
+        };
+
+        let scheduler_guard = self.scheduler.lock().await;
+        let Some(sched) = scheduler_guard.as_ref() else {
+            return;
+        };
+        match sched.add(job).await {
+            Ok(uuid) => {
+                self.jobs.lock().await.insert(task.id, uuid);
+            }
+            Err(e) => log::error!("添加任务 {} 失败: {:?}", task.title(), e),
+        }
+    }
+
+    /// 移除任务对应的调度任务，不影响其他任务和正在执行的任务
+    async fn unschedule_task(&self, id: TaskId) {
+        let Some(uuid) = self.jobs.lock().await.remove(&id) else {
+            return;
+        };
+        if let Some(sched) = self.scheduler.lock().await.as_ref() {
+            if let Err(e) = sched.remove(&uuid).await {
+                log::warn!("移除任务 #{} 的调度失败: {:?}", id, e);
+            }
+        }
+    }
+
+    /// 按任务的最新设置重新创建调度任务，任务已删除时只移除
+    async fn reschedule_task(&self, id: TaskId) {
+        self.unschedule_task(id).await;
+        let task = self.state.lock().await.get_task(id).cloned();
+        if let Some(task) = task {
+            self.schedule_task(&task).await;
+        }
+    }
+
+    /// 按任务的执行计划创建调度任务，一次性计划已经过期时返回 None
+    fn build_job(&self, task: &ScheduledTask) -> Result<Option<Job>, String> {
+        let id = task.id;
+        let run = {
+            let manager = self.clone();
+            let bot = self.bot.clone();
+            let config = self.config.clone();
+            let task_type = task.task_type.clone();
+            let options = execution_options(task, &config);
+            move || {
+                let manager = manager.clone();
+                let bot = bot.clone();
+                let config = config.clone();
+                let task_type = task_type.clone();
+                let options = options.clone();
+                Box::pin(async move {
+                    manager.run_task(id, task_type, bot, config, options).await;
+                }) as std::pin::Pin<Box<dyn std::future::Future<Output = ()> + Send>>
+            }
+        };
+
+        let job = match &task.schedule {
+            Schedule::Cron => {
+                let cron_expr = cron::to_scheduler_expression(&task.cron_expression)?;
+                Job::new_async_tz(cron_expr.as_str(), task.timezone(), move |_uuid, _l| run())
+            }
+            Schedule::Interval { .. } => {
+                // 定期检查是否到达对齐的触发时间，重启后仍按基准时间对齐
+                let schedule = task.schedule.clone();
+                let checked_until = Arc::new(std::sync::Mutex::new(chrono::Local::now()));
+                Job::new_repeated_async(std::time::Duration::from_secs(schedule::MIN_INTERVAL_SECS), move |_uuid, _l| {
+                    let now = chrono::Local::now();
+                    let since = std::mem::replace(&mut *checked_until.lock().unwrap(), now);
+                    if schedule.fire_times("", since, now, 1).is_empty() {
+                        Box::pin(async {})
+                    } else {
+                        run()
                     }
+                })
+            }
+            Schedule::Once { .. } | Schedule::AfterStart { .. } => match task.schedule.delay_until_fire(Utc::now()) {
+                Some(delay) => Job::new_one_shot_async(delay, move |_uuid, _l| run()),
+                None => return Ok(None),
+            },
+            Schedule::Event { .. } => return Ok(None),
+        };
+        job.map(Some).map_err(|e| format!("创建任务失败: {:?}", e))
+    }
+
+    /// 执行任务并把结果写回任务状态
+    async fn run_task(&self, id: TaskId, task_type: TaskType, bot: Bot, config: Config, mut options: ExecutionOptions) {
+        log::info!("执行定时任务 #{}: {:?}", id, task_type);
+        if !self.wait_for_window(id, &task_type, &bot, &config, &mut options).await {
+            self.update_after_run(id, &task_type, None).await;
+            return;
+        }
+        if let TaskType::Pipeline(name) = &task_type {
+            // 执行时读取最新的流水线定义
+            options.pipeline = self.state.lock().await.get_pipeline(name).cloned();
+        }
+        let started_at = Utc::now();
+        // 执行前先保存，维护后重启系统时本次触发不会在启动后被当作错过
+        self.mark_started(id, started_at).await;
+        let result = task_type.execute(&bot, config.chat_id, &options).await;
+        if result != MaintenanceResult::Success {
+            log::warn!("定时任务 {} 执行结果: {}", task_type.get_display_name(), result.label());
+        }
+        self.update_after_run(id, &task_type, Some((started_at, result))).await;
+    }
+
+    /// 在维护窗口外或冻结期内触发时按策略推迟或跳过，返回是否继续执行
+    async fn wait_for_window(&self, id: TaskId, task_type: &TaskType, bot: &Bot, config: &Config, options: &mut ExecutionOptions) -> bool {
+        let windows = match MaintenanceWindows::from_config(&config.scheduler) {
+            Ok(windows) => windows,
+            Err(e) => {
+                log::error!("维护窗口配置无效，忽略限制: {}", e);
+                return true;
+            }
+        };
+        // 维护窗口按全局默认时区解释
+        let now = Utc::now().with_timezone(&timezone::default_timezone());
+        let Some(reason) = windows.check(&now).reason() else {
+            return true;
+        };
+
+        let task_name = task_type.get_display_name();
+        let chat_id = ChatId(config.chat_id);
+        let notify = options.notification_policy.notifies_progress();
+        let next = match config.scheduler.outside_window_policy {
+            OutsideWindowPolicy::Defer => windows.next_allowed(&now),
+            OutsideWindowPolicy::Skip => None,
+        };
+        let Some(next) = next else {
+            log::info!("定时任务 {} {}，跳过本次执行", task_name, reason);
+            if notify {
+                let _ = bot.send_message(chat_id, format!("⏭️ [定时任务] {} {}，本次执行已跳过", task_name, reason))
+                    .disable_notification(options.disable_notification)
+                    .await;
+            }
+            return false;
+        };
+
+        if !self.deferred.lock().await.insert(id) {
+            log::info!("定时任务 {} 已有推迟中的执行，忽略本次触发", task_name);
+            return false;
+        }
+        log::info!("定时任务 {} {}，推迟到 {}", task_name, reason, next);
+        if notify {
+            let _ = bot.send_message(chat_id,
+                format!("⏳ [定时任务] {} {}，推迟到 {} 执行", task_name, reason, cron::format_run_time(&next)))
+                .disable_notification(options.disable_notification)
+                .await;
+        }
+        tokio::time::sleep((next - now).to_std().unwrap_or_default()).await;
+        self.deferred.lock().await.remove(&id);
+
+        // 等待期间任务可能已被删除或停用
+        let still_scheduled = self.state.lock().await.get_task(id)
+            .is_some_and(|task| task.enabled && task.task_type == *task_type);
+        if !still_scheduled {
+            log::info!("定时任务 {} 在推迟期间已被删除或停用", task_name);
+            return false;
+        }
+        options.deferred_from = Some(now);
+        true
+    }
+
+    /// 记录执行结果（跳过时为 None），一次性任务随后停用或删除
+    async fn update_after_run(&self, id: TaskId, task_type: &TaskType, run: Option<(DateTime<Utc>, MaintenanceResult)>) {
+        let mut state_guard = self.state.lock().await;
+        let matched = match run {
+            Some((started_at, result)) => state_guard.record_run(id, started_at, result),
+            None => state_guard.mark_checked(id, Utc::now()),
+        };
+        if matched {
+            if state_guard.get_task(id).is_some_and(|task| task.schedule.is_one_shot()) {
+                // 一次性调度任务执行后由调度器自动移除
+                self.jobs.lock().await.remove(&id);
+            }
+            if state_guard.finish_one_shot(id) {
+                log::info!("一次性任务 #{} {} 已执行并删除", id, task_type.get_display_name());
+            }
+            if let Err(e) = state_guard.save_to_file(&self.state_path) {
+                log::error!("保存任务状态失败: {}", e);
+            }
+        }
+    }
+
+    /// 保存开始执行的时间
+    async fn mark_started(&self, id: TaskId, started_at: DateTime<Utc>) {
+        let mut state_guard = self.state.lock().await;
+        if state_guard.mark_checked(id, started_at) {
+            if let Err(e) = state_guard.save_to_file(&self.state_path) {
+                log::error!("保存任务状态失败: {}", e);
+            }
+        }
+    }
+
+    /// 启动时处理停机期间错过的执行
+    pub async fn catch_up_missed_runs(&self, config: &Config, bot: &Bot) {
+        let mut state_guard = self.state.lock().await;
+        let missed = state_guard.take_missed_runs(chrono::Local::now());
+        for &(id, _, _) in &missed {
+            // 不补执行的一次性任务已经过期，直接停用；补执行的任务执行后按设置停用或删除
+            if let Ok(task) = state_guard.task_mut(id) {
+                if task.schedule.is_one_shot() && task.missed_run_policy != MissedRunPolicy::RunOnce {
+                    task.enabled = false;
                 }
             }
-            
-            let _ = sched.start().await;
         }
-        
-        Ok(())
+        let tasks = state_guard.tasks.clone();
+        if let Err(e) = state_guard.save_to_file(&self.state_path) {
+            log::error!("保存任务状态失败: {}", e);
+        }
+        drop(state_guard);
+
+        for (id, count, latest) in missed {
+            let Some(task) = tasks.iter().find(|task| task.id == id) else {
+                continue;
+            };
+            let task_name = task.title();
+            log::info!("定时任务 {} 在停机期间错过 {} 次执行，策略: {}", task_name, count, task.missed_run_policy.get_display_name());
+            let missed_text = format!("{} 在停机期间错过了 {} 次执行（最近一次: {}）", task_name, count, cron::format_run_time(&latest));
+
+            match task.missed_run_policy {
+                MissedRunPolicy::RunOnce => {
+                    let _ = bot.send_message(ChatId(config.chat_id), format!("⏰ [定时任务] {}，现在补执行一次", missed_text)).await;
+                    let manager = self.clone();
+                    let bot = bot.clone();
+                    let task_type = task.task_type.clone();
+                    let options = execution_options(task, config);
+                    let config = config.clone();
+                    tokio::spawn(async move {
+                        manager.run_task(id, task_type, bot, config, options).await;
+                    });
+                }
+                MissedRunPolicy::NotifyOnly => {
+                    let _ = bot.send_message(ChatId(config.chat_id), format!("⚠️ [定时任务] {}，未补执行", missed_text)).await;
+                }
+                MissedRunPolicy::Ignore => {}
+            }
+        }
+    }
+
+    pub async fn add_new_task(&self, task_type: TaskType, cron_expression: &str) -> Result<String, JobSchedulerError> {
+        let validator = SchedulerValidator::new();
+        if let Err(validation_error) = validator.validate_cron_expression(cron_expression) {
+            return Ok(format!("❌ {}", validation_error));
+        }
+
+        let new_task = ScheduledTask::new(task_type.clone(), cron_expression);
+        self.add_task(new_task).await
+    }
+
+    /// 添加已设置好执行计划和时区的任务
+    pub async fn add_task(&self, new_task: ScheduledTask) -> Result<String, JobSchedulerError> {
+        let display_name = new_task.get_display_name();
+        let next_runs = new_task.next_runs(chrono::Local::now(), cron::NEXT_RUNS_PREVIEW);
+
+        let mut state_guard = self.state.lock().await;
+        let id = state_guard.add_task(new_task);
+        if let Err(e) = state_guard.save_to_file(&self.state_path) {
+            log::error!("保存任务状态失败: {}", e);
+        }
+        drop(state_guard);
+
+        self.reschedule_task(id).await;
+
+        let preview = if next_runs.is_empty() {
+            "⏭️ 本次运行期间不会再触发".to_string()
+        } else {
+            cron::format_run_list(&next_runs)
+        };
+        Ok(format!("✅ 新任务已添加: {}\n\n{}", display_name, preview))
+    }
+
+    pub async fn remove_task_by_id(&self, id: TaskId) -> Result<String> {
+        let mut state_guard = self.state.lock().await;
+        let result = state_guard.remove_task(id);
+        match result {
+            Ok(task) => {
+                state_guard.save_to_file(&self.state_path)?;
+                drop(state_guard);
+
+                self.unschedule_task(id).await;
+
+                Ok(format!("✅ 任务 {} 已删除", task.title()))
+            }
+            Err(e) => {
+                Ok(format!("❌ 删除任务失败: {}", e))
+            }
+        }
+    }
+
+    #[allow(dead_code)]
+    pub async fn toggle_task_by_id(&self, id: TaskId) -> Result<String> {
+        let mut state_guard = self.state.lock().await;
+        let result = state_guard.toggle_task(id);
+        match result {
+            Ok(_) => {
+                state_guard.save_to_file(&self.state_path)?;
+                drop(state_guard);
+
+                self.reschedule_task(id).await;
+
+                Ok("✅ 任务状态已切换".to_string())
+            }
+            Err(e) => {
+                Ok(format!("❌ 切换任务状态失败: {}", e))
+            }
+        }
+    }
+
+    pub async fn cycle_reboot_policy_by_id(&self, id: TaskId) -> Result<String> {
+        let mut state_guard = self.state.lock().await;
+        match state_guard.cycle_reboot_policy(id) {
+            Ok(policy) => {
+                state_guard.save_to_file(&self.state_path)?;
+                drop(state_guard);
+
+                // 调度任务持有执行参数的副本，需要重新创建
+                self.reschedule_task(id).await;
+
+                Ok(format!("✅ 任务 #{} 的重启策略已设为: {}", id, policy.get_display_name()))
+            }
+            Err(e) => {
+                Ok(format!("❌ 设置重启策略失败: {}", e))
+            }
+        }
+    }
+
+    pub async fn cycle_missed_run_policy_by_id(&self, id: TaskId) -> Result<String> {
+        let mut state_guard = self.state.lock().await;
+        match state_guard.cycle_missed_run_policy(id) {
+            Ok(policy) => {
+                state_guard.save_to_file(&self.state_path)?;
+                Ok(format!("✅ 任务 #{} 错过执行时将: {}", id, policy.get_display_name()))
+            }
+            Err(e) => {
+                Ok(format!("❌ 设置错过执行策略失败: {}", e))
+            }
+        }
+    }
+
+    pub async fn cycle_notification_policy_by_id(&self, id: TaskId) -> Result<String> {
+        let mut state_guard = self.state.lock().await;
+        match state_guard.cycle_notification_policy(id) {
+            Ok(policy) => {
+                state_guard.save_to_file(&self.state_path)?;
+                drop(state_guard);
+
+                // 调度任务持有执行参数的副本，需要重新创建
+                self.reschedule_task(id).await;
+
+                Ok(format!("✅ 任务 #{} 的通知策略已设为: {}", id, policy.get_display_name()))
+            }
+            Err(e) => {
+                Ok(format!("❌ 设置通知策略失败: {}", e))
+            }
+        }
+    }
+
+    pub async fn toggle_silent_delivery_by_id(&self, id: TaskId) -> Result<String> {
+        let mut state_guard = self.state.lock().await;
+        match state_guard.toggle_silent_delivery(id) {
+            Ok(silent) => {
+                state_guard.save_to_file(&self.state_path)?;
+                drop(state_guard);
+
+                self.reschedule_task(id).await;
+
+                Ok(format!("✅ 任务 #{} 的通知{}", id, if silent { "将静默送达（不响铃）" } else { "将正常提醒" }))
+            }
+            Err(e) => {
+                Ok(format!("❌ 设置静默送达失败: {}", e))
+            }
+        }
     }
 
-    pub async fn add_new_task(&self, config: Config, bot: Bot, task_type: TaskType, cron_expression: &str) -> Result<String, JobSchedulerError> {
-        let validator = SchedulerValidator::new();
-        if let Err(validation_error) = validator.validate_cron_expression(cron_expression) {
-            return Ok(format!("❌ {}", validation_error));
+    /// 设置任务标签，标签为空时清除
+    pub async fn set_task_label(&self, id: TaskId, label: &str) -> Result<String> {
+        let label = Some(label.trim().to_string()).filter(|label| !label.is_empty());
+        let mut state_guard = self.state.lock().await;
+        match state_guard.set_task_label(id, label.clone()) {
+            Ok(()) => {
+                state_guard.save_to_file(&self.state_path)?;
+                Ok(match label {
+                    Some(label) => format!("✅ 任务 #{} 的标签已设为: {}", id, label),
+                    None => format!("✅ 任务 #{} 的标签已清除", id),
+                })
+            }
+            Err(e) => Ok(format!("❌ {}", e)),
         }
+    }
 
-        let new_task = ScheduledTask::new(task_type.clone(), cron_expression);
-        
+    pub async fn get_pipelines(&self) -> Vec<Pipeline> {
+        self.state.lock().await.pipelines.clone()
+    }
+
+    pub async fn get_pipeline_by_id(&self, id: PipelineId) -> Option<Pipeline> {
+        self.state.lock().await.get_pipeline_by_id(id).cloned()
+    }
+
+    pub async fn set_pipeline(&self, pipeline: Pipeline) -> Result<String> {
+        let name = pipeline.name.clone();
+        let description = pipeline.describe();
         let mut state_guard = self.state.lock().await;
-        state_guard.add_task(new_task);
-        if let Err(e) = state_guard.save_to_file(&self.state_path) {
-            log::error!("保存任务状态失败: {}", e);
+        let replaced = state_guard.set_pipeline(pipeline);
+        state_guard.save_to_file(&self.state_path)?;
+        Ok(format!("✅ 流水线「{}」已{}:\n{}", name, if replaced { "更新" } else { "添加" }, description))
+    }
+
+    pub async fn remove_pipeline(&self, name: &str) -> Result<String> {
+        let mut state_guard = self.state.lock().await;
+        match state_guard.remove_pipeline(name) {
+            Ok(()) => {
+                state_guard.save_to_file(&self.state_path)?;
+                Ok(format!("✅ 流水线「{}」已删除", name))
+            }
+            Err(e) => Ok(format!("❌ {}", e)),
         }
-        drop(state_guard);
+    }
 
-        // 重新启动调度器
-        self.restart_scheduler(config, bot).await?;
-        
-        Ok(format!("✅ 新任务已添加: {} ({})", 
-            task_type.get_display_name(), cron_expression))
+    pub async fn get_custom_commands(&self) -> Vec<CustomCommand> {
+        self.state.lock().await.custom_commands.clone()
     }
 
-    #[allow(dead_code)]
-    pub async fn remove_task_by_index(&self, config: Config, bot: Bot, index: usize) -> Result<String> {
+    /// 保存自定义命令，程序必须在白名单中；已使用该命令的定时任务会同步更新
+    pub async fn set_custom_command(&self, command: CustomCommand) -> Result<String> {
+        if let Err(e) = custom::check_allowed(&command.program, &self.config.scheduler.custom_command_allowlist) {
+            return Ok(format!("❌ {}", e));
+        }
+        let name = command.name.clone();
+        let description = command.describe();
         let mut state_guard = self.state.lock().await;
-        let result = state_guard.remove_task(index);
-        match result {
-            Ok(_) => {
-                state_guard.save_to_file(&self.state_path)?;
-                drop(state_guard);
+        let updated = state_guard.set_custom_command(command);
+        state_guard.save_to_file(&self.state_path)?;
+        let affected: Vec<TaskId> = state_guard
+            .tasks
+            .iter()
+            .filter(|task| matches!(&task.task_type, TaskType::Custom { name: task_name, .. } if *task_name == name))
+            .map(|task| task.id)
+            .collect();
+        drop(state_guard);
 
-                // 重新启动调度器
-                self.restart_scheduler(config, bot).await?;
-                
-                Ok("✅ 任务已删除".to_string())
-            }
-            Err(e) => {
-                Ok(format!("❌ 删除任务失败: {}", e))
+        let mut reply = format!("✅ 自定义命令「{}」已保存:\n{}", name, description);
+        if updated > 0 {
+            // 定时任务持有命令定义的副本，需要重新创建
+            for id in affected {
+                self.reschedule_task(id).await;
             }
+            reply.push_str(&format!("\n\n已同步更新 {} 个定时任务", updated));
         }
+        Ok(reply)
     }
 
-    #[allow(dead_code)]
-    pub async fn toggle_task_by_index(&self, config: Config, bot: Bot, index: usize) -> Result<String> {
+    pub async fn get_custom_command_by_id(&self, id: CustomCommandId) -> Option<CustomCommand> {
+        self.state.lock().await.get_custom_command_by_id(id).cloned()
+    }
+
+    pub async fn remove_custom_command(&self, name: &str) -> Result<String> {
         let mut state_guard = self.state.lock().await;
-        let result = state_guard.toggle_task(index);
-        match result {
-            Ok(_) => {
+        match state_guard.remove_custom_command(name) {
+            Ok(()) => {
                 state_guard.save_to_file(&self.state_path)?;
-                drop(state_guard);
-
-                // 重新启动调度器
-                self.restart_scheduler(config, bot).await?;
-                
-                Ok("✅ 任务状态已切换".to_string())
-            }
-            Err(e) => {
-                Ok(format!("❌ 切换任务状态失败: {}", e))
+                Ok(format!("✅ 自定义命令「{}」已删除", name))
             }
+            Err(e) => Ok(format!("❌ {}", e)),
         }
     }
 
-    #[allow(dead_code)]
-    pub async fn update_task_by_index(&self, config: Config, bot: Bot, index: usize, new_cron: &str) -> Result<String> {
+    /// 立即执行流水线（手动触发），维护锁被占用时直接拒绝
+    pub async fn run_pipeline_now(&self, config: &Config, bot: Bot, name: &str, initiator: String) -> MaintenanceResult {
+        let pipeline = self.state.lock().await.get_pipeline(name).cloned();
+        let task = ScheduledTask::new(TaskType::Pipeline(name.to_string()), "");
+        let options = ExecutionOptions {
+            pipeline,
+            initiator,
+            wait_for_lock: false,
+            ..execution_options(&task, config)
+        };
+        task.task_type.execute(&bot, config.chat_id, &options).await
+    }
+
+    pub async fn get_tasks(&self) -> Vec<ScheduledTask> {
+        self.state.lock().await.tasks.clone()
+    }
+
+    /// 修改任务的 Cron 表达式，只重建该任务的调度任务
+    pub async fn update_task_by_id(&self, id: TaskId, new_cron: &str) -> Result<String> {
         let mut state_guard = self.state.lock().await;
-        let result = state_guard.update_task(index, new_cron);
+        let result = state_guard.update_task(id, new_cron);
         match result {
             Ok(_) => {
                 state_guard.save_to_file(&self.state_path)?;
                 drop(state_guard);
 
-                // 重新启动调度器
-                self.restart_scheduler(config, bot).await?;
-                
-                Ok(format!("✅ 任务 {} 已更新为: {}", index + 1, new_cron))
+                self.reschedule_task(id).await;
+
+                Ok(format!("✅ 任务 #{} 已更新为: {}", id, new_cron))
             }
             Err(e) => {
                 Ok(format!("❌ 更新任务失败: {}", e))
@@ -277,29 +1091,35 @@ impl SchedulerManager {
         }
     }
 
-    async fn restart_scheduler(&self, config: Config, bot: Bot) -> Result<(), JobSchedulerError> {
-        let mut scheduler_guard = self.scheduler.lock().await;
-        if let Some(mut sched) = scheduler_guard.take() {
-            sched.shutdown().await?;
-        }
-        
-        let new_sched = JobScheduler::new().await?;
-        *scheduler_guard = Some(new_sched);
-        
-        // 重新启动所有任务
-        drop(scheduler_guard);
-        self.start_all_tasks(config, bot).await?;
-        
-        Ok(())
-    }
-
     pub async fn get_tasks_summary(&self) -> String {
         let state_guard = self.state.lock().await;
         state_guard.get_all_tasks_summary()
     }
 }
 
-// Cron 表达式验证器
+/// 根据任务设置和全局配置生成执行参数
+fn execution_options(task: &ScheduledTask, config: &Config) -> ExecutionOptions {
+    ExecutionOptions {
+        reboot_policy: task.reboot_policy,
+        retry_policy: task.retry_policy.clone(),
+        timeout: task
+            .task_type
+            .timeout_override()
+            .unwrap_or_else(|| config.scheduler.task_timeout(task.task_type.key())),
+        pipeline: None,
+        initiator: SCHEDULED_INITIATOR.to_string(),
+        wait_for_lock: true,
+        custom_allowlist: config.scheduler.custom_command_allowlist.clone(),
+        deferred_from: None,
+        notification_policy: task.notification_policy,
+        disable_notification: task.silent_delivery,
+        trigger: None,
+        timezone: task.timezone,
+        task_id: (task.id != 0).then_some(task.id),
+    }
+}
+
+// Cron 表达式验证器，委托给 cron 模块
 pub struct SchedulerValidator;
 
 impl SchedulerValidator {
@@ -307,135 +1127,38 @@ impl SchedulerValidator {
         Self
     }
 
+    /// 使用与调度器一致的解析器校验 5 字段 Cron 表达式
     pub fn validate_cron_expression(&self, cron_expr: &str) -> Result<(), String> {
-        let fields: Vec<&str> = cron_expr.split_whitespace().collect();
-        
-        // 检查字段数量
-        if fields.len() != 5 {
-            return Err(format!("无效的 Cron 表达式。应为 5 个字段（分钟 小时 日 月 周几），当前有 {} 个字段", fields.len()));
-        }
-        
-        let (minute, hour, day, month, weekday) = (fields[0], fields[1], fields[2], fields[3], fields[4]);
-        
-        // 验证分钟字段 (0-59)
-        if !self.is_valid_field(minute, 0, 59) {
-            return Err(format!("分钟字段无效。应在 0-59 之间，当前值: {}", minute));
-        }
-        
-        // 验证小时字段 (0-23)
-        if !self.is_valid_field(hour, 0, 23) {
-            return Err(format!("小时字段无效。应在 0-23 之间，当前值: {}", hour));
-        }
-        
-        // 验证日期字段 (1-31)
-        if !self.is_valid_field(day, 1, 31) {
-            return Err(format!("日期字段无效。应在 1-31 之间，当前值: {}", day));
-        }
-        
-        // 验证月份字段 (1-12)
-        if !self.is_valid_field(month, 1, 12) {
-            return Err(format!("月份字段无效。应在 1-12 之间，当前值: {}", month));
-        }
-        
-        // 验证星期字段 (0-7, 0 和 7 都表示周日)
-        if !self.is_valid_weekday_field(weekday) {
-            return Err(format!("星期字段无效。应在 0-7 之间（0 和 7 都表示周日），当前值: {}", weekday));
-        }
-        
-        Ok(())
-    }
-    
-    fn is_valid_field(&self, field: &str, min: i32, max: i32) -> bool {
-        // 处理特殊字符 *
-        if field == "*" {
-            return true;
-        }
-        
-        // 处理列表 (如: 1,3,5)
-        if field.contains(',') {
-            return field.split(',').all(|part| self.is_valid_single_value(part, min, max));
-        }
-        
-        // 处理范围 (如: 1-5)
-        if field.contains('-') {
-            let parts: Vec<&str> = field.split('-').collect();
-            if parts.len() != 2 {
-                return false;
-            }
-            return self.is_valid_single_value(parts[0], min, max) && 
-                   self.is_valid_single_value(parts[1], min, max);
-        }
-        
-        // 处理步长 (如: */5 或 1-10/2)
-        if field.contains('/') {
-            let parts: Vec<&str> = field.split('/').collect();
-            if parts.len() != 2 {
-                return false;
-            }
-            let base = parts[0];
-            let step = parts[1];
-            
-            // 步长必须是数字
-            if step.parse::<i32>().is_err() {
-                return false;
-            }
-            
-            // 基础部分可以是 * 或具体值或范围
-            if base == "*" {
-                return true;
-            }
-            if base.contains('-') {
-                let base_parts: Vec<&str> = base.split('-').collect();
-                if base_parts.len() != 2 {
-                    return false;
-                }
-                return self.is_valid_single_value(base_parts[0].trim(), min, max) && 
-                       self.is_valid_single_value(base_parts[1].trim(), min, max);
-            }
-            return self.is_valid_single_value(base, min, max);
-        }
-        
-        // 单个数字值
-        self.is_valid_single_value(field, min, max)
-    }
-    
-    fn is_valid_single_value(&self, value: &str, min: i32, max: i32) -> bool {
-        if let Ok(num) = value.parse::<i32>() {
-            num >= min && num <= max
-        } else {
-            false
-        }
-    }
-    
-    fn is_valid_weekday_field(&self, field: &str) -> bool {
-        // 特殊处理星期字段，接受数字和缩写
-        if field == "*" {
-            return true;
-        }
-        
-        // 处理缩写 (Sun, Mon, Tue, Wed, Thu, Fri, Sat)
-        let weekdays = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
-        if weekdays.contains(&field) {
-            return true;
-        }
-        
-        // 使用通用的字段验证逻辑，但范围是 0-7
-        self.is_valid_field(field, 0, 7)
+        cron::parse(cron_expr).map(|_| ())
     }
 }
 
 // 全局调度器管理器实例
 pub static SCHEDULER_MANAGER: Lazy<Arc<Mutex<Option<SchedulerManager>>>> = Lazy::new(|| Arc::new(Mutex::new(None)));
 
-pub async fn start_scheduler(config: Config, bot: Bot) -> Result<(), JobSchedulerError> {
+pub async fn start_scheduler(config: Config, bot: Bot) -> Result<()> {
     log::info!("⏰ 开始初始化调度器...");
+    // 启动后延迟执行的任务以此刻为基准
+    Lazy::force(&schedule::BOT_STARTED_AT);
+    timezone::set_default_timezone(Some(config.scheduler.timezone()));
+    log::info!("🌐 任务默认时区: {}", timezone::default_timezone());
     
-    let manager = SchedulerManager::new(config.clone(), bot.clone(), "scheduler_state.json".to_string()).await?;
+    let state_path = config.scheduler.state_dir.join(STATE_FILE_NAME);
+    storage::migrate_legacy_file(&state_path);
+    log::info!("📁 调度器状态文件: {}", state_path.display());
+
+    let manager = SchedulerManager::new(config.clone(), bot.clone(), state_path.to_string_lossy().into_owned()).await?;
     let mut manager_guard = SCHEDULER_MANAGER.lock().await;
-    *manager_guard = Some(manager);
+    *manager_guard = Some(manager.clone());
     drop(manager_guard);
+    events::start_event_watcher(manager, config.clone(), bot.clone());
     
     log::info!("✅ 调度器初始化完成");
+
+    // 补偿停机期间错过的执行
+    if let Some(manager) = &*SCHEDULER_MANAGER.lock().await {
+        manager.catch_up_missed_runs(&config, &bot).await;
+    }
     
     // 添加关闭处理器
     if let Some(manager) = &mut *SCHEDULER_MANAGER.lock().await {
@@ -452,6 +1175,7 @@ pub async fn start_scheduler(config: Config, bot: Bot) -> Result<(), JobSchedule
     Ok(())
 }
 
+
 pub async fn get_tasks_summary() -> Result<String> {
     let manager_guard = SCHEDULER_MANAGER.lock().await;
     if let Some(manager) = &*manager_guard {
@@ -461,15 +1185,92 @@ pub async fn get_tasks_summary() -> Result<String> {
     }
 }
 
+pub async fn get_pipelines() -> Vec<Pipeline> {
+    let manager_guard = SCHEDULER_MANAGER.lock().await;
+    match &*manager_guard {
+        Some(manager) => manager.get_pipelines().await,
+        None => Vec::new(),
+    }
+}
+
+/// 按 ID 查找流水线，已删除或调度器未初始化时返回 None
+pub async fn get_pipeline_by_id(id: PipelineId) -> Option<Pipeline> {
+    let manager_guard = SCHEDULER_MANAGER.lock().await;
+    match &*manager_guard {
+        Some(manager) => manager.get_pipeline_by_id(id).await,
+        None => None,
+    }
+}
+
+pub async fn get_custom_commands() -> Vec<CustomCommand> {
+    let manager_guard = SCHEDULER_MANAGER.lock().await;
+    match &*manager_guard {
+        Some(manager) => manager.get_custom_commands().await,
+        None => Vec::new(),
+    }
+}
+
+/// 按 ID 查找自定义命令，已删除或调度器未初始化时返回 None
+pub async fn get_custom_command_by_id(id: CustomCommandId) -> Option<CustomCommand> {
+    let manager_guard = SCHEDULER_MANAGER.lock().await;
+    match &*manager_guard {
+        Some(manager) => manager.get_custom_command_by_id(id).await,
+        None => None,
+    }
+}
+
+pub async fn get_tasks() -> Vec<ScheduledTask> {
+    let manager_guard = SCHEDULER_MANAGER.lock().await;
+    match &*manager_guard {
+        Some(manager) => manager.get_tasks().await,
+        None => Vec::new(),
+    }
+}
+
+/// 拆分计划前的 `tz=时区` 选项，例如 `tz=Asia/Shanghai 0 4 * * *`
+fn split_timezone(plan: &str) -> Result<(Option<Tz>, &str), String> {
+    match plan.split_once(char::is_whitespace) {
+        Some((option, rest)) => match option.strip_prefix("tz=") {
+            Some(name) => Ok((Some(timezone::parse(name)?), rest.trim())),
+            None => Ok((None, plan)),
+        },
+        None => Ok((None, plan)),
+    }
+}
+
 // 向后兼容的函数
-pub async fn update_schedule(new_cron: &str) -> Result<String> {
+/// 添加定时任务，`text` 为 `[任务标识] [tz=时区] 计划`，计划可以是 Cron 表达式、`every 6h`、
+/// `once 2026-11-02 03:00 [remove]` 或 `boot 10m`；未指定任务标识时添加系统维护任务
+pub async fn update_schedule(text: &str) -> Result<String> {
     let manager_guard = SCHEDULER_MANAGER.lock().await;
     if let Some(manager) = &*manager_guard {
-        // 使用第一个任务的类型来保持兼容性
-        let config = Config::load().unwrap_or_else(|_| Config { bot_token: "".to_string(), chat_id: 0, check_interval: 300 });
-        let bot = Bot::new(config.bot_token.clone());
-        
-        match manager.add_new_task(config, bot, TaskType::SystemMaintenance, new_cron).await {
+        let text = text.trim();
+        let (task_type, plan) = match text.split_once(char::is_whitespace) {
+            Some((key, rest)) => match TaskType::from_key(key) {
+                Some(task_type) => (task_type, rest.trim()),
+                None => (TaskType::SystemMaintenance, text),
+            },
+            None => (TaskType::SystemMaintenance, text),
+        };
+        let (task_timezone, plan) = match split_timezone(plan) {
+            Ok(parsed) => parsed,
+            Err(e) => return Ok(format!("❌ {}", e)),
+        };
+
+        let timezone = task_timezone.unwrap_or_else(timezone::default_timezone);
+        let mut task = match Schedule::parse(plan, Utc::now(), &timezone) {
+            Some(Ok(schedule)) => ScheduledTask::with_schedule(task_type, schedule),
+            Some(Err(e)) => return Ok(format!("❌ {}", e)),
+            None => {
+                if let Err(e) = SchedulerValidator::new().validate_cron_expression(plan) {
+                    return Ok(format!("❌ {}", e));
+                }
+                ScheduledTask::new(task_type, plan)
+            }
+        };
+        task.timezone = task_timezone;
+        let result = manager.add_task(task).await;
+        match result {
             Ok(msg) => Ok(msg),
             Err(e) => Ok(format!("❌ 更新调度失败: {}", e))
         }
@@ -512,7 +1313,7 @@ mod tests {
         let mut state = SchedulerState::new();
         
         // 移除存在的任务
-        let result = state.remove_task(0);
+        let result = state.remove_task(1);
         assert!(result.is_ok());
         assert_eq!(state.tasks.len(), 0);
         
@@ -526,7 +1327,7 @@ mod tests {
         let state = SchedulerState::new();
         
         // 获取存在的任务
-        let task = state.get_task(0);
+        let task = state.get_task(1);
         assert!(task.is_some());
         assert_eq!(task.unwrap().task_type, TaskType::SystemMaintenance);
         
@@ -540,7 +1341,7 @@ mod tests {
         let mut state = SchedulerState::new();
         
         // 更新存在的任务
-        let result = state.update_task(0, "0 6 * * *");
+        let result = state.update_task(1, "0 6 * * *");
         assert!(result.is_ok());
         assert_eq!(state.tasks[0].cron_expression, "0 6 * * *");
         
@@ -549,7 +1350,7 @@ mod tests {
         assert!(result.is_err());
         
         // 尝试更新为无效的Cron表达式
-        let result = state.update_task(0, "invalid_cron");
+        let result = state.update_task(1, "invalid_cron");
         assert!(result.is_err());
     }
 
@@ -561,12 +1362,12 @@ mod tests {
         assert!(state.tasks[0].enabled);
         
         // 切换任务状态
-        let result = state.toggle_task(0);
+        let result = state.toggle_task(1);
         assert!(result.is_ok());
         assert!(!state.tasks[0].enabled);
         
         // 再次切换
-        let result = state.toggle_task(0);
+        let result = state.toggle_task(1);
         assert!(result.is_ok());
         assert!(state.tasks[0].enabled);
         
@@ -575,9 +1376,314 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_scheduler_state_cycle_reboot_policy() {
+        let mut state = SchedulerState::new();
+        state.add_task(ScheduledTask::new(TaskType::UpdateXray, "0 6 * * Sun"));
+
+        assert_eq!(state.cycle_reboot_policy(1).unwrap(), RebootPolicy::Always);
+        assert!(state.get_all_tasks_summary().contains("重启策略: 总是重启"));
+        assert_eq!(state.cycle_reboot_policy(1).unwrap(), RebootPolicy::Never);
+
+        // 不会重启的任务和不存在的任务都不能设置
+        assert!(state.cycle_reboot_policy(2).is_err());
+        assert!(state.cycle_reboot_policy(5).is_err());
+    }
+
+    #[test]
+    fn test_scheduler_state_missed_runs_and_last_run() {
+        use chrono::{Duration, Local};
+
+        let mut state = SchedulerState::new();
+        state.tasks[0].cron_expression = "0 * * * *".to_string();
+        let now = Local::now();
+
+        // 停机 3 小时：上次执行在 3 小时前，之后错过了整点触发
+        let last_run = (now - Duration::hours(3)).with_timezone(&Utc);
+        assert!(state.record_run(1, last_run, MaintenanceResult::Success));
+        assert!(!state.record_run(99, last_run, MaintenanceResult::Success));
+        state.tasks[0].missed_checked_at = None;
+
+        let missed = state.take_missed_runs(now);
+        assert_eq!(missed.len(), 1);
+        let (id, count, latest) = missed[0];
+        assert_eq!(id, 1);
+        assert!((2..=3).contains(&count));
+        assert!(latest <= now && latest > now - Duration::hours(1));
+
+        // 已检查过的时间段不会重复补偿
+        assert!(state.take_missed_runs(now).is_empty());
+
+        let summary = state.get_all_tasks_summary();
+        assert!(summary.contains("上次执行: "));
+        assert!(summary.contains("✅ 成功"));
+        assert!(summary.contains("错过执行: 仅通知"));
+        assert_eq!(state.cycle_missed_run_policy(1).unwrap(), MissedRunPolicy::Ignore);
+    }
+
+    #[test]
+    fn test_run_interrupted_by_reboot_is_not_missed() {
+        use chrono::{Duration, Local, TimeZone};
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let path = temp_file.path().to_str().unwrap();
+
+        let mut state = SchedulerState::new();
+        state.tasks[0].cron_expression = "0 4 * * *".to_string();
+        let fire_time = Local.with_ymd_and_hms(2026, 10, 17, 4, 0, 0).unwrap();
+        state.tasks[0].missed_checked_at = Some((fire_time - Duration::days(1)).with_timezone(&Utc));
+
+        // 开始执行时保存，执行中系统重启，没有机会记录结果
+        assert!(state.mark_checked(1, fire_time.with_timezone(&Utc)));
+        state.save_to_file(path).unwrap();
+
+        let mut restarted = SchedulerState::load_from_file(path).unwrap();
+        assert!(restarted.take_missed_runs(fire_time + Duration::minutes(10)).is_empty());
+    }
+
+    #[test]
+    fn test_scheduler_state_pipelines() {
+        // 旧版状态文件没有 pipelines 字段，加载后带上默认的完整维护流水线
+        let state: SchedulerState = serde_json::from_str(r#"{"tasks": []}"#).unwrap();
+        assert_eq!(state.pipelines, vec![Pipeline::full_maintenance()]);
+
+        let mut state = SchedulerState::new();
+        let pipeline = Pipeline::parse_definition("每周维护 core_maintenance! rules_maintenance?").unwrap();
+        assert!(!state.set_pipeline(pipeline.clone()));
+        assert!(state.set_pipeline(pipeline));
+        assert_eq!(state.pipelines.len(), 2);
+
+        let id = state.add_task(ScheduledTask::new(TaskType::Pipeline("每周维护".to_string()), "0 4 * * Sun"));
+        assert!(state.remove_pipeline("每周维护").is_err());
+        state.remove_task(id).unwrap();
+        assert!(state.remove_pipeline("每周维护").is_ok());
+        assert!(state.remove_pipeline("每周维护").is_err());
+        assert!(state.get_all_tasks_summary().contains("🧩 流水线:"));
+    }
+
+    #[test]
+    fn test_pipeline_ids_are_stable_and_assigned_on_load() {
+        let mut state = SchedulerState::new();
+        assert_eq!(state.pipelines[0].id, 1);
+        let weekly = Pipeline::parse_definition("每周维护 core_maintenance").unwrap();
+        state.set_pipeline(weekly.clone());
+        state.set_pipeline(Pipeline::parse_definition("更新 update_xray").unwrap());
+
+        // 删除前面的流水线后，其余流水线的 ID 不变；替换同名流水线保留 ID
+        state.remove_pipeline(pipeline::FULL_MAINTENANCE_PIPELINE).unwrap();
+        assert!(state.get_pipeline_by_id(1).is_none());
+        assert_eq!(state.get_pipeline_by_id(3).unwrap().name, "更新");
+        assert!(state.set_pipeline(Pipeline::parse_definition("每周维护 rules_maintenance").unwrap()));
+        assert_eq!(state.get_pipeline("每周维护").unwrap().id, 2);
+        state.remove_pipeline("每周维护").unwrap();
+        state.set_pipeline(weekly);
+        assert_eq!(state.get_pipeline("每周维护").unwrap().id, 4);
+
+        // 旧状态文件中的流水线没有 ID
+        let temp_file = NamedTempFile::new().unwrap();
+        let path = temp_file.path().to_str().unwrap();
+        let legacy = r#"{"tasks": [], "pipelines": [
+            {"name": "a", "steps": [{"task_type": "UpdateXray"}]},
+            {"name": "b", "steps": [{"task_type": "UpdateSingbox"}]}
+        ], "custom_commands": [{"name": "c", "program": "/usr/bin/true"}]}"#;
+        fs::write(path, legacy).unwrap();
+        let state = SchedulerState::load_from_file(path).unwrap();
+        let ids: Vec<PipelineId> = state.pipelines.iter().map(|pipeline| pipeline.id).collect();
+        assert_eq!(ids, vec![1, 2]);
+        assert_eq!(state.next_pipeline_id, 3);
+        assert_eq!((state.custom_commands[0].id, state.next_command_id), (1, 2));
+        assert_eq!(SchedulerState::load_from_file(path).unwrap().pipelines[1].id, 2);
+    }
+
+    #[test]
+    fn test_scheduler_state_custom_commands() {
+        let mut state = SchedulerState::new();
+        let command = CustomCommand::parse_definition("重载 /usr/bin/systemctl reload nginx").unwrap();
+        assert_eq!(state.set_custom_command(command.clone()), 0);
+        let id = state.add_task(ScheduledTask::new(command.to_task_type(), "0 4 * * *"));
+
+        // 修改定义后已有任务同步更新
+        let updated = CustomCommand::parse_definition("重载 timeout=60 /usr/bin/systemctl reload caddy").unwrap();
+        assert_eq!(state.set_custom_command(updated.clone()), 1);
+        assert_eq!(state.custom_commands, vec![CustomCommand { id: 1, ..updated.clone() }]);
+        assert_eq!(state.tasks[1].task_type, updated.to_task_type());
+        assert_eq!(state.get_custom_command_by_id(1).unwrap().name, "重载");
+
+        assert!(state.remove_custom_command("重载").is_err());
+        state.remove_task(id).unwrap();
+        assert!(state.remove_custom_command("重载").is_ok());
+        assert!(state.remove_custom_command("重载").is_err());
+        // 删除后重新定义的命令使用新 ID，旧按钮不会指向它
+        state.set_custom_command(command);
+        assert!(state.get_custom_command_by_id(1).is_none());
+        assert_eq!(state.custom_commands[0].id, 2);
+
+        // 旧版状态文件没有 custom_commands 字段
+        let state: SchedulerState = serde_json::from_str(r#"{"tasks": []}"#).unwrap();
+        assert!(state.custom_commands.is_empty());
+    }
+
+    #[test]
+    fn test_task_ids_are_stable_and_migrated() {
+        let mut state = SchedulerState::new();
+        let xray = state.add_task(ScheduledTask::new(TaskType::UpdateXray, "0 6 * * *"));
+        let rules = state.add_task(ScheduledTask::new(TaskType::RulesMaintenance, "0 7 * * *"));
+        assert_eq!((xray, rules), (2, 3));
+
+        // 删除前面的任务后，其余任务的 ID 不变，已删除的 ID 不再复用
+        state.remove_task(1).unwrap();
+        assert!(state.remove_task(1).is_err());
+        assert_eq!(state.get_task(rules).unwrap().task_type, TaskType::RulesMaintenance);
+        assert_eq!(state.add_task(ScheduledTask::new(TaskType::UpdateSingbox, "0 8 * * *")), 4);
+
+        state.set_task_label(xray, Some("夜间更新".to_string())).unwrap();
+        assert_eq!(state.get_task(xray).unwrap().title(), "#2 夜间更新");
+        assert!(state.get_all_tasks_summary().contains("#2 夜间更新"));
+
+        // 旧状态文件中的任务没有 ID
+        let temp_file = NamedTempFile::new().unwrap();
+        let path = temp_file.path().to_str().unwrap();
+        let legacy = r#"{"tasks": [
+            {"task_type": "SystemMaintenance", "cron_expression": "0 4 * * Sun", "enabled": true},
+            {"task_type": "UpdateXray", "cron_expression": "0 6 * * *", "enabled": false}
+        ]}"#;
+        fs::write(path, legacy).unwrap();
+
+        let state = SchedulerState::load_from_file(path).unwrap();
+        let ids: Vec<TaskId> = state.tasks.iter().map(|task| task.id).collect();
+        assert_eq!(ids, vec![1, 2]);
+        assert_eq!(state.next_task_id, 3);
+        assert_eq!(state.version, STATE_VERSION);
+        // 迁移结果写回文件，再次加载时 ID 保持不变
+        let reloaded = SchedulerState::load_from_file(path).unwrap();
+        assert_eq!(reloaded.tasks[1].id, 2);
+        assert_eq!(reloaded.next_task_id, 3);
+    }
+
+    #[test]
+    fn test_state_file_versions_and_quarantine() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("state").join(STATE_FILE_NAME);
+        let path = path.to_str().unwrap();
+
+        // 状态目录不存在时自动创建，写入当前版本
+        SchedulerState::new().save_to_file(path).unwrap();
+        let (state, warning) = SchedulerState::load_or_quarantine(path).unwrap();
+        assert!(warning.is_none());
+        assert_eq!(state.version, STATE_VERSION);
+
+        // 写了一半的文件被隔离，而不是被默认状态静默覆盖
+        fs::write(path, r#"{"tasks": [{"task_type": "#).unwrap();
+        let (state, warning) = SchedulerState::load_or_quarantine(path).unwrap();
+        assert_eq!(state.tasks.len(), 1);
+        assert!(warning.unwrap().contains(".corrupt-"));
+        assert!(!Path::new(path).exists());
+        let quarantined: Vec<_> = fs::read_dir(temp_dir.path().join("state")).unwrap().collect();
+        assert_eq!(quarantined.len(), 1);
+
+        // 更新版本的程序写入的文件不会被当作旧版本加载，也不会被当作损坏文件隔离
+        let newer = format!(r#"{{"version": {}, "tasks": []}}"#, STATE_VERSION + 1);
+        fs::write(path, &newer).unwrap();
+        assert!(SchedulerState::load_from_file(path).unwrap_err().to_string().contains("版本"));
+        let error = SchedulerState::load_or_quarantine(path).unwrap_err();
+        assert!(error.downcast_ref::<NewerStateVersion>().is_some());
+        assert_eq!(fs::read_to_string(path).unwrap(), newer);
+        assert_eq!(fs::read_dir(temp_dir.path().join("state")).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_notification_settings_are_persisted() {
+        let mut state = SchedulerState::new();
+        assert_eq!(state.cycle_notification_policy(1).unwrap(), NotificationPolicy::FailuresOnly);
+        assert_eq!(state.cycle_notification_policy(1).unwrap(), NotificationPolicy::ChangesOnly);
+        assert!(state.toggle_silent_delivery(1).unwrap());
+        assert!(state.cycle_notification_policy(99).is_err());
+        assert!(state.get_all_tasks_summary().contains("通知: 仅有变化（静默送达）"));
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let path = temp_file.path().to_str().unwrap();
+        state.save_to_file(path).unwrap();
+        let loaded = SchedulerState::load_from_file(path).unwrap();
+        assert_eq!(loaded.tasks[0].notification_policy, NotificationPolicy::ChangesOnly);
+        assert!(loaded.tasks[0].silent_delivery);
+
+        let options = execution_options(&loaded.tasks[0], &Config {
+            bot_token: String::new(),
+            chat_id: 0,
+            check_interval: 300,
+            monitor: Default::default(),
+            scheduler: Default::default(),
+        });
+        assert_eq!(options.notification_policy, NotificationPolicy::ChangesOnly);
+        assert!(options.disable_notification);
+    }
+
+    #[test]
+    fn test_split_timezone_option() {
+        let (timezone, plan) = split_timezone("tz=Asia/Shanghai 0 4 * * *").unwrap();
+        assert_eq!(timezone, Some(chrono_tz::Asia::Shanghai));
+        assert_eq!(plan, "0 4 * * *");
+
+        assert_eq!(split_timezone("every 6h").unwrap(), (None, "every 6h"));
+        assert!(split_timezone("tz=Asia/Nowhere 0 4 * * *").is_err());
+
+        // 任务时区随状态文件保存，旧状态文件没有时区时使用全局默认时区
+        let mut task = ScheduledTask::new(TaskType::UpdateXray, "0 4 * * *");
+        task.timezone = timezone;
+        let json = serde_json::to_string(&task).unwrap();
+        assert!(json.contains("Asia/Shanghai"));
+        let restored: ScheduledTask = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.timezone(), chrono_tz::Asia::Shanghai);
+        let legacy: ScheduledTask = serde_json::from_str(&json.replace(r#""timezone":"Asia/Shanghai","#, "")).unwrap();
+        assert_eq!(legacy.timezone, None);
+        assert_eq!(legacy.timezone(), timezone::default_timezone());
+    }
+
+    #[test]
+    fn test_one_shot_tasks_finish_after_run() {
+        use chrono::Duration;
+
+        let mut state = SchedulerState::new();
+        let at = Utc::now() + Duration::hours(1);
+        state.add_task(ScheduledTask::with_schedule(TaskType::UpdateXray, Schedule::Once { at, remove_after_run: false }));
+        state.add_task(ScheduledTask::with_schedule(TaskType::UpdateSingbox, Schedule::Once { at, remove_after_run: true }));
+
+        // 普通任务和不存在的任务不受影响
+        assert!(!state.finish_one_shot(1));
+        assert!(state.tasks[0].enabled);
+        assert!(!state.finish_one_shot(99));
+
+        assert!(!state.finish_one_shot(2));
+        assert!(!state.tasks[1].enabled);
+        assert!(state.finish_one_shot(3));
+        assert_eq!(state.tasks.len(), 2);
+
+        // 停机期间错过的单次任务
+        let mut task = ScheduledTask::with_schedule(TaskType::RulesMaintenance, Schedule::Once { at: Utc::now() - Duration::minutes(5), remove_after_run: false });
+        task.missed_checked_at = Some(Utc::now() - Duration::hours(1));
+        assert_eq!(task.missed_runs(chrono::Local::now()).map(|(count, _)| count), Some(1));
+        assert!(task.get_display_name().contains("单次: "));
+    }
+
+    #[test]
+    fn test_missed_runs_ignore_paused_and_new_tasks() {
+        use chrono::{Duration, Local};
+
+        let now = Local::now();
+        let mut task = ScheduledTask::new(TaskType::RulesMaintenance, "* * * * *");
+        // 新建任务从创建时刻开始计算
+        assert!(task.missed_runs(now).is_none());
+        assert_eq!(task.describe_last_run(), "从未执行");
+
+        task.missed_checked_at = Some((now - Duration::minutes(10)).with_timezone(&Utc));
+        assert!(task.missed_runs(now).is_some());
+        task.enabled = false;
+        assert!(task.missed_runs(now).is_none());
+    }
+
     #[test]
     fn test_scheduler_state_get_all_tasks_summary_empty() {
-        let state = SchedulerState { tasks: vec![] };
+        let state = SchedulerState { version: STATE_VERSION, tasks: vec![], pipelines: vec![], custom_commands: vec![], next_task_id: 1, next_pipeline_id: 1, next_command_id: 1, public_ip: None };
         let summary = state.get_all_tasks_summary();
         assert_eq!(summary, "📝 暂无定时任务");
     }
@@ -639,6 +1745,22 @@ mod tests {
         assert_eq!(state.tasks[0].task_type, TaskType::SystemMaintenance);
     }
 
+    #[test]
+    fn test_legacy_cron_with_seconds_is_converted_on_load() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let path = temp_file.path().to_str().unwrap();
+        let legacy = r#"{"tasks": [
+            {"task_type": "SystemMaintenance", "cron_expression": "0 0 4 * * Sun", "enabled": true},
+            {"task_type": "UpdateXray", "cron_expression": "0 6 * * *", "enabled": true}
+        ]}"#;
+        fs::write(path, legacy).unwrap();
+
+        let state = SchedulerState::load_from_file(path).unwrap();
+        assert_eq!(state.tasks[0].cron_expression, "0 4 * * Sun");
+        assert_eq!(state.tasks[1].cron_expression, "0 6 * * *");
+        assert!(fs::read_to_string(path).unwrap().contains(r#""cron_expression": "0 4 * * Sun""#));
+    }
+
     #[test]
     fn test_scheduler_validator_new() {
         let validator = SchedulerValidator::new();
@@ -694,63 +1816,6 @@ mod tests {
             assert!(result.is_err(), "表达式 '{}' 应该无效", expr);
         }
     }
-
-    #[test]
-    fn test_scheduler_validator_is_valid_field() {
-        let validator = SchedulerValidator::new();
-        
-        // 测试字段验证
-        assert!(validator.is_valid_field("*", 0, 59));           // 通配符
-        assert!(validator.is_valid_field("30", 0, 59));          // 有效数字
-        assert!(validator.is_valid_field("1,3,5", 0, 59));       // 列表
-        assert!(validator.is_valid_field("1-5", 0, 59));         // 范围
-        assert!(validator.is_valid_field("*/5", 0, 59));         // 步长
-        assert!(validator.is_valid_field("1-10/2", 0, 59));      // 范围步长
-        
-        // 无效值
-        assert!(!validator.is_valid_field("60", 0, 59));         // 超出范围
-        assert!(!validator.is_valid_field("invalid", 0, 59));    // 非数字
-        assert!(!validator.is_valid_field("1-10-20", 0, 59));    // 格式错误
-        assert!(!validator.is_valid_field("*/", 0, 59));         // 步长错误
-    }
-
-    #[test]
-    fn test_scheduler_validator_is_valid_weekday_field() {
-        let validator = SchedulerValidator::new();
-        
-        // 测试星期字段验证
-        assert!(validator.is_valid_weekday_field("*"));         // 通配符
-        assert!(validator.is_valid_weekday_field("0"));         // 周日
-        assert!(validator.is_valid_weekday_field("7"));         // 周日（别名）
-        assert!(validator.is_valid_weekday_field("1"));         // 周一
-        assert!(validator.is_valid_weekday_field("6"));         // 周六
-        assert!(validator.is_valid_weekday_field("Sun"));       // 缩写周日
-        assert!(validator.is_valid_weekday_field("Mon"));       // 缩写周一
-        assert!(validator.is_valid_weekday_field("Sat"));       // 缩写周六
-        assert!(validator.is_valid_weekday_field("1,3,5"));     // 列表
-        
-        // 无效值
-        assert!(!validator.is_valid_weekday_field("8"));        // 超出范围
-        assert!(!validator.is_valid_weekday_field("-1"));       // 负数
-        assert!(!validator.is_valid_weekday_field("Invalid"));  // 无效缩写
-    }
-
-    #[test]
-    fn test_scheduler_validator_weekday_abbreviations() {
-        let validator = SchedulerValidator::new();
-        
-        // 测试所有星期缩写
-        let weekdays = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
-        for day in &weekdays {
-            assert!(validator.is_valid_weekday_field(day), 
-                "星期缩写 '{}' 应该有效", day);
-        }
-        
-        // 测试大小写变体
-        assert!(!validator.is_valid_weekday_field("SUN"));      // 大写无效
-        assert!(!validator.is_valid_weekday_field("sun"));      // 小写无效
-        assert!(!validator.is_valid_weekday_field("Sunday"));   // 全名无效
-    }
 }
 
 // SchedulerManager 集成测试
@@ -767,6 +1832,8 @@ mod scheduler_manager_tests {
             bot_token: "test_token".to_string(),
             chat_id: 12345,
             check_interval: 300,
+            monitor: Default::default(),
+            scheduler: Default::default(),
         }
     }
 
@@ -812,7 +1879,7 @@ mod scheduler_manager_tests {
         let task_type = TaskType::CoreMaintenance;
         let cron_expr = "0 5 * * *";
         
-        let result = manager.add_new_task(config, bot, task_type.clone(), cron_expr).await;
+        let result = manager.add_new_task(task_type.clone(), cron_expr).await;
         assert!(result.is_ok());
         assert!(result.unwrap().contains("✅"));
         
@@ -828,7 +1895,7 @@ mod scheduler_manager_tests {
         
         let (manager, _temp) = create_manager_with_temp_state(config.clone(), bot.clone()).await;
         
-        let result = manager.remove_task_by_index(config, bot, 0).await;
+        let result = manager.remove_task_by_id(1).await;
         assert!(result.is_ok());
         assert!(result.unwrap().contains("✅"));
         
@@ -837,6 +1904,60 @@ mod scheduler_manager_tests {
         assert_eq!(state.tasks.len(), 0);
     }
 
+    /// 调度器中是否还有该 UUID 的调度任务
+    async fn job_scheduled(manager: &SchedulerManager, uuid: Uuid) -> bool {
+        let mut scheduler_guard = manager.scheduler.lock().await;
+        scheduler_guard.as_mut().unwrap().next_tick_for_job(uuid).await.unwrap().is_some()
+    }
+
+    #[tokio::test]
+    async fn test_editing_a_task_only_replaces_its_own_job() {
+        let config = create_test_config();
+        let bot = create_test_bot();
+        let (manager, _temp) = create_manager_with_temp_state(config, bot).await;
+        let jobs = manager.jobs.lock().await.clone();
+        assert_eq!(jobs.keys().copied().collect::<Vec<_>>(), vec![1]);
+        let default_job = jobs[&1];
+        assert!(job_scheduled(&manager, default_job).await);
+
+        // 移除后重新创建会得到新的调度任务
+        manager.unschedule_task(1).await;
+        assert!(manager.jobs.lock().await.is_empty());
+        assert!(!job_scheduled(&manager, default_job).await);
+        let task = manager.state.lock().await.get_task(1).cloned().unwrap();
+        manager.schedule_task(&task).await;
+        let default_job = manager.jobs.lock().await[&1];
+        assert!(job_scheduled(&manager, default_job).await);
+
+        // 添加任务只新增自己的调度任务
+        manager.add_new_task(TaskType::UpdateXray, "0 6 * * *").await.unwrap();
+        let jobs = manager.jobs.lock().await.clone();
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[&1], default_job);
+        let added_job = jobs[&2];
+        assert!(job_scheduled(&manager, added_job).await);
+
+        // 修改任务只替换自己的调度任务
+        manager.update_task_by_id(2, "0 5 * * *").await.unwrap();
+        let jobs = manager.jobs.lock().await.clone();
+        assert_eq!(jobs[&1], default_job);
+        assert_ne!(jobs[&2], added_job);
+        assert!(!job_scheduled(&manager, added_job).await);
+        let updated_job = jobs[&2];
+        assert!(job_scheduled(&manager, updated_job).await);
+
+        // 禁用任务只移除自己的调度任务
+        manager.toggle_task_by_id(2).await.unwrap();
+        let jobs = manager.jobs.lock().await.clone();
+        assert_eq!(jobs.keys().copied().collect::<Vec<_>>(), vec![1]);
+        assert!(!job_scheduled(&manager, updated_job).await);
+        assert!(job_scheduled(&manager, default_job).await);
+
+        manager.remove_task_by_id(1).await.unwrap();
+        assert!(manager.jobs.lock().await.is_empty());
+        assert!(!job_scheduled(&manager, default_job).await);
+    }
+
     #[tokio::test]
     async fn test_scheduler_manager_remove_nonexistent_task() {
         let config = create_test_config();
@@ -844,7 +1965,7 @@ mod scheduler_manager_tests {
         
         let (manager, _temp) = create_manager_with_temp_state(config.clone(), bot.clone()).await;
         
-        let result = manager.remove_task_by_index(config, bot, 999).await;
+        let result = manager.remove_task_by_id(999).await;
         assert!(result.is_ok());
         assert!(result.unwrap().contains("❌"));
     }
@@ -862,7 +1983,7 @@ mod scheduler_manager_tests {
         drop(state_before);
         
         // 切换任务状态
-        let result = manager.toggle_task_by_index(config, bot, 0).await;
+        let result = manager.toggle_task_by_id(1).await;
         assert!(result.is_ok());
         assert!(result.unwrap().contains("✅"));
         
@@ -880,7 +2001,7 @@ mod scheduler_manager_tests {
         
         // 更新任务Cron表达式
         let new_cron = "0 6 * * *";
-        let result = manager.update_task_by_index(config, bot, 0, new_cron).await;
+        let result = manager.update_task_by_id(1, new_cron).await;
         assert!(result.is_ok());
         assert!(result.unwrap().contains("✅"));
         
@@ -898,7 +2019,7 @@ mod scheduler_manager_tests {
         
         // 尝试更新为无效的Cron表达式
         let invalid_cron = "invalid_cron";
-        let result = manager.update_task_by_index(config, bot, 0, invalid_cron).await;
+        let result = manager.update_task_by_id(1, invalid_cron).await;
         assert!(result.is_ok());
         assert!(result.unwrap().contains("❌"));
     }
@@ -927,7 +2048,7 @@ mod scheduler_manager_tests {
         let task_type = TaskType::CoreMaintenance;
         let invalid_cron = "invalid_cron";
         
-        let result = manager.add_new_task(config, bot, task_type, invalid_cron).await;
+        let result = manager.add_new_task(task_type, invalid_cron).await;
         assert!(result.is_ok());
         assert!(result.unwrap().contains("❌"));
         
@@ -948,13 +2069,11 @@ mod scheduler_manager_tests {
         let mut handles = vec![];
         for i in 0..5 {
             let manager_clone = manager.clone();
-            let config_clone = config.clone();
-            let bot_clone = bot.clone();
             let task_type = TaskType::CoreMaintenance;
             let cron_expr = format!("0 {} * * *", 5 + i);
             
             let handle = tokio::spawn(async move {
-                manager_clone.add_new_task(config_clone, bot_clone, task_type, &cron_expr).await
+                manager_clone.add_new_task(task_type, &cron_expr).await
             });
             handles.push(handle);
         }
@@ -981,7 +2100,7 @@ mod scheduler_manager_tests {
         // 创建调度器并添加任务
         {
             let manager = SchedulerManager::new(config.clone(), bot.clone(), state_path.clone()).await.unwrap();
-            let add_result = manager.add_new_task(config.clone(), bot.clone(), TaskType::UpdateXray, "0 8 * * *").await;
+            let add_result = manager.add_new_task(TaskType::UpdateXray, "0 8 * * *").await;
             assert!(add_result.is_ok());
             
             // 获取任务数量
@@ -1051,6 +2170,9 @@ mod maintenance_history_tests {
             result: MaintenanceResult::Success,
             output: "测试维护记录".to_string(),
             error_message: None,
+            attempts: Vec::new(),
+            timezone: None,
+            task_id: None,
         }
     }
 
@@ -1094,6 +2216,9 @@ mod maintenance_history_tests {
             result: MaintenanceResult::Success,
             output: "核心维护记录".to_string(),
             error_message: None,
+            attempts: Vec::new(),
+            timezone: None,
+            task_id: None,
         };
         
         history.add_record(record1);
@@ -1126,6 +2251,9 @@ mod maintenance_history_tests {
                 result: MaintenanceResult::Success,
                 output: format!("记录 {}", i),
                 error_message: None,
+                attempts: Vec::new(),
+                timezone: None,
+                task_id: None,
             };
             history.add_record(record);
         }
@@ -1188,6 +2316,9 @@ mod maintenance_history_tests {
                 result: status.clone(),
                 output: format!("统计测试记录 {}", i),
                 error_message: None,
+                attempts: Vec::new(),
+                timezone: None,
+                task_id: None,
             };
             history.add_record(record);
         }
@@ -0,0 +1,135 @@
+//! 任务通知策略
+//!
+//! 每个任务可以选择发送哪些通知：总是、仅失败、仅有变化或静默。另外可以开启
+//! 静默送达（Telegram 的 `disable_notification`），消息照常发送但不会响铃。
+//! 判断是否有变化时优先使用 apt 的升级统计，输出中没有统计时与上次成功执行的输出比较
+
+use crate::scheduler::maintenance_history::MaintenanceResult;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// 任务执行时发送哪些通知
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum NotificationPolicy {
+    /// 发送开始、过程和结果通知
+    #[default]
+    Always,
+    /// 只在执行失败、部分失败、超时或取消时通知
+    FailuresOnly,
+    /// 只在执行失败或产生变化（例如确实升级了软件包）时通知
+    ChangesOnly,
+    /// 不发送任务通知，自动重启提醒除外
+    Silent,
+}
+
+impl NotificationPolicy {
+    pub fn get_display_name(&self) -> &'static str {
+        match self {
+            NotificationPolicy::Always => "总是通知",
+            NotificationPolicy::FailuresOnly => "仅失败",
+            NotificationPolicy::ChangesOnly => "仅有变化",
+            NotificationPolicy::Silent => "不通知",
+        }
+    }
+
+    /// 按 总是 → 仅失败 → 仅有变化 → 不通知 的顺序切换
+    pub fn next(&self) -> Self {
+        match self {
+            NotificationPolicy::Always => NotificationPolicy::FailuresOnly,
+            NotificationPolicy::FailuresOnly => NotificationPolicy::ChangesOnly,
+            NotificationPolicy::ChangesOnly => NotificationPolicy::Silent,
+            NotificationPolicy::Silent => NotificationPolicy::Always,
+        }
+    }
+
+    /// 是否发送开始执行、排队、推迟等过程消息
+    pub fn notifies_progress(&self) -> bool {
+        *self == NotificationPolicy::Always
+    }
+
+    /// 是否发送执行结果，`changed` 只在需要时计算
+    pub fn notifies_result(&self, result: &MaintenanceResult, changed: impl FnOnce() -> bool) -> bool {
+        match self {
+            NotificationPolicy::Always => true,
+            NotificationPolicy::FailuresOnly => *result != MaintenanceResult::Success,
+            NotificationPolicy::ChangesOnly => *result != MaintenanceResult::Success || changed(),
+            NotificationPolicy::Silent => false,
+        }
+    }
+}
+
+/// apt-get 的操作统计，例如 `2 upgraded, 0 newly installed, 0 to remove and 0 not upgraded.`。
+/// apt 命令以 `LC_ALL=C` 执行，同时识别中文系统上的译文，兼容旧的维护记录
+static APT_SUMMARY: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(\d+) upgraded, (\d+) newly installed, (\d+) to remove|升级了 (\d+) 个软件包，新安装了 (\d+) 个软件包，要卸载 (\d+) 个软件包").unwrap()
+});
+
+/// 判断本次执行是否产生了变化，`previous` 为上次成功执行的输出
+pub fn has_changes(output: &str, previous: Option<&str>) -> bool {
+    let mut has_summary = false;
+    for captures in APT_SUMMARY.captures_iter(output) {
+        has_summary = true;
+        if captures.iter().skip(1).flatten().any(|count| count.as_str() != "0") {
+            return true;
+        }
+    }
+    if has_summary {
+        return false;
+    }
+    previous.is_none_or(|previous| previous.trim() != output.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notifies_result_by_policy() {
+        let success = MaintenanceResult::Success;
+        let partial = MaintenanceResult::Partial;
+        assert!(NotificationPolicy::Always.notifies_result(&success, || false));
+        assert!(!NotificationPolicy::FailuresOnly.notifies_result(&success, || true));
+        assert!(NotificationPolicy::FailuresOnly.notifies_result(&partial, || false));
+        assert!(!NotificationPolicy::ChangesOnly.notifies_result(&success, || false));
+        assert!(NotificationPolicy::ChangesOnly.notifies_result(&success, || true));
+        assert!(NotificationPolicy::ChangesOnly.notifies_result(&MaintenanceResult::TimedOut, || false));
+        assert!(!NotificationPolicy::Silent.notifies_result(&MaintenanceResult::Failed, || true));
+
+        assert!(NotificationPolicy::Always.notifies_progress());
+        assert!(!NotificationPolicy::ChangesOnly.notifies_progress());
+        assert_eq!(NotificationPolicy::Silent.next(), NotificationPolicy::Always);
+    }
+
+    #[test]
+    fn test_has_changes() {
+        let unchanged = "✅ Apt 完全升级: 成功\n0 upgraded, 0 newly installed, 0 to remove and 0 not upgraded.\n";
+        let upgraded = "✅ Apt 完全升级: 成功\n3 upgraded, 0 newly installed, 0 to remove and 0 not upgraded.\n";
+        // 有 apt 统计时不比较输出
+        assert!(!has_changes(unchanged, None));
+        assert!(has_changes(upgraded, Some(upgraded)));
+        assert!(has_changes(&format!("{}0 upgraded, 0 newly installed, 2 to remove", unchanged), None));
+
+        // 没有统计时与上次输出比较，首次执行视为有变化
+        assert!(has_changes("规则已更新", None));
+        assert!(!has_changes("规则无变化\n", Some("规则无变化")));
+        assert!(has_changes("新增 3 条规则", Some("规则无变化")));
+    }
+
+    #[test]
+    fn test_has_changes_with_localized_apt_output() {
+        let log = |fetched: &str, upgraded: u32| {
+            format!(
+                "✅ Apt 更新: 成功\n命中:1 http://deb.debian.org/debian bookworm InRelease\n\
+                 获取:2 http://deb.debian.org/debian-security bookworm-security InRelease [48.0 kB]\n\
+                 已下载 {}，耗时 1秒 (52.1 kB/s)\n正在读取软件包列表...\n\
+                 ✅ Apt 完全升级: 成功\n正在读取软件包列表...\n正在分析软件包的依赖关系树...\n正在计算更新...\n\
+                 升级了 {} 个软件包，新安装了 0 个软件包，要卸载 0 个软件包，有 0 个软件包未被升级。\n",
+                fetched, upgraded
+            )
+        };
+        // apt update 的下载信息每次都不同，只看升级统计
+        assert!(!has_changes(&log("48.0 kB", 0), Some(&log("96.3 kB", 0))));
+        assert!(has_changes(&log("48.0 kB", 2), Some(&log("48.0 kB", 0))));
+    }
+}
@@ -0,0 +1,290 @@
+//! 任务流水线
+//!
+//! 流水线是按顺序执行的一组任务步骤，保存在 `SchedulerState` 中，可以像普通任务
+//! 一样定时执行，也可以从维护菜单手动执行。每个步骤可以设置失败后是否继续，
+//! 以及是否仅在上一步成功时才执行。流水线中的步骤不会自行重启系统，是否重启
+//! 由整个流水线结束后的重启策略决定
+
+use crate::scheduler::maintenance_history::MaintenanceResult;
+use crate::scheduler::task_types::TaskType;
+use crate::system::SystemError;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+
+/// 默认流水线名称
+pub const FULL_MAINTENANCE_PIPELINE: &str = "完整维护";
+
+/// 流水线的稳定 ID，列表增删后不变，用于按钮回调
+pub type PipelineId = u64;
+
+/// 步骤失败后的处理方式
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum StepFailureAction {
+    /// 停止执行后续步骤
+    #[default]
+    Stop,
+    /// 继续执行后续步骤
+    Continue,
+}
+
+/// 流水线中的一个步骤
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PipelineStep {
+    pub task_type: TaskType,
+    #[serde(default)]
+    pub on_failure: StepFailureAction,
+    /// 仅在上一步成功时执行，上一步失败或被跳过时跳过本步骤
+    #[serde(default)]
+    pub only_if_previous_succeeded: bool,
+}
+
+impl PipelineStep {
+    pub fn new(task_type: TaskType) -> Self {
+        Self {
+            task_type,
+            on_failure: StepFailureAction::Stop,
+            only_if_previous_succeeded: false,
+        }
+    }
+
+    /// 定义语法中的写法：`!` 表示失败后继续，`?` 表示仅在上一步成功时执行
+    fn to_definition(&self) -> String {
+        let mut text = self.task_type.key().to_string();
+        if self.on_failure == StepFailureAction::Continue {
+            text.push('!');
+        }
+        if self.only_if_previous_succeeded {
+            text.push('?');
+        }
+        text
+    }
+}
+
+/// 用户定义的任务流水线
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Pipeline {
+    /// 由 `SchedulerState` 分配，尚未保存的流水线为 0
+    #[serde(default)]
+    pub id: PipelineId,
+    pub name: String,
+    pub steps: Vec<PipelineStep>,
+}
+
+/// 步骤执行情况
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum StepStatus {
+    Succeeded,
+    Failed,
+    Skipped,
+}
+
+impl Pipeline {
+    /// 内置的完整维护流水线：系统升级后执行规则维护
+    pub fn full_maintenance() -> Self {
+        Self {
+            id: 0,
+            name: FULL_MAINTENANCE_PIPELINE.to_string(),
+            steps: vec![
+                PipelineStep {
+                    on_failure: StepFailureAction::Continue,
+                    ..PipelineStep::new(TaskType::CoreMaintenance)
+                },
+                PipelineStep::new(TaskType::RulesMaintenance),
+            ],
+        }
+    }
+
+    /// 解析 `/setpipeline` 的定义，例如 `每周维护 core_maintenance! rules_maintenance? update_xray`
+    pub fn parse_definition(text: &str) -> Result<Self, String> {
+        let mut parts = text.split_whitespace();
+        let name = parts.next().ok_or("请提供流水线名称和步骤")?.to_string();
+
+        let mut steps = Vec::new();
+        for part in parts {
+            let mut key = part;
+            let mut step_flags = (StepFailureAction::Stop, false);
+            while let Some(last) = key.chars().last() {
+                match last {
+                    '!' => step_flags.0 = StepFailureAction::Continue,
+                    '?' => step_flags.1 = true,
+                    _ => break,
+                }
+                key = &key[..key.len() - 1];
+            }
+
+            let task_type = TaskType::from_key(key)
+                .ok_or_else(|| format!("未知的步骤 '{}'，可用: {}", key, TaskType::builtin_keys().join(", ")))?;
+            steps.push(PipelineStep {
+                task_type,
+                on_failure: step_flags.0,
+                only_if_previous_succeeded: step_flags.1,
+            });
+        }
+
+        if steps.is_empty() {
+            return Err("流水线至少需要一个步骤".to_string());
+        }
+        Ok(Self { id: 0, name, steps })
+    }
+
+    /// 转换回定义语法
+    pub fn to_definition(&self) -> String {
+        let steps: Vec<String> = self.steps.iter().map(PipelineStep::to_definition).collect();
+        format!("{} {}", self.name, steps.join(" "))
+    }
+
+    /// 是否包含可能需要重启的步骤
+    pub fn can_reboot(&self) -> bool {
+        self.steps.iter().any(|step| step.task_type.can_reboot())
+    }
+
+    /// 单行描述，例如 `🚀 核心维护 (失败继续) → 🌍 规则维护`
+    pub fn describe(&self) -> String {
+        let steps: Vec<String> = self
+            .steps
+            .iter()
+            .map(|step| {
+                let mut text = step.task_type.get_display_name();
+                if step.only_if_previous_succeeded {
+                    text.push_str(" (上一步成功时)");
+                }
+                if step.on_failure == StepFailureAction::Continue {
+                    text.push_str(" (失败继续)");
+                }
+                text
+            })
+            .collect();
+        steps.join(" → ")
+    }
+
+    /// 依次执行所有步骤
+    ///
+    /// 所有步骤成功时结果为 Success，有失败但允许继续的步骤时为 Partial；
+    /// 设置为失败即停止的步骤失败时返回不可重试的 `PipelineStopped`，错误信息包含已执行的日志，
+    /// 避免重试策略把已成功的步骤再执行一遍
+    pub async fn run(&self) -> Result<(String, MaintenanceResult), SystemError> {
+        self.run_with(|task_type| {
+            let task_type = task_type.clone();
+            async move { task_type.run_operation().await }
+        })
+        .await
+    }
+
+    async fn run_with<F, Fut>(&self, mut run_step: F) -> Result<(String, MaintenanceResult), SystemError>
+    where
+        F: FnMut(&TaskType) -> Fut,
+        Fut: Future<Output = Result<String, SystemError>>,
+    {
+        let mut log = format!("🧩 流水线「{}」: {}\n\n", self.name, self.describe());
+        let mut previous = StepStatus::Succeeded;
+        let mut has_failures = false;
+
+        for (i, step) in self.steps.iter().enumerate() {
+            let step_name = format!("第 {} 步 {}", i + 1, step.task_type.get_display_name());
+
+            if step.only_if_previous_succeeded && previous != StepStatus::Succeeded {
+                log.push_str(&format!("⏭️ {}: 上一步未成功，已跳过\n\n", step_name));
+                previous = StepStatus::Skipped;
+                continue;
+            }
+
+            if matches!(step.task_type, TaskType::Pipeline(_)) {
+                log.push_str(&format!("❌ {}: 流水线不能嵌套\n\n", step_name));
+                previous = StepStatus::Failed;
+                has_failures = true;
+                continue;
+            }
+
+            match run_step(&step.task_type).await {
+                Ok(output) => {
+                    log.push_str(&format!("✅ {}:\n{}\n\n", step_name, output));
+                    previous = StepStatus::Succeeded;
+                }
+                Err(e) => {
+                    log.push_str(&format!("❌ {}: {}\n\n", step_name, e));
+                    has_failures = true;
+                    previous = StepStatus::Failed;
+                    if step.on_failure == StepFailureAction::Stop {
+                        log.push_str("⛔ 后续步骤已停止");
+                        return Err(SystemError::PipelineStopped(log));
+                    }
+                }
+            }
+        }
+
+        let result = if has_failures {
+            MaintenanceResult::Partial
+        } else {
+            MaintenanceResult::Success
+        };
+        Ok((log, result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    async fn run_fake(pipeline: &Pipeline, failing: &[TaskType]) -> (Result<(String, MaintenanceResult), SystemError>, Vec<TaskType>) {
+        let executed = RefCell::new(Vec::new());
+        let result = pipeline.run_with(|task_type| {
+            executed.borrow_mut().push(task_type.clone());
+            let fail = failing.contains(task_type);
+            async move {
+                if fail {
+                    Err(SystemError::NetworkError("下载失败".to_string()))
+                } else {
+                    Ok("完成".to_string())
+                }
+            }
+        })
+        .await;
+        (result, executed.into_inner())
+    }
+
+    #[test]
+    fn test_parse_definition_round_trip() {
+        let pipeline = Pipeline::parse_definition("每周维护 core_maintenance! rules_maintenance? update_xray").unwrap();
+        assert_eq!(pipeline.name, "每周维护");
+        assert_eq!(pipeline.steps.len(), 3);
+        assert_eq!(pipeline.steps[0].on_failure, StepFailureAction::Continue);
+        assert!(pipeline.steps[1].only_if_previous_succeeded);
+        assert_eq!(pipeline.steps[2], PipelineStep::new(TaskType::UpdateXray));
+        assert!(pipeline.can_reboot());
+        assert_eq!(Pipeline::parse_definition(&pipeline.to_definition()).unwrap(), pipeline);
+
+        assert!(Pipeline::parse_definition("空流水线").is_err());
+        assert!(Pipeline::parse_definition("错误 reboot_now").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_stop_on_failure_stops_remaining_steps() {
+        let pipeline = Pipeline::parse_definition("p rules_maintenance update_xray").unwrap();
+        let (result, executed) = run_fake(&pipeline, &[TaskType::RulesMaintenance]).await;
+
+        // 步骤本身的网络错误可重试，但整条流水线不再重试
+        let error = result.unwrap_err();
+        assert!(matches!(error, SystemError::PipelineStopped(_)));
+        assert!(!error.is_retryable());
+        assert!(error.to_string().contains("网络连接失败: 下载失败"));
+        assert!(error.to_string().contains("后续步骤已停止"));
+        assert_eq!(executed, vec![TaskType::RulesMaintenance]);
+    }
+
+    #[tokio::test]
+    async fn test_continue_on_failure_and_conditional_steps() {
+        let pipeline = Pipeline::parse_definition("p core_maintenance! rules_maintenance? update_xray").unwrap();
+        let (result, executed) = run_fake(&pipeline, &[TaskType::CoreMaintenance]).await;
+
+        let (log, result) = result.unwrap();
+        assert_eq!(result, MaintenanceResult::Partial);
+        assert!(log.contains("上一步未成功，已跳过"));
+        // 规则维护被跳过，Xray 更新不依赖上一步照常执行
+        assert_eq!(executed, vec![TaskType::CoreMaintenance, TaskType::UpdateXray]);
+
+        let (result, executed) = run_fake(&Pipeline::full_maintenance(), &[]).await;
+        assert_eq!(result.unwrap().1, MaintenanceResult::Success);
+        assert_eq!(executed, vec![TaskType::CoreMaintenance, TaskType::RulesMaintenance]);
+    }
+}
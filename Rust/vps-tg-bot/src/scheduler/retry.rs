@@ -0,0 +1,183 @@
+//! 定时任务重试策略
+//!
+//! 失败的错误需要同时满足 `SystemError::is_retryable` 且类别在策略的
+//! `retry_on` 列表中才会重试。两次尝试之间按指数退避等待，等待时间不超过上限。
+//! 任务超时限制的是单次尝试，退避等待不计入；超时的尝试不再重试
+
+use crate::scheduler::jobs::JobOutcome;
+use crate::scheduler::maintenance_history::MaintenanceAttempt;
+use crate::system::{ErrorClass, SystemError};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::Duration;
+
+/// 任务失败后的重试策略
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// 最多执行次数（含首次），1 表示不重试
+    pub max_attempts: u32,
+    /// 首次重试前的等待时间（秒），之后每次翻倍
+    pub initial_backoff_secs: u64,
+    /// 等待时间上限（秒）
+    pub max_backoff_secs: u64,
+    /// 需要重试的错误类别
+    pub retry_on: Vec<ErrorClass>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff_secs: 30,
+            max_backoff_secs: 600,
+            retry_on: vec![ErrorClass::Network, ErrorClass::PackageManager],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 第 `attempt` 次尝试失败后是否继续重试
+    pub fn should_retry(&self, error: &SystemError, attempt: u32) -> bool {
+        attempt < self.max_attempts && error.is_retryable() && self.retry_on.contains(&error.class())
+    }
+
+    /// 第 `attempt` 次尝试失败后的等待时间
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u64.saturating_pow(attempt.saturating_sub(1));
+        Duration::from_secs(self.initial_backoff_secs.saturating_mul(factor).min(self.max_backoff_secs))
+    }
+}
+
+/// 按策略执行操作，每次尝试都记录到 `attempts`，单次尝试超过 `timeout` 时返回 `TimedOut`
+pub async fn run_with_retry<T, F, Fut>(
+    policy: &RetryPolicy,
+    timeout: Duration,
+    attempts: &mut Vec<MaintenanceAttempt>,
+    mut operation: F,
+) -> JobOutcome<Result<T, SystemError>>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, SystemError>>,
+{
+    let mut attempt = 1;
+    loop {
+        let started_at = Utc::now();
+        match tokio::time::timeout(timeout, operation()).await {
+            Err(_) => {
+                let error = format!("执行超过 {} 秒，已终止", timeout.as_secs());
+                attempts.push(MaintenanceAttempt { attempt, started_at, error: Some(error) });
+                return JobOutcome::TimedOut;
+            }
+            Ok(Ok(output)) => {
+                attempts.push(MaintenanceAttempt { attempt, started_at, error: None });
+                return JobOutcome::Completed(Ok(output));
+            }
+            Ok(Err(e)) => {
+                attempts.push(MaintenanceAttempt { attempt, started_at, error: Some(e.to_string()) });
+                if !policy.should_retry(&e, attempt) {
+                    return JobOutcome::Completed(Err(e));
+                }
+                let delay = policy.backoff(attempt);
+                log::warn!("第 {} 次尝试失败: {}，{} 秒后重试", attempt, e, delay.as_secs());
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_up_to_cap() {
+        let policy = RetryPolicy { initial_backoff_secs: 30, max_backoff_secs: 100, ..Default::default() };
+        assert_eq!(policy.backoff(1), Duration::from_secs(30));
+        assert_eq!(policy.backoff(2), Duration::from_secs(60));
+        assert_eq!(policy.backoff(3), Duration::from_secs(100));
+        assert_eq!(policy.backoff(40), Duration::from_secs(100));
+    }
+
+    #[test]
+    fn test_should_retry_respects_classes_and_attempts() {
+        let policy = RetryPolicy::default();
+        let network = SystemError::NetworkError("timeout".to_string());
+        assert!(policy.should_retry(&network, 1));
+        assert!(!policy.should_retry(&network, 3));
+        assert!(policy.should_retry(&SystemError::PackageManagerError("dpkg".to_string()), 2));
+        // 可重试但未列入策略
+        assert!(!policy.should_retry(&SystemError::CommandExecutionError("exit 1".to_string()), 1));
+        // 列入策略但本身不可重试
+        let policy = RetryPolicy { retry_on: vec![ErrorClass::DiskSpace], ..Default::default() };
+        assert!(!policy.should_retry(&SystemError::DiskSpaceError("full".to_string()), 1));
+    }
+
+    #[tokio::test]
+    async fn test_run_with_retry_records_every_attempt() {
+        let policy = RetryPolicy { initial_backoff_secs: 0, ..Default::default() };
+        let mut attempts = Vec::new();
+        let mut calls = 0;
+        let result = run_with_retry(&policy, Duration::from_secs(5), &mut attempts, || {
+            calls += 1;
+            let current = calls;
+            async move {
+                if current < 3 {
+                    Err(SystemError::NetworkError(format!("第 {} 次", current)))
+                } else {
+                    Ok("done")
+                }
+            }
+        })
+        .await;
+
+        assert!(matches!(result, JobOutcome::Completed(Ok("done"))));
+        assert_eq!(attempts.len(), 3);
+        assert!(attempts[0].error.as_deref().unwrap().contains("第 1 次"));
+        assert_eq!(attempts[2].error, None);
+
+        let mut attempts = Vec::new();
+        let result: JobOutcome<Result<(), _>> = run_with_retry(&policy, Duration::from_secs(5), &mut attempts, || async {
+            Err(SystemError::PermissionDenied("root".to_string()))
+        })
+        .await;
+        assert!(matches!(result, JobOutcome::Completed(Err(_))));
+        assert_eq!(attempts.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_applies_to_each_attempt() {
+        // 退避等待比超时时间长，但不计入超时
+        let policy = RetryPolicy { max_attempts: 2, initial_backoff_secs: 1, ..Default::default() };
+        let timeout = Duration::from_millis(500);
+        let mut attempts = Vec::new();
+        let mut calls = 0;
+        let result = run_with_retry(&policy, timeout, &mut attempts, || {
+            calls += 1;
+            let current = calls;
+            async move {
+                if current < 2 {
+                    Err(SystemError::NetworkError("timeout".to_string()))
+                } else {
+                    Ok(())
+                }
+            }
+        })
+        .await;
+        assert!(matches!(result, JobOutcome::Completed(Ok(()))));
+        assert_eq!(attempts.len(), 2);
+
+        // 单次尝试超时后不再重试
+        let mut attempts = Vec::new();
+        let result: JobOutcome<Result<(), SystemError>> = run_with_retry(&policy, Duration::from_millis(20), &mut attempts, || async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(())
+        })
+        .await;
+        assert!(matches!(result, JobOutcome::TimedOut));
+        assert_eq!(attempts.len(), 1);
+        assert!(attempts[0].error.as_deref().unwrap().contains("执行超过"));
+    }
+}
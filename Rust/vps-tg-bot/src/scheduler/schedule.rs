@@ -0,0 +1,327 @@
+//! 任务执行计划
+//!
+//! 除 Cron 表达式外，任务还可以按固定间隔执行、在指定时间执行一次、在 Bot
+//! 启动后延迟执行，或在事件发生时执行（见 `events`）。旧版状态文件中的任务没有 `schedule` 字段，按 Cron 计划处理。
+//! 间隔计划以创建时间为基准对齐，Bot 重启后不会漂移。
+//! Cron 计划和单次计划的时间按任务时区解释
+
+use crate::scheduler::cron;
+use crate::scheduler::events::{EventTrigger, DEFAULT_COOLDOWN_SECS};
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+/// Bot 启动时间，启动后延迟执行的任务以此为基准
+pub static BOT_STARTED_AT: Lazy<DateTime<Utc>> = Lazy::new(Utc::now);
+
+/// 间隔计划的最小间隔（秒），同时也是检查是否到期的频率
+pub const MIN_INTERVAL_SECS: u64 = 60;
+
+/// 预览执行时间时向后查找的天数
+const PREVIEW_HORIZON_DAYS: i64 = 3660;
+
+/// 任务执行计划
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Schedule {
+    /// 按任务的 `cron_expression` 执行
+    #[default]
+    Cron,
+    /// 从 `anchor` 开始每隔 `every_secs` 秒执行
+    Interval { every_secs: u64, anchor: DateTime<Utc> },
+    /// 在指定时间执行一次，执行后停用或删除任务
+    Once {
+        at: DateTime<Utc>,
+        #[serde(default)]
+        remove_after_run: bool,
+    },
+    /// 每次 Bot 启动后延迟执行
+    AfterStart { delay_secs: u64 },
+    /// 事件发生时执行，两次执行至少间隔 `cooldown_secs` 秒
+    Event { trigger: EventTrigger, cooldown_secs: u64 },
+}
+
+impl Schedule {
+    /// 解析用户输入的计划，不是以下格式时返回 None，按 Cron 表达式处理：
+    /// `every 6h`、`once 2026-11-02 03:00 [remove]`、`boot 10m`、`on disk / 10% [cooldown=1h]`，
+    /// 单次计划的时间按 `timezone` 解释
+    pub fn parse(text: &str, now: DateTime<Utc>, timezone: &Tz) -> Option<Result<Self, String>> {
+        let mut parts = text.split_whitespace();
+        let kind = parts.next()?.to_lowercase();
+        let rest: Vec<&str> = parts.collect();
+
+        let result = match kind.as_str() {
+            "every" => parse_single_duration(&rest).and_then(|every_secs| {
+                if every_secs < MIN_INTERVAL_SECS {
+                    return Err(format!("间隔不能小于 {} 秒", MIN_INTERVAL_SECS));
+                }
+                Ok(Schedule::Interval { every_secs, anchor: now })
+            }),
+            "boot" => parse_single_duration(&rest).map(|delay_secs| Schedule::AfterStart { delay_secs }),
+            "once" => parse_once(&rest, now, timezone),
+            "on" => parse_event(&rest),
+            _ => return None,
+        };
+        Some(result)
+    }
+
+    /// 是否为执行一次后结束的计划
+    pub fn is_one_shot(&self) -> bool {
+        matches!(self, Schedule::Once { .. })
+    }
+
+    /// 计划描述，Cron 计划需要传入表达式，单次计划的时间按 `timezone` 显示
+    pub fn describe(&self, cron_expression: &str, timezone: &Tz) -> String {
+        match self {
+            Schedule::Cron => format!("Cron: {}", cron_expression),
+            Schedule::Interval { every_secs, .. } => format!("每 {}", format_duration(*every_secs)),
+            Schedule::Once { at, remove_after_run } => format!(
+                "单次: {}{}",
+                cron::format_run_time(&at.with_timezone(timezone)),
+                if *remove_after_run { "（执行后删除）" } else { "（执行后停用）" }
+            ),
+            Schedule::AfterStart { delay_secs } => format!("启动后 {}", format_duration(*delay_secs)),
+            Schedule::Event { trigger, cooldown_secs } => {
+                format!("事件: {}（冷却 {}）", trigger.describe(), format_duration(*cooldown_secs))
+            }
+        }
+    }
+
+    /// `after` 之后、不晚于 `until` 的触发时间，最多 `limit` 个
+    ///
+    /// 启动后执行和事件触发的计划没有固定的触发时间，不参与错过执行的统计。Cron 计划按 `after` 的时区计算
+    pub fn fire_times<Z: TimeZone>(&self, cron_expression: &str, after: DateTime<Z>, until: DateTime<Z>, limit: usize) -> Vec<DateTime<Z>> {
+        let timezone = after.timezone();
+        match self {
+            Schedule::Cron => match cron::parse(cron_expression) {
+                Ok(schedule) => schedule.iter_after(after).take_while(|run| *run <= until).take(limit).collect(),
+                Err(_) => Vec::new(),
+            },
+            Schedule::Interval { every_secs, anchor } => {
+                let every = chrono::Duration::seconds(*every_secs as i64);
+                let anchor = anchor.with_timezone(&timezone);
+                // 第一个晚于 `after` 的对齐时间
+                let elapsed = (after.clone() - anchor.clone()).num_seconds().max(0) / *every_secs as i64;
+                let mut run = anchor + every * (elapsed as i32);
+                let mut runs = Vec::new();
+                while runs.len() < limit {
+                    if run > after {
+                        if run > until {
+                            break;
+                        }
+                        runs.push(run.clone());
+                    }
+                    run += every;
+                }
+                runs
+            }
+            Schedule::Once { at, .. } => {
+                let at = at.with_timezone(&timezone);
+                if at > after && at <= until && limit > 0 {
+                    vec![at]
+                } else {
+                    Vec::new()
+                }
+            }
+            Schedule::AfterStart { .. } | Schedule::Event { .. } => Vec::new(),
+        }
+    }
+
+    /// `now` 之后的 N 次执行时间，用于预览
+    pub fn next_runs<Z: TimeZone>(&self, cron_expression: &str, now: DateTime<Z>, count: usize) -> Vec<DateTime<Z>> {
+        match self {
+            Schedule::AfterStart { delay_secs } => {
+                let run = (*BOT_STARTED_AT + chrono::Duration::seconds(*delay_secs as i64)).with_timezone(&now.timezone());
+                if run > now && count > 0 {
+                    vec![run]
+                } else {
+                    Vec::new()
+                }
+            }
+            _ => {
+                let until = now.clone() + chrono::Duration::days(PREVIEW_HORIZON_DAYS);
+                self.fire_times(cron_expression, now, until, count)
+            }
+        }
+    }
+
+    /// 一次性触发的计划距离 `now` 的等待时间，已经过期时返回 None
+    pub fn delay_until_fire(&self, now: DateTime<Utc>) -> Option<std::time::Duration> {
+        let fire_at = match self {
+            Schedule::Once { at, .. } => *at,
+            Schedule::AfterStart { delay_secs } => *BOT_STARTED_AT + chrono::Duration::seconds(*delay_secs as i64),
+            _ => return None,
+        };
+        (fire_at - now).to_std().ok()
+    }
+}
+
+/// 解析 `30m`、`6h`、`2d` 形式的时长
+pub fn parse_duration(text: &str) -> Result<u64, String> {
+    let invalid = || format!("无效的时长 '{}'，示例: 30m、6h、2d", text);
+    let split = text.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+    let (value, unit) = text.split_at(split);
+    let value: u64 = value.parse().map_err(|_| invalid())?;
+    let unit_secs = match unit {
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => return Err(invalid()),
+    };
+    match value.checked_mul(unit_secs) {
+        Some(secs) if secs > 0 => Ok(secs),
+        _ => Err(invalid()),
+    }
+}
+
+/// 格式化时长，例如 `6 小时`、`90 分钟`
+pub fn format_duration(secs: u64) -> String {
+    if secs >= 86400 && secs.is_multiple_of(86400) {
+        format!("{} 天", secs / 86400)
+    } else if secs >= 3600 && secs.is_multiple_of(3600) {
+        format!("{} 小时", secs / 3600)
+    } else if secs >= 60 && secs.is_multiple_of(60) {
+        format!("{} 分钟", secs / 60)
+    } else {
+        format!("{} 秒", secs)
+    }
+}
+
+fn parse_single_duration(parts: &[&str]) -> Result<u64, String> {
+    match parts {
+        [duration] => parse_duration(duration),
+        _ => Err("请提供一个时长，示例: every 6h、boot 10m".to_string()),
+    }
+}
+
+fn parse_once(parts: &[&str], now: DateTime<Utc>, timezone: &Tz) -> Result<Schedule, String> {
+    let (date_time, remove_after_run) = match parts {
+        [date, time] => (format!("{} {}", date, time), false),
+        [date, time, flag] if flag.eq_ignore_ascii_case("remove") => (format!("{} {}", date, time), true),
+        _ => return Err("请提供执行时间，示例: once 2026-11-02 03:00 [remove]".to_string()),
+    };
+    let naive = NaiveDateTime::parse_from_str(&date_time, "%Y-%m-%d %H:%M")
+        .map_err(|_| format!("无效的时间 '{}'，格式应为 YYYY-MM-DD HH:MM", date_time))?;
+    let at = timezone
+        .from_local_datetime(&naive)
+        .earliest()
+        .ok_or_else(|| format!("时间 '{}' 在时区 {} 中不存在", date_time, timezone))?
+        .with_timezone(&Utc);
+    if at <= now {
+        return Err(format!("执行时间 {} 已经过去", date_time));
+    }
+    Ok(Schedule::Once { at, remove_after_run })
+}
+
+fn parse_event(parts: &[&str]) -> Result<Schedule, String> {
+    let (cooldown_secs, parts) = match parts.split_last() {
+        Some((last, rest)) if last.starts_with("cooldown=") => (parse_duration(&last["cooldown=".len()..])?, rest),
+        _ => (DEFAULT_COOLDOWN_SECS, parts),
+    };
+    let trigger = EventTrigger::parse(parts)?;
+    Ok(Schedule::Event { trigger, cooldown_secs })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Local;
+
+    #[test]
+    fn test_parse_schedules() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 1, 0, 0, 0).unwrap();
+        assert_eq!(
+            Schedule::parse("every 6h", now, &Tz::UTC).unwrap().unwrap(),
+            Schedule::Interval { every_secs: 6 * 3600, anchor: now }
+        );
+        assert_eq!(Schedule::parse("boot 10m", now, &Tz::UTC).unwrap().unwrap(), Schedule::AfterStart { delay_secs: 600 });
+
+        let once = Schedule::parse("once 2026-11-02 03:00 remove", now, &Tz::UTC).unwrap().unwrap();
+        assert!(matches!(once, Schedule::Once { remove_after_run: true, .. }));
+        assert!(once.is_one_shot());
+
+        // Cron 表达式交给 Cron 解析器处理
+        assert!(Schedule::parse("0 4 * * Sun", now, &Tz::UTC).is_none());
+        assert!(Schedule::parse("every 30s", now, &Tz::UTC).unwrap().is_err());
+        assert!(Schedule::parse("every 0m", now, &Tz::UTC).unwrap().is_err());
+        assert!(Schedule::parse("once 2020-01-01 00:00", now, &Tz::UTC).unwrap().is_err());
+        assert!(Schedule::parse("once tomorrow", now, &Tz::UTC).unwrap().is_err());
+    }
+
+    #[test]
+    fn test_parse_event_schedules() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 1, 0, 0, 0).unwrap();
+        let disk = Schedule::parse("on disk / 10%", now, &Tz::UTC).unwrap().unwrap();
+        assert_eq!(
+            disk,
+            Schedule::Event {
+                trigger: EventTrigger::DiskFree { mount_point: "/".to_string(), below_percent: 10.0 },
+                cooldown_secs: DEFAULT_COOLDOWN_SECS
+            }
+        );
+        assert_eq!(disk.describe("", &Tz::UTC), "事件: / 剩余空间低于 10%（冷却 30 分钟）");
+        assert!(disk.fire_times("", now, now + chrono::Duration::days(1), 5).is_empty());
+
+        let unit = Schedule::parse("on unit xray.service cooldown=2h", now, &Tz::UTC).unwrap().unwrap();
+        assert_eq!(
+            unit,
+            Schedule::Event { trigger: EventTrigger::UnitFailed { unit: "xray.service".to_string() }, cooldown_secs: 7200 }
+        );
+        assert!(Schedule::parse("on ip cooldown=soon", now, &Tz::UTC).unwrap().is_err());
+        assert!(Schedule::parse("on", now, &Tz::UTC).unwrap().is_err());
+    }
+
+    #[test]
+    fn test_schedules_use_task_timezone() {
+        let shanghai = chrono_tz::Asia::Shanghai;
+        let now = Utc.with_ymd_and_hms(2026, 10, 1, 0, 0, 0).unwrap();
+
+        // 单次计划的时间按任务时区解释和显示
+        let once = Schedule::parse("once 2026-11-02 03:00", now, &shanghai).unwrap().unwrap();
+        assert_eq!(once, Schedule::Once { at: Utc.with_ymd_and_hms(2026, 11, 1, 19, 0, 0).unwrap(), remove_after_run: false });
+        assert!(once.describe("", &shanghai).contains("2026-11-02 03:00"));
+        assert!(once.describe("", &Tz::UTC).contains("2026-11-01 19:00"));
+
+        // Cron 计划在任务时区的 04:00 触发
+        let runs = Schedule::Cron.next_runs("0 4 * * *", now.with_timezone(&shanghai), 1);
+        assert_eq!(runs[0].with_timezone(&Utc), Utc.with_ymd_and_hms(2026, 10, 1, 20, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn test_interval_fire_times_stay_aligned() {
+        let anchor = Local.with_ymd_and_hms(2026, 10, 1, 0, 10, 0).unwrap();
+        let schedule = Schedule::Interval { every_secs: 6 * 3600, anchor: anchor.with_timezone(&Utc) };
+
+        let after = Local.with_ymd_and_hms(2026, 10, 1, 7, 0, 0).unwrap();
+        let until = Local.with_ymd_and_hms(2026, 10, 2, 0, 10, 0).unwrap();
+        let runs = schedule.fire_times("", after, until, 10);
+        assert_eq!(runs.len(), 3);
+        assert_eq!(runs[0], Local.with_ymd_and_hms(2026, 10, 1, 12, 10, 0).unwrap());
+        assert_eq!(runs[2], until);
+
+        // 基准时间之前不会触发
+        let runs = schedule.fire_times("", anchor - chrono::Duration::days(1), anchor, 10);
+        assert_eq!(runs, vec![anchor]);
+        assert_eq!(schedule.describe("", &Tz::UTC), "每 6 小时");
+    }
+
+    #[test]
+    fn test_once_and_after_start() {
+        let at = Local.with_ymd_and_hms(2026, 11, 2, 3, 0, 0).unwrap();
+        let once = Schedule::Once { at: at.with_timezone(&Utc), remove_after_run: false };
+        assert_eq!(once.fire_times("", at - chrono::Duration::hours(1), at, 5), vec![at]);
+        assert!(once.fire_times("", at, at + chrono::Duration::hours(1), 5).is_empty());
+        assert_eq!(once.next_runs("", at - chrono::Duration::days(1), 3), vec![at]);
+        assert_eq!(
+            once.delay_until_fire((at - chrono::Duration::minutes(5)).with_timezone(&Utc)),
+            Some(std::time::Duration::from_secs(300))
+        );
+        assert_eq!(once.delay_until_fire((at + chrono::Duration::minutes(5)).with_timezone(&Utc)), None);
+
+        let boot = Schedule::AfterStart { delay_secs: 600 };
+        assert!(boot.fire_times("", at - chrono::Duration::days(1), at, 5).is_empty());
+        assert_eq!(boot.describe("", &Tz::UTC), "启动后 10 分钟");
+        assert_eq!(Schedule::Cron.describe("0 4 * * *", &Tz::UTC), "Cron: 0 4 * * *");
+    }
+}
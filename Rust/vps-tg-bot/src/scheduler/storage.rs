@@ -0,0 +1,124 @@
+//! 状态文件持久化
+//!
+//! 状态文件先写入同目录下的临时文件并 fsync，再通过 rename 原子替换，
+//! 写入过程中崩溃时旧文件保持完整。无法解析的状态文件会被移到带时间戳的
+//! 隔离文件中保留，而不是被默认状态直接覆盖。调度器和后台监控的状态文件都保存在
+//! 状态目录 `STATE_DIR` 中
+
+use chrono::Local;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// 原子写入文件，目录不存在时自动创建
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(dir)?;
+
+    let mut temp = tempfile::NamedTempFile::new_in(dir)?;
+    temp.write_all(contents)?;
+    temp.as_file().sync_all()?;
+    temp.persist(path).map_err(|e| e.error)?;
+
+    // 同步目录项，保证 rename 在断电后仍然生效
+    File::open(dir)?.sync_all()
+}
+
+/// 把损坏的文件重命名为 `<文件名>.corrupt-<时间戳>`，返回新路径
+pub fn quarantine(path: &Path) -> io::Result<PathBuf> {
+    let file_name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let target = path.with_file_name(format!("{}.corrupt-{}", file_name, Local::now().format("%Y%m%d%H%M%S")));
+    fs::rename(path, &target)?;
+    Ok(target)
+}
+
+/// 读取 JSON 状态文件，文件不存在或无法读取时返回 None；
+/// 内容无法解析时先隔离原文件，避免被新状态覆盖
+pub fn load_json_or_quarantine<T: DeserializeOwned>(path: &Path, description: &str) -> Option<T> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return None,
+        Err(e) => {
+            log::warn!("读取{} {} 失败: {}", description, path.display(), e);
+            return None;
+        }
+    };
+    let error = match serde_json::from_str(&content) {
+        Ok(value) => return Some(value),
+        Err(e) => e,
+    };
+
+    log::error!("{} {} 已损坏: {}", description, path.display(), error);
+    match quarantine(path) {
+        Ok(target) => log::warn!("损坏的{}已移至 {}，将重新开始", description, target.display()),
+        Err(e) => log::error!("隔离损坏的{}失败: {}", description, e),
+    }
+    None
+}
+
+/// 以 JSON 格式原子写入状态文件
+pub fn save_json<T: Serialize>(path: &Path, value: &T) -> anyhow::Result<()> {
+    let json = serde_json::to_string_pretty(value)?;
+    write_atomic(path, json.as_bytes())?;
+    Ok(())
+}
+
+/// 旧版本把状态文件保存在工作目录中，首次使用状态目录时复制过来
+pub fn migrate_legacy_file(path: &Path) {
+    let Some(file_name) = path.file_name() else {
+        return;
+    };
+    let legacy = Path::new(file_name);
+    if path.exists() || !legacy.is_file() {
+        return;
+    }
+    match fs::read(legacy).and_then(|content| write_atomic(path, &content)) {
+        Ok(()) => log::info!("已将工作目录中的 {} 迁移到 {}", legacy.display(), path.display()),
+        Err(e) => log::error!("迁移旧状态文件到 {} 失败: {}", path.display(), e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_write_atomic_and_quarantine() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("nested").join("state.json");
+
+        write_atomic(&path, b"first").unwrap();
+        write_atomic(&path, b"second").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        // 临时文件已被重命名，目录中只有目标文件
+        assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+
+        let quarantined = quarantine(&path).unwrap();
+        assert!(!path.exists());
+        assert_eq!(fs::read_to_string(&quarantined).unwrap(), "second");
+        assert!(quarantined.file_name().unwrap().to_string_lossy().starts_with("state.json.corrupt-"));
+    }
+
+    #[test]
+    fn test_load_json_quarantines_corrupt_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("watch_state.json");
+        assert_eq!(load_json_or_quarantine::<Vec<u32>>(&path, "测试状态"), None);
+
+        save_json(&path, &vec![1u32, 2]).unwrap();
+        assert_eq!(load_json_or_quarantine::<Vec<u32>>(&path, "测试状态"), Some(vec![1, 2]));
+
+        fs::write(&path, "{ 截断").unwrap();
+        assert_eq!(load_json_or_quarantine::<Vec<u32>>(&path, "测试状态"), None);
+        assert!(!path.exists());
+        let quarantined: Vec<_> = fs::read_dir(temp_dir.path()).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+        assert_eq!(quarantined.len(), 1);
+        assert!(quarantined[0].to_string_lossy().starts_with("watch_state.json.corrupt-"));
+    }
+}
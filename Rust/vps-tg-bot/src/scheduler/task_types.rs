@@ -2,29 +2,227 @@ use serde::{Serialize, Deserialize};
 use teloxide::Bot;
 use teloxide::types::ChatId;
 use teloxide::prelude::Requester;
-use crate::system::ops;
-use crate::scheduler::maintenance_history::{record_maintenance, MaintenanceResult};
-use anyhow::{Result, anyhow};
+use teloxide::payloads::SendMessageSetters;
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup};
+use std::time::Duration;
+use crate::system::{ops, reboot, SystemError};
+use crate::system::lock::MaintenanceLock;
+use crate::scheduler::jobs::{self, JobOutcome};
+use crate::scheduler::maintenance_history::{self, record_maintenance_with_attempts, MaintenanceResult};
+use crate::scheduler::cron;
+use crate::scheduler::custom;
+use crate::scheduler::notification::{self, NotificationPolicy};
+use crate::scheduler::pipeline::Pipeline;
+use crate::scheduler::retry::{self, RetryPolicy};
+use crate::scheduler::schedule::Schedule;
+use crate::scheduler::timezone;
+use chrono::{DateTime, Local, Utc};
+use chrono_tz::Tz;
+
+/// 任务 ID，创建后不再改变，删除后也不会复用
+pub type TaskId = u64;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ScheduledTask {
+    /// 任务 ID，由 `SchedulerState::add_task` 分配，旧状态文件加载时补齐
+    #[serde(default)]
+    pub id: TaskId,
+    /// 用户设置的标签，用于在列表和通知中区分任务
+    #[serde(default)]
+    pub label: Option<String>,
     pub task_type: TaskType,
+    /// Cron 表达式，仅在 `schedule` 为 Cron 时使用
     pub cron_expression: String,
+    /// 执行计划，旧状态文件没有该字段时按 Cron 处理
+    #[serde(default)]
+    pub schedule: Schedule,
+    /// 任务时区，未设置时使用全局默认时区
+    #[serde(default)]
+    pub timezone: Option<Tz>,
     pub enabled: bool,
+    /// 维护完成后的重启策略，仅对会重启系统的任务生效
+    #[serde(default)]
+    pub reboot_policy: RebootPolicy,
+    /// 失败后的重试策略
+    #[serde(default)]
+    pub retry_policy: RetryPolicy,
+    /// 停机期间错过执行时的处理策略
+    #[serde(default)]
+    pub missed_run_policy: MissedRunPolicy,
+    /// 执行时发送哪些通知
+    #[serde(default)]
+    pub notification_policy: NotificationPolicy,
+    /// 静默送达：通知照常发送但不响铃
+    #[serde(default)]
+    pub silent_delivery: bool,
+    /// 最近一次执行的开始时间
+    #[serde(default)]
+    pub last_run: Option<DateTime<Utc>>,
+    /// 最近一次执行的结果
+    #[serde(default)]
+    pub last_result: Option<MaintenanceResult>,
+    /// 已检查过错过执行的时间点，早于此时间的触发不再补偿
+    #[serde(default)]
+    pub missed_checked_at: Option<DateTime<Utc>>,
+    /// 事件任务最近一次触发的时间，在执行前保存，执行中重启系统后冷却仍然有效
+    #[serde(default)]
+    pub last_triggered_at: Option<DateTime<Utc>>,
 }
 
 impl ScheduledTask {
     pub fn new(task_type: TaskType, cron_expression: &str) -> Self {
         Self {
+            id: 0,
+            label: None,
             task_type,
             cron_expression: cron_expression.to_string(),
+            schedule: Schedule::Cron,
+            timezone: None,
             enabled: true,
+            reboot_policy: RebootPolicy::default(),
+            retry_policy: RetryPolicy::default(),
+            missed_run_policy: MissedRunPolicy::default(),
+            notification_policy: NotificationPolicy::default(),
+            silent_delivery: false,
+            last_run: None,
+            last_result: None,
+            missed_checked_at: Some(Utc::now()),
+            last_triggered_at: None,
+        }
+    }
+
+    /// 按非 Cron 计划创建任务
+    pub fn with_schedule(task_type: TaskType, schedule: Schedule) -> Self {
+        Self {
+            schedule,
+            ..Self::new(task_type, "")
+        }
+    }
+
+    /// 任务标题，例如 `#3 🚀 更新 Xray` 或 `#3 夜间更新`
+    pub fn title(&self) -> String {
+        match &self.label {
+            Some(label) => format!("#{} {}", self.id, label),
+            None => format!("#{} {}", self.id, self.task_type.get_display_name()),
+        }
+    }
+
+    /// 任务使用的时区
+    pub fn timezone(&self) -> Tz {
+        self.timezone.unwrap_or_else(timezone::default_timezone)
+    }
+
+    /// 计算 `last_run`/`missed_checked_at` 之后、`now` 之前错过的触发次数和最近一次触发时间
+    pub fn missed_runs(&self, now: DateTime<Local>) -> Option<(usize, DateTime<Tz>)> {
+        if !self.enabled {
+            return None;
+        }
+        let timezone = self.timezone();
+        let since = self.last_run.max(self.missed_checked_at)?.with_timezone(&timezone);
+        let runs = self.schedule.fire_times(&self.cron_expression, since, now.with_timezone(&timezone), MAX_MISSED_RUNS_COUNTED);
+        runs.last().map(|latest| (runs.len(), *latest))
+    }
+
+    /// 接下来的执行时间，按任务时区表示
+    pub fn next_runs(&self, now: DateTime<Local>, count: usize) -> Vec<DateTime<Tz>> {
+        self.schedule.next_runs(&self.cron_expression, now.with_timezone(&self.timezone()), count)
+    }
+
+    /// 执行计划描述，例如 `Cron: 0 4 * * *` 或 `每 6 小时`
+    pub fn describe_schedule(&self) -> String {
+        self.schedule.describe(&self.cron_expression, &self.timezone())
+    }
+
+    /// 上次执行的描述，用于任务列表
+    pub fn describe_last_run(&self) -> String {
+        let timezone = self.timezone();
+        match (self.last_run, &self.last_result) {
+            (Some(time), Some(result)) => format!("{} {} {}", cron::format_run_time(&time.with_timezone(&timezone)), result.icon(), result.label()),
+            (Some(time), None) => cron::format_run_time(&time.with_timezone(&timezone)),
+            (None, _) => "从未执行".to_string(),
         }
     }
 
     #[allow(dead_code)]
     pub fn get_display_name(&self) -> String {
-        format!("{} ({})", self.task_type.get_display_name(), self.cron_expression)
+        match self.schedule {
+            Schedule::Cron => format!("{} ({})", self.task_type.get_display_name(), self.cron_expression),
+            _ => format!("{} ({})", self.task_type.get_display_name(), self.describe_schedule()),
+        }
+    }
+}
+
+/// 统计错过执行次数的上限，避免长时间停机后遍历过多触发时间
+const MAX_MISSED_RUNS_COUNTED: usize = 1000;
+
+/// 停机期间错过执行时的处理策略
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum MissedRunPolicy {
+    /// 启动后立即补执行一次
+    RunOnce,
+    /// 仅发送通知
+    #[default]
+    NotifyOnly,
+    /// 忽略
+    Ignore,
+}
+
+impl MissedRunPolicy {
+    pub fn get_display_name(&self) -> &'static str {
+        match self {
+            MissedRunPolicy::RunOnce => "补执行一次",
+            MissedRunPolicy::NotifyOnly => "仅通知",
+            MissedRunPolicy::Ignore => "忽略",
+        }
+    }
+
+    /// 按 补执行 → 仅通知 → 忽略 的顺序切换
+    pub fn next(&self) -> Self {
+        match self {
+            MissedRunPolicy::RunOnce => MissedRunPolicy::NotifyOnly,
+            MissedRunPolicy::NotifyOnly => MissedRunPolicy::Ignore,
+            MissedRunPolicy::Ignore => MissedRunPolicy::RunOnce,
+        }
+    }
+}
+
+/// 维护任务完成后的重启策略
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum RebootPolicy {
+    /// 仅在检测到需要重启时重启
+    #[default]
+    IfRequired,
+    /// 总是重启
+    Always,
+    /// 从不自动重启
+    Never,
+}
+
+impl RebootPolicy {
+    pub fn get_display_name(&self) -> &'static str {
+        match self {
+            RebootPolicy::IfRequired => "按需重启",
+            RebootPolicy::Always => "总是重启",
+            RebootPolicy::Never => "从不重启",
+        }
+    }
+
+    /// 按 按需 → 总是 → 从不 的顺序切换
+    pub fn next(&self) -> Self {
+        match self {
+            RebootPolicy::IfRequired => RebootPolicy::Always,
+            RebootPolicy::Always => RebootPolicy::Never,
+            RebootPolicy::Never => RebootPolicy::IfRequired,
+        }
+    }
+
+    /// 结合重启检测结果决定是否重启
+    pub fn should_reboot(&self, status: &reboot::RebootStatus) -> bool {
+        match self {
+            RebootPolicy::IfRequired => status.required,
+            RebootPolicy::Always => true,
+            RebootPolicy::Never => false,
+        }
     }
 }
 
@@ -35,23 +233,48 @@ pub enum TaskType {
     RulesMaintenance,     // 规则维护
     UpdateXray,          // 更新 Xray
     UpdateSingbox,       // 更新 Sing-box
+    Pipeline(String),    // 任务流水线（按名称引用 SchedulerState 中的定义）
+    Custom {             // 自定义命令，程序需在白名单中
+        name: String,
+        program: String,
+        #[serde(default)]
+        args: Vec<String>,
+        #[serde(default)]
+        timeout: Option<u64>,
+    },
 }
 
+/// 内置任务类型，按菜单顺序排列
+const BUILTIN_TASK_TYPES: [TaskType; 5] = [
+    TaskType::SystemMaintenance,
+    TaskType::CoreMaintenance,
+    TaskType::RulesMaintenance,
+    TaskType::UpdateXray,
+    TaskType::UpdateSingbox,
+];
+
 impl TaskType {
-    pub fn get_display_name(&self) -> &'static str {
+    pub fn get_display_name(&self) -> String {
         match self {
-            TaskType::SystemMaintenance => "🔄 系统维护",
-            TaskType::CoreMaintenance => "🚀 核心维护",
-            TaskType::RulesMaintenance => "🌍 规则维护",
-            TaskType::UpdateXray => "🔧 更新 Xray",
-            TaskType::UpdateSingbox => "📦 更新 Sing-box",
+            TaskType::SystemMaintenance => "🔄 系统维护".to_string(),
+            TaskType::CoreMaintenance => "🚀 核心维护".to_string(),
+            TaskType::RulesMaintenance => "🌍 规则维护".to_string(),
+            TaskType::UpdateXray => "🔧 更新 Xray".to_string(),
+            TaskType::UpdateSingbox => "📦 更新 Sing-box".to_string(),
+            TaskType::Pipeline(name) => format!("🧩 {}", name),
+            TaskType::Custom { name, .. } => format!("⚙️ {}", name),
         }
     }
 
+    /// 任务完成后是否可能重启系统；流水线是否重启取决于其中的步骤
+    pub fn can_reboot(&self) -> bool {
+        matches!(self, TaskType::SystemMaintenance | TaskType::CoreMaintenance | TaskType::Pipeline(_))
+    }
+
     #[allow(dead_code)]
     pub fn get_cron_suggestions(&self) -> Vec<(&'static str, &'static str)> {
         match self {
-            TaskType::SystemMaintenance => vec![
+            TaskType::SystemMaintenance | TaskType::Pipeline(_) | TaskType::Custom { .. } => vec![
                 ("每天凌晨4点", "0 4 * * *"),
                 ("每周日凌晨4点", "0 4 * * Sun"),
                 ("每月1号凌晨4点", "0 4 1 * *"),
@@ -79,134 +302,256 @@ impl TaskType {
         }
     }
 
-    pub async fn execute(&self, bot: &Bot, chat_id: i64) -> Result<String> {
-        let task_name = self.get_display_name();
+    /// 任务类型标识，用于回调数据和按类型的配置（如超时）
+    pub fn key(&self) -> &'static str {
+        match self {
+            TaskType::SystemMaintenance => "system_maintenance",
+            TaskType::CoreMaintenance => "core_maintenance",
+            TaskType::RulesMaintenance => "rules_maintenance",
+            TaskType::UpdateXray => "update_xray",
+            TaskType::UpdateSingbox => "update_singbox",
+            TaskType::Pipeline(_) => "pipeline",
+            TaskType::Custom { .. } => "custom",
+        }
+    }
 
-        // 发送任务开始执行通知
-        let _ = bot.send_message(ChatId(chat_id),
-            format!("🔄 [定时任务] {} 开始执行...", task_name)).await;
+    /// 任务自身设置的超时时间，优先于全局配置
+    pub fn timeout_override(&self) -> Option<Duration> {
+        match self {
+            TaskType::Custom { timeout: Some(secs), .. } => Some(Duration::from_secs(*secs)),
+            _ => None,
+        }
+    }
+
+    /// 根据标识查找内置任务类型
+    pub fn from_key(key: &str) -> Option<TaskType> {
+        BUILTIN_TASK_TYPES.into_iter().find(|task_type| task_type.key() == key)
+    }
 
+    /// 所有内置任务类型的标识
+    pub fn builtin_keys() -> Vec<&'static str> {
+        BUILTIN_TASK_TYPES.iter().map(TaskType::key).collect()
+    }
+
+    /// 执行任务对应的操作本身，不包含通知和历史记录
+    pub(crate) async fn run_operation(&self) -> Result<String, SystemError> {
         match self {
-            TaskType::SystemMaintenance => {
-                match ops::perform_maintenance().await {
-                    Ok(log) => {
-                        let _ = bot.send_message(ChatId(chat_id),
-                            format!("✅ [定时任务] {} 执行成功:\n{}", task_name, log)).await;
-                        // 记录到维护历史
-                        record_maintenance(task_name, MaintenanceResult::Success, &log, None).await;
-                        
-                        // 系统维护完成后自动重启
-                        let _ = bot.send_message(ChatId(chat_id),
-                            "🔄 系统维护完成，将在 5 秒后自动重启...".to_string()).await;
-                        tokio::time::sleep(std::time::Duration::from_secs(5)).await;
-                        
-                        if let Err(e) = ops::reboot_system().await {
-                            let _ = bot.send_message(ChatId(chat_id),
-                                format!("❌ 自动重启失败: {}", e)).await;
-                        }
-                        
-                        Ok(format!("{} 完成", task_name))
-                    }
-                    Err(e) => {
-                        let user_message = e.user_message();
-                        let error_msg = format!("{}", e);
-                        let _ = bot.send_message(ChatId(chat_id),
-                            format!("❌ [定时任务] {} 执行失败:\n{}\n\n建议: {}", task_name, e,
-                                if e.is_retryable() { "可以稍后重试" } else { "请检查系统配置" })).await;
-                        // 记录到维护历史
-                        record_maintenance(task_name, MaintenanceResult::Failed, user_message, Some(&error_msg)).await;
-                        Err(anyhow!("{}", user_message))
+            TaskType::SystemMaintenance => ops::perform_maintenance().await,
+            // 是否重启由重启策略决定
+            TaskType::CoreMaintenance => ops::upgrade_core().await,
+            TaskType::RulesMaintenance => ops::maintain_rules().await,
+            TaskType::UpdateXray => ops::update_xray().await,
+            TaskType::UpdateSingbox => ops::update_singbox().await,
+            TaskType::Pipeline(name) => Err(SystemError::UnknownError(format!("流水线「{}」需要通过调度器执行", name))),
+            TaskType::Custom { name, .. } => Err(SystemError::UnknownError(format!("自定义命令「{}」需要通过调度器执行", name))),
+        }
+    }
+
+    /// 执行任务、流水线或自定义命令，返回日志和结果（流水线可能部分成功）
+    async fn run_with_options(&self, options: &ExecutionOptions) -> Result<(String, MaintenanceResult), SystemError> {
+        match (self, options.pipeline.as_ref()) {
+            (TaskType::Pipeline(_), Some(pipeline)) => pipeline.run().await,
+            (TaskType::Pipeline(name), None) => Err(SystemError::UnknownError(format!("流水线「{}」不存在", name))),
+            (TaskType::Custom { program, args, .. }, _) => {
+                // 每次执行前重新检查，白名单收紧后已有任务也不再执行
+                custom::check_allowed(program, &options.custom_allowlist)?;
+                let output = ops::run_custom_command(program, args).await?;
+                Ok((format!("$ {}\n{}", custom::command_line(program, args), output), MaintenanceResult::Success))
+            }
+            _ => self.run_operation().await.map(|log| (log, MaintenanceResult::Success)),
+        }
+    }
+
+    /// 执行任务并发送通知、记录维护历史，返回本次执行结果
+    pub async fn execute(&self, bot: &Bot, chat_id: i64, options: &ExecutionOptions) -> MaintenanceResult {
+        let task_name = self.get_display_name();
+        let source = &options.initiator;
+        let notification = options.notification_policy;
+        let silent = options.disable_notification;
+        let send = |text: String| bot.send_message(ChatId(chat_id), text).disable_notification(silent);
+
+        let lock = if options.wait_for_lock {
+            // 已有维护在执行时排队等待
+            let queue_bot = bot.clone();
+            MaintenanceLock::global()
+                .acquire(&task_name, source, |holder| {
+                    if !notification.notifies_progress() {
+                        return;
                     }
+                    let text = match holder {
+                        Some(holder) => format!("⏳ [{}] {} 排队等待中\n{}", source, task_name, holder.describe()),
+                        None => format!("⏳ [{}] {} 排队等待中", source, task_name),
+                    };
+                    tokio::spawn(async move {
+                        let _ = queue_bot.send_message(ChatId(chat_id), text).disable_notification(silent).await;
+                    });
+                })
+                .await
+        } else {
+            MaintenanceLock::global().try_acquire(&task_name, source)
+        };
+        let _guard = match lock {
+            Ok(guard) => guard,
+            Err(e) => {
+                log::error!("{} 无法获取维护锁: {}", task_name, e);
+                if notification.notifies_result(&MaintenanceResult::Failed, || true) {
+                    let _ = send(format!("🚫 [{}] {} 未执行: {}", source, task_name, e)).await;
                 }
+                return MaintenanceResult::Failed;
             }
-            TaskType::CoreMaintenance => {
-                match ops::maintain_core().await {
-                    Ok(log) => {
-                        let _ = bot.send_message(ChatId(chat_id),
-                            format!("✅ [定时任务] {} 执行成功:\n{}", task_name, log)).await;
-                        // 记录到维护历史
-                        record_maintenance(task_name, MaintenanceResult::Success, &log, None).await;
-                        Ok(format!("{} 完成", task_name))
-                    }
-                    Err(e) => {
-                        let user_message = e.user_message();
-                        let error_msg = format!("{}", e);
-                        let _ = bot.send_message(ChatId(chat_id),
-                            format!("❌ [定时任务] {} 执行失败:\n{}\n\n建议: {}", task_name, e,
-                                if e.is_retryable() { "可以稍后重试" } else { "请检查系统配置" })).await;
-                        // 记录到维护历史
-                        record_maintenance(task_name, MaintenanceResult::Failed, user_message, Some(&error_msg)).await;
-                        Err(anyhow!("{}", user_message))
-                    }
+        };
+
+        let job = jobs::register(&task_name);
+        let (deferred_tag, mut start_note) = match options.deferred_from {
+            Some(from) => ("（已推迟）", format!("\n⏳ 原定 {} 执行，因维护窗口推迟", cron::format_run_time(&from))),
+            None => ("", String::new()),
+        };
+        if let Some(trigger) = &options.trigger {
+            start_note.push_str(&format!("\n⚡ 触发条件: {}", trigger));
+        }
+
+        // 发送任务开始执行通知，附带取消按钮；不发送过程消息时可在 /jobs 列表中取消
+        let start_message = if notification.notifies_progress() {
+            send(format!("🔄 [{}] {} 开始执行...{}", source, task_name, start_note))
+                .reply_markup(InlineKeyboardMarkup::new(vec![vec![
+                    InlineKeyboardButton::callback("⛔ 取消", format!("cancel_job_{}", job.id())),
+                ]]))
+                .await
+                .ok()
+        } else {
+            None
+        };
+
+        let mut attempts = Vec::new();
+        let pipeline = options.pipeline.as_ref();
+        let outcome = job
+            .run(retry::run_with_retry(&options.retry_policy, options.timeout, &mut attempts, || self.run_with_options(options)))
+            .await;
+
+        // 任务已结束，移除取消按钮
+        if let Some(message) = start_message {
+            let _ = bot.edit_message_reply_markup(message.chat.id, message.id).await;
+        }
+
+        let attempt_note = if attempts.len() > 1 {
+            format!("{}（共尝试 {} 次）", deferred_tag, attempts.len())
+        } else {
+            deferred_tag.to_string()
+        };
+
+        match outcome {
+            JobOutcome::Completed(Ok((log, result))) => {
+                // 记录本次结果前取出上次成功的输出
+                let previous = match (notification, options.task_id) {
+                    (NotificationPolicy::ChangesOnly, Some(task_id)) => maintenance_history::last_successful_output(task_id).await,
+                    _ => None,
+                };
+                if notification.notifies_result(&result, || notification::has_changes(&log, previous.as_deref())) {
+                    let summary = if result == MaintenanceResult::Success { "执行成功" } else { "部分步骤失败" };
+                    let _ = send(format!("{} [{}] {} {}{}:\n{}", result.icon(), source, task_name, summary, attempt_note, log)).await;
+                }
+                // 记录到维护历史
+                record_maintenance_with_attempts(&task_name, result.clone(), &log, None, attempts, options.task_id, options.timezone).await;
+                let may_reboot = match pipeline {
+                    Some(pipeline) => pipeline.can_reboot(),
+                    None => self.can_reboot(),
+                };
+                if may_reboot {
+                    apply_reboot_policy(bot, chat_id, options).await;
                 }
+                result
             }
-            TaskType::RulesMaintenance => {
-                match ops::maintain_rules().await {
-                    Ok(log) => {
-                        let _ = bot.send_message(ChatId(chat_id),
-                            format!("✅ [定时任务] {} 执行成功:\n{}", task_name, log)).await;
-                        // 记录到维护历史
-                        record_maintenance(task_name, MaintenanceResult::Success, &log, None).await;
-                        Ok(format!("{} 完成", task_name))
-                    }
-                    Err(e) => {
-                        let user_message = e.user_message();
-                        let error_msg = format!("{}", e);
-                        let _ = bot.send_message(ChatId(chat_id),
-                            format!("❌ [定时任务] {} 执行失败:\n{}\n\n建议: {}", task_name, e,
-                                if e.is_retryable() { "可以稍后重试" } else { "请检查系统配置" })).await;
-                        // 记录到维护历史
-                        record_maintenance(task_name, MaintenanceResult::Failed, user_message, Some(&error_msg)).await;
-                        Err(anyhow!("{}", user_message))
-                    }
+            JobOutcome::Completed(Err(e)) => {
+                let user_message = e.user_message();
+                let error_msg = format!("{}", e);
+                if notification.notifies_result(&MaintenanceResult::Failed, || true) {
+                    let _ = send(format!("❌ [{}] {} 执行失败{}:\n{}\n\n建议: {}", source, task_name, attempt_note, e,
+                        if e.is_retryable() { "可以稍后重试" } else { "请检查系统配置" })).await;
                 }
+                // 记录到维护历史
+                record_maintenance_with_attempts(&task_name, MaintenanceResult::Failed, user_message, Some(&error_msg), attempts, options.task_id, options.timezone).await;
+                MaintenanceResult::Failed
             }
-            TaskType::UpdateXray => {
-                match ops::update_xray().await {
-                    Ok(log) => {
-                        let _ = bot.send_message(ChatId(chat_id),
-                            format!("✅ [定时任务] {} 执行成功:\n{}", task_name, log)).await;
-                        // 记录到维护历史
-                        record_maintenance(task_name, MaintenanceResult::Success, &log, None).await;
-                        Ok(format!("{} 完成", task_name))
-                    }
-                    Err(e) => {
-                        let user_message = e.user_message();
-                        let error_msg = format!("{}", e);
-                        let _ = bot.send_message(ChatId(chat_id),
-                            format!("❌ [定时任务] {} 执行失败:\n{}\n\n建议: {}", task_name, e,
-                                if e.is_retryable() { "可以稍后重试" } else { "请检查系统配置" })).await;
-                        // 记录到维护历史
-                        record_maintenance(task_name, MaintenanceResult::Failed, user_message, Some(&error_msg)).await;
-                        Err(anyhow!("{}", user_message))
-                    }
+            JobOutcome::TimedOut => {
+                let error_msg = format!("单次执行超过 {} 秒，已终止", options.timeout.as_secs());
+                if notification.notifies_result(&MaintenanceResult::TimedOut, || true) {
+                    let _ = send(format!("⏱️ [{}] {} 执行超时{}: {}", source, task_name, attempt_note, error_msg)).await;
                 }
+                record_maintenance_with_attempts(&task_name, MaintenanceResult::TimedOut, "执行超时", Some(&error_msg), attempts, options.task_id, options.timezone).await;
+                MaintenanceResult::TimedOut
             }
-            TaskType::UpdateSingbox => {
-                match ops::update_singbox().await {
-                    Ok(log) => {
-                        let _ = bot.send_message(ChatId(chat_id),
-                            format!("✅ [定时任务] {} 执行成功:\n{}", task_name, log)).await;
-                        // 记录到维护历史
-                        record_maintenance(task_name, MaintenanceResult::Success, &log, None).await;
-                        Ok(format!("{} 完成", task_name))
-                    }
-                    Err(e) => {
-                        let user_message = e.user_message();
-                        let error_msg = format!("{}", e);
-                        let _ = bot.send_message(ChatId(chat_id),
-                            format!("❌ [定时任务] {} 执行失败:\n{}\n\n建议: {}", task_name, e,
-                                if e.is_retryable() { "可以稍后重试" } else { "请检查系统配置" })).await;
-                        // 记录到维护历史
-                        record_maintenance(task_name, MaintenanceResult::Failed, user_message, Some(&error_msg)).await;
-                        Err(anyhow!("{}", user_message))
-                    }
+            JobOutcome::Cancelled => {
+                if notification.notifies_result(&MaintenanceResult::Cancelled, || true) {
+                    let _ = send(format!("⛔ [{}] {} 已取消{}", source, task_name, attempt_note)).await;
                 }
+                record_maintenance_with_attempts(&task_name, MaintenanceResult::Cancelled, "已通过 Telegram 取消", None, attempts, options.task_id, options.timezone).await;
+                MaintenanceResult::Cancelled
             }
         }
     }
 }
 
+/// 定时任务触发时的发起人名称
+pub const SCHEDULED_INITIATOR: &str = "定时任务";
+
+/// 单次执行的参数
+#[derive(Debug, Clone)]
+pub struct ExecutionOptions {
+    pub reboot_policy: RebootPolicy,
+    pub retry_policy: RetryPolicy,
+    pub timeout: Duration,
+    /// 流水线任务执行时的定义
+    pub pipeline: Option<Pipeline>,
+    /// 发起人，显示在通知和维护锁信息中
+    pub initiator: String,
+    /// 维护锁被占用时排队等待，否则直接放弃
+    pub wait_for_lock: bool,
+    /// 自定义命令允许执行的程序
+    pub custom_allowlist: Vec<String>,
+    /// 因维护窗口推迟执行时的原定时间
+    pub deferred_from: Option<DateTime<Tz>>,
+    /// 发送哪些通知，手动执行时总是通知
+    pub notification_policy: NotificationPolicy,
+    /// 通知不响铃（Telegram `disable_notification`）
+    pub disable_notification: bool,
+    /// 事件触发时的触发原因
+    pub trigger: Option<String>,
+    /// 任务时区，维护历史按此时区显示，未指定时使用全局默认时区
+    pub timezone: Option<Tz>,
+    /// 执行的定时任务 ID，临时构造的任务（如手动运行流水线）为空
+    pub task_id: Option<TaskId>,
+}
+
+/// 维护完成后按策略决定是否重启，自动重启前总是发送提醒
+async fn apply_reboot_policy(bot: &Bot, chat_id: i64, options: &ExecutionOptions) {
+    let policy = options.reboot_policy;
+    let notification = options.notification_policy;
+    let status = reboot::check_reboot_required().await;
+
+    if !policy.should_reboot(&status) {
+        let message = match (policy, status.required) {
+            (RebootPolicy::Never, true) if notification != NotificationPolicy::Silent => {
+                format!("⚠️ 重启策略为「从不重启」，请手动重启系统\n{}", status.describe())
+            }
+            (_, false) if notification.notifies_progress() => format!("ℹ️ 维护完成，无需重启\n{}", status.describe()),
+            _ => return,
+        };
+        let _ = bot.send_message(ChatId(chat_id), message).disable_notification(options.disable_notification).await;
+        return;
+    }
+
+    let _ = bot.send_message(ChatId(chat_id),
+        format!("🔄 维护完成，将在 5 秒后自动重启 ({})\n{}", policy.get_display_name(), status.describe()))
+        .disable_notification(options.disable_notification)
+        .await;
+    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+
+    if let Err(e) = ops::reboot_system().await {
+        let _ = bot.send_message(ChatId(chat_id),
+            format!("❌ 自动重启失败: {}", e)).await;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -262,6 +607,59 @@ mod tests {
         assert_eq!(task.task_type, TaskType::SystemMaintenance);
         assert_eq!(task.cron_expression, "0 4 * * *");
         assert!(task.enabled);
+        // 旧状态文件没有重启策略字段，默认按需重启
+        assert_eq!(task.reboot_policy, RebootPolicy::IfRequired);
+        // 也没有执行计划字段，按 Cron 表达式执行
+        assert_eq!(task.schedule, Schedule::Cron);
+    }
+
+    #[test]
+    fn test_interval_task_round_trip() {
+        let schedule = Schedule::Interval { every_secs: 6 * 3600, anchor: Utc::now() };
+        let task = ScheduledTask::with_schedule(TaskType::RulesMaintenance, schedule.clone());
+        let json = serde_json::to_string(&task).unwrap();
+        assert!(json.contains("\"type\":\"interval\""));
+
+        let deserialized: ScheduledTask = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.schedule, schedule);
+        assert_eq!(deserialized.get_display_name(), "🌍 规则维护 (每 6 小时)");
+        assert_eq!(deserialized.next_runs(Local::now(), 3).len(), 3);
+    }
+
+    #[test]
+    fn test_reboot_policy_decision() {
+        let required = reboot::RebootStatus { required: true, reasons: vec!["内核已更新".to_string()] };
+        let not_required = reboot::RebootStatus::default();
+
+        assert!(RebootPolicy::IfRequired.should_reboot(&required));
+        assert!(!RebootPolicy::IfRequired.should_reboot(&not_required));
+        assert!(RebootPolicy::Always.should_reboot(&not_required));
+        assert!(!RebootPolicy::Never.should_reboot(&required));
+
+        assert_eq!(RebootPolicy::IfRequired.next().next().next(), RebootPolicy::IfRequired);
+        assert!(TaskType::CoreMaintenance.can_reboot());
+        assert!(!TaskType::UpdateXray.can_reboot());
+    }
+
+    #[test]
+    fn test_custom_task_type() {
+        let task_type = TaskType::Custom {
+            name: "重载 Nginx".to_string(),
+            program: "/usr/bin/systemctl".to_string(),
+            args: vec!["reload".to_string(), "nginx".to_string()],
+            timeout: Some(120),
+        };
+        assert_eq!(task_type.key(), "custom");
+        assert_eq!(task_type.get_display_name(), "⚙️ 重载 Nginx");
+        assert_eq!(task_type.timeout_override(), Some(Duration::from_secs(120)));
+        assert_eq!(TaskType::UpdateXray.timeout_override(), None);
+        assert!(!task_type.can_reboot());
+        assert!(TaskType::from_key("custom").is_none());
+
+        let task = ScheduledTask::new(task_type.clone(), "0 4 * * *");
+        let json = serde_json::to_string(&task).unwrap();
+        let deserialized: ScheduledTask = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.task_type, task_type);
     }
 
     #[test]
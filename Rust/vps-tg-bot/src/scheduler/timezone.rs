@@ -0,0 +1,68 @@
+//! 任务时区
+//!
+//! 任务可以单独指定 IANA 时区（如 `Asia/Shanghai`），未指定时使用全局默认时区
+//! `DEFAULT_TIMEZONE`，两者都未设置时使用系统时区。Cron 计划按任务时区触发，
+//! 执行时间预览和维护历史也按任务时区显示，维护窗口和冻结期按全局默认时区解释。
+//! 调度器直接使用 IANA 时区创建任务，夏令时切换无需重启
+
+use chrono::{DateTime, TimeZone};
+use chrono_tz::Tz;
+use once_cell::sync::Lazy;
+use std::sync::RwLock;
+
+/// 系统时区，无法识别时使用 UTC
+static SYSTEM_TIMEZONE: Lazy<Tz> = Lazy::new(|| {
+    iana_time_zone::get_timezone()
+        .ok()
+        .and_then(|name| name.parse().ok())
+        .unwrap_or_else(|| {
+            log::warn!("无法识别系统时区，使用 UTC");
+            Tz::UTC
+        })
+});
+
+/// 全局默认时区，启动调度器时根据配置设置
+static DEFAULT_TIMEZONE: Lazy<RwLock<Option<Tz>>> = Lazy::new(|| RwLock::new(None));
+
+/// 解析 IANA 时区名称
+pub fn parse(name: &str) -> Result<Tz, String> {
+    name.trim()
+        .parse()
+        .map_err(|_| format!("未知的时区 '{}'，请使用 IANA 时区名称，例如 Asia/Shanghai", name.trim()))
+}
+
+pub fn system_timezone() -> Tz {
+    *SYSTEM_TIMEZONE
+}
+
+/// 全局默认时区，未配置时为系统时区
+pub fn default_timezone() -> Tz {
+    DEFAULT_TIMEZONE.read().unwrap().unwrap_or_else(system_timezone)
+}
+
+pub fn set_default_timezone(timezone: Option<Tz>) {
+    *DEFAULT_TIMEZONE.write().unwrap() = timezone;
+}
+
+/// 按指定时区格式化时间，未指定时使用全局默认时区。`%Z` 显示时区缩写，例如 `2026-10-17 04:00:00 CST`
+pub fn format_in<Z: TimeZone>(time: &DateTime<Z>, timezone: Option<Tz>, format: &str) -> String {
+    time.with_timezone(&timezone.unwrap_or_else(default_timezone)).format(format).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn test_parse_timezone() {
+        assert_eq!(parse("Asia/Shanghai").unwrap(), chrono_tz::Asia::Shanghai);
+        assert_eq!(parse(" UTC ").unwrap(), Tz::UTC);
+        assert!(parse("Asia/Nowhere").unwrap_err().contains("Asia/Nowhere"));
+        assert!(parse("+08:00").is_err());
+
+        let time = Utc.with_ymd_and_hms(2026, 10, 1, 20, 0, 0).unwrap();
+        let shanghai = time.with_timezone(&chrono_tz::Asia::Shanghai);
+        assert_eq!(shanghai.format("%Y-%m-%d %H:%M %Z").to_string(), "2026-10-02 04:00 CST");
+    }
+}
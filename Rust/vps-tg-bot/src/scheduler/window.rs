@@ -0,0 +1,348 @@
+//! 维护窗口与冻结期
+//!
+//! 维护窗口限制定时任务只能在指定的星期和时间段内执行，例如 `Mon-Fri 02:00-06:00`，
+//! 结束时间早于开始时间表示跨越午夜。冻结期是按日期指定的禁止维护时段，
+//! 例如 `2026-12-24..2026-12-26`。未配置维护窗口时除冻结期外任何时间都允许执行。
+//! 定时任务在窗口外触发时按策略推迟到下一个窗口开始或直接跳过，
+//! 冻结期内的手动维护需要额外确认。窗口和冻结期按全局默认时区解释，
+//! 与未单独设置时区的任务的 Cron 计划一致
+
+use crate::config::types::SchedulerConfig;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Weekday};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// 查找下一个可执行时间时向后搜索的天数
+const MAX_SEARCH_DAYS: i64 = 400;
+
+/// 定时任务在维护窗口外或冻结期内触发时的处理方式
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum OutsideWindowPolicy {
+    /// 推迟到下一个允许执行的时间
+    #[default]
+    Defer,
+    /// 跳过本次执行
+    Skip,
+}
+
+impl FromStr for OutsideWindowPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "defer" => Ok(OutsideWindowPolicy::Defer),
+            "skip" => Ok(OutsideWindowPolicy::Skip),
+            _ => Err(format!("未知的维护窗口策略 '{}'，可选 defer 或 skip", s)),
+        }
+    }
+}
+
+/// 每周重复的维护时间段
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowRule {
+    days: Vec<Weekday>,
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl FromStr for WindowRule {
+    type Err = String;
+
+    /// 解析 `[星期] HH:MM-HH:MM`，星期支持 `Mon-Fri`、`Sat,Sun`，省略时表示每天
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split_whitespace().collect();
+        let (days, times) = match parts.as_slice() {
+            [times] => (ALL_WEEKDAYS.to_vec(), *times),
+            [days, times] => (parse_weekdays(days)?, *times),
+            _ => return Err(format!("维护窗口格式应为 [星期] HH:MM-HH:MM: {}", s)),
+        };
+        let (start, end) = times
+            .split_once('-')
+            .ok_or_else(|| format!("维护窗口时间格式应为 HH:MM-HH:MM: {}", s))?;
+        let parse_time = |text: &str| {
+            NaiveTime::parse_from_str(text, "%H:%M").map_err(|_| format!("无效的时间 '{}': {}", text, s))
+        };
+        let (start, end) = (parse_time(start)?, parse_time(end)?);
+        if start == end {
+            return Err(format!("维护窗口的开始和结束时间不能相同: {}", s));
+        }
+        Ok(Self { days, start, end })
+    }
+}
+
+impl WindowRule {
+    /// 从 `day` 开始的这一次窗口在 `timezone` 中的起止时间
+    fn occurrence<Z: TimeZone>(&self, day: NaiveDate, timezone: &Z) -> Option<(DateTime<Z>, DateTime<Z>)> {
+        if !self.days.contains(&day.weekday()) {
+            return None;
+        }
+        let end_day = if self.end > self.start { day } else { day.succ_opt()? };
+        let start = timezone.from_local_datetime(&day.and_time(self.start)).earliest()?;
+        let end = timezone.from_local_datetime(&end_day.and_time(self.end)).earliest()?;
+        Some((start, end))
+    }
+}
+
+/// 按日期指定的冻结期，包含首尾两天
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlackoutPeriod {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+impl FromStr for BlackoutPeriod {
+    type Err = String;
+
+    /// 解析 `YYYY-MM-DD` 或 `YYYY-MM-DD..YYYY-MM-DD`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_date = |text: &str| {
+            NaiveDate::parse_from_str(text.trim(), "%Y-%m-%d").map_err(|_| format!("无效的冻结日期 '{}'", text.trim()))
+        };
+        let (start, end) = match s.split_once("..") {
+            Some((start, end)) => (parse_date(start)?, parse_date(end)?),
+            None => {
+                let date = parse_date(s)?;
+                (date, date)
+            }
+        };
+        if end < start {
+            return Err(format!("冻结期结束日期早于开始日期: {}", s));
+        }
+        Ok(Self { start, end })
+    }
+}
+
+impl BlackoutPeriod {
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.start <= date && date <= self.end
+    }
+}
+
+impl fmt::Display for BlackoutPeriod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.start == self.end {
+            write!(f, "{}", self.start)
+        } else {
+            write!(f, "{} 至 {}", self.start, self.end)
+        }
+    }
+}
+
+/// 某一时刻是否允许执行定时维护
+#[derive(Debug, Clone, PartialEq)]
+pub enum WindowStatus {
+    Allowed,
+    OutsideWindow,
+    Blackout(BlackoutPeriod),
+}
+
+impl WindowStatus {
+    /// 不允许执行时的原因
+    pub fn reason(&self) -> Option<String> {
+        match self {
+            WindowStatus::Allowed => None,
+            WindowStatus::OutsideWindow => Some("不在维护窗口内".to_string()),
+            WindowStatus::Blackout(period) => Some(format!("处于维护冻结期 ({})", period)),
+        }
+    }
+}
+
+/// 维护窗口和冻结期配置
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MaintenanceWindows {
+    rules: Vec<WindowRule>,
+    blackouts: Vec<BlackoutPeriod>,
+}
+
+impl MaintenanceWindows {
+    pub fn parse(windows: &[String], blackouts: &[String]) -> Result<Self, String> {
+        Ok(Self {
+            rules: windows.iter().map(|rule| rule.parse()).collect::<Result<_, _>>()?,
+            blackouts: blackouts.iter().map(|period| period.parse()).collect::<Result<_, _>>()?,
+        })
+    }
+
+    pub fn from_config(config: &SchedulerConfig) -> Result<Self, String> {
+        Self::parse(&config.maintenance_windows, &config.blackout_dates)
+    }
+
+    /// `time` 所在的冻结期，日期按 `time` 的时区计算
+    pub fn blackout_at<Z: TimeZone>(&self, time: &DateTime<Z>) -> Option<&BlackoutPeriod> {
+        self.blackouts.iter().find(|period| period.contains(time.date_naive()))
+    }
+
+    /// `time` 是否允许执行，窗口按 `time` 的时区解释
+    pub fn check<Z: TimeZone>(&self, time: &DateTime<Z>) -> WindowStatus {
+        if let Some(period) = self.blackout_at(time) {
+            return WindowStatus::Blackout(period.clone());
+        }
+        if self.rules.is_empty() || self.window_containing(time).is_some() {
+            WindowStatus::Allowed
+        } else {
+            WindowStatus::OutsideWindow
+        }
+    }
+
+    /// 包含 `time` 的窗口（前一天开始的跨午夜窗口也算）
+    fn window_containing<Z: TimeZone>(&self, time: &DateTime<Z>) -> Option<(DateTime<Z>, DateTime<Z>)> {
+        let timezone = time.timezone();
+        let today = time.date_naive();
+        [today.pred_opt(), Some(today)]
+            .into_iter()
+            .flatten()
+            .flat_map(|day| self.rules.iter().filter_map(|rule| rule.occurrence(day, &timezone)).collect::<Vec<_>>())
+            .find(|(start, end)| start <= time && time < end)
+    }
+
+    /// `after` 之后（含）最早允许执行的时间，找不到时返回 None
+    pub fn next_allowed<Z: TimeZone>(&self, after: &DateTime<Z>) -> Option<DateTime<Z>> {
+        let timezone = after.timezone();
+        let today = after.date_naive();
+        let mut best: Option<DateTime<Z>> = None;
+
+        for offset in -1..=MAX_SEARCH_DAYS {
+            let day = today + Duration::days(offset);
+            if best.as_ref().is_some_and(|best| best.date_naive() < day) {
+                break;
+            }
+
+            let candidates: Vec<DateTime<Z>> = if self.rules.is_empty() {
+                // 没有窗口限制时，当天或之后某天的零点即可
+                let start = if day == today {
+                    Some(after.clone())
+                } else {
+                    timezone.from_local_datetime(&day.and_time(NaiveTime::MIN)).earliest()
+                };
+                start.into_iter().filter(|start| start >= after).collect()
+            } else {
+                self.rules
+                    .iter()
+                    .filter_map(|rule| rule.occurrence(day, &timezone))
+                    .filter(|(_, end)| end > after)
+                    .map(|(start, _)| start.max(after.clone()))
+                    .collect()
+            };
+
+            for candidate in candidates {
+                if self.blackout_at(&candidate).is_none() && best.as_ref().is_none_or(|best| candidate < *best) {
+                    best = Some(candidate);
+                }
+            }
+        }
+        best
+    }
+}
+
+const ALL_WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+/// 解析 `Mon-Fri`、`Sat,Sun` 形式的星期列表
+fn parse_weekdays(text: &str) -> Result<Vec<Weekday>, String> {
+    let parse_day = |day: &str| day.parse::<Weekday>().map_err(|_| format!("无效的星期 '{}'", day));
+    let mut days = Vec::new();
+    for part in text.split(',') {
+        match part.split_once('-') {
+            Some((from, to)) => {
+                let (mut day, to) = (parse_day(from)?, parse_day(to)?);
+                days.push(day);
+                while day != to {
+                    day = day.succ();
+                    days.push(day);
+                }
+            }
+            None => days.push(parse_day(part)?),
+        }
+    }
+    Ok(days)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::Local;
+
+    fn local(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    fn windows(rules: &[&str], blackouts: &[&str]) -> MaintenanceWindows {
+        let to_strings = |items: &[&str]| items.iter().map(|item| item.to_string()).collect::<Vec<_>>();
+        MaintenanceWindows::parse(&to_strings(rules), &to_strings(blackouts)).unwrap()
+    }
+
+    #[test]
+    fn test_parse_rules_and_blackouts() {
+        let rule: WindowRule = "Mon-Fri 02:00-06:00".parse().unwrap();
+        assert_eq!(rule.days.len(), 5);
+        let rule: WindowRule = "Sat,Sun 22:00-02:00".parse().unwrap();
+        assert_eq!(rule.days, vec![Weekday::Sat, Weekday::Sun]);
+        assert_eq!("03:00-05:00".parse::<WindowRule>().unwrap().days.len(), 7);
+        // 周日到周二的范围跨越周末
+        assert_eq!("Sun-Tue 01:00-02:00".parse::<WindowRule>().unwrap().days.len(), 3);
+
+        assert!("Mon-Fri".parse::<WindowRule>().is_err());
+        assert!("Someday 02:00-06:00".parse::<WindowRule>().is_err());
+        assert!("02:00-02:00".parse::<WindowRule>().is_err());
+
+        let period: BlackoutPeriod = "2026-12-24..2026-12-26".parse().unwrap();
+        assert_eq!(period.to_string(), "2026-12-24 至 2026-12-26");
+        assert!("2026-12-26..2026-12-24".parse::<BlackoutPeriod>().is_err());
+        assert_eq!("defer".parse::<OutsideWindowPolicy>().unwrap(), OutsideWindowPolicy::Defer);
+        assert!("later".parse::<OutsideWindowPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_check_and_next_allowed_with_windows() {
+        // 2026-11-02 是周一
+        let windows = windows(&["Mon-Fri 02:00-06:00", "Sat 22:00-02:00"], &[]);
+        assert_eq!(windows.check(&local(2026, 11, 2, 3, 0)), WindowStatus::Allowed);
+        assert_eq!(windows.check(&local(2026, 11, 2, 6, 0)), WindowStatus::OutsideWindow);
+        // 周六开始的跨午夜窗口在周日凌晨仍有效
+        assert_eq!(windows.check(&local(2026, 11, 8, 1, 0)), WindowStatus::Allowed);
+
+        assert_eq!(windows.next_allowed(&local(2026, 11, 2, 3, 0)), Some(local(2026, 11, 2, 3, 0)));
+        assert_eq!(windows.next_allowed(&local(2026, 11, 2, 7, 0)), Some(local(2026, 11, 3, 2, 0)));
+        assert_eq!(windows.next_allowed(&local(2026, 11, 6, 7, 0)), Some(local(2026, 11, 7, 22, 0)));
+    }
+
+    #[test]
+    fn test_blackouts_block_windows() {
+        let windows = windows(&["02:00-06:00"], &["2026-12-24..2026-12-26"]);
+        let status = windows.check(&local(2026, 12, 25, 3, 0));
+        assert!(matches!(status, WindowStatus::Blackout(_)));
+        assert!(status.reason().unwrap().contains("2026-12-24 至 2026-12-26"));
+        assert_eq!(windows.next_allowed(&local(2026, 12, 24, 1, 0)), Some(local(2026, 12, 27, 2, 0)));
+
+        // 没有窗口限制时冻结期结束后的零点即可执行
+        let windows = self::windows(&[], &["2026-12-25"]);
+        assert_eq!(windows.check(&local(2026, 12, 24, 12, 0)), WindowStatus::Allowed);
+        assert_eq!(windows.next_allowed(&local(2026, 12, 25, 12, 0)), Some(local(2026, 12, 26, 0, 0)));
+        assert_eq!(MaintenanceWindows::default().check(&local(2026, 12, 25, 12, 0)), WindowStatus::Allowed);
+    }
+
+    #[test]
+    fn test_windows_follow_the_given_timezone() {
+        let windows = windows(&["02:00-05:00"], &["2026-10-18"]);
+        // 北京时间 03:00 是 UTC 前一天 19:00
+        let time = chrono::Utc.with_ymd_and_hms(2026, 10, 16, 19, 0, 0).unwrap();
+        let shanghai = time.with_timezone(&chrono_tz::Asia::Shanghai);
+        assert_eq!(windows.check(&shanghai), WindowStatus::Allowed);
+        assert_eq!(windows.check(&time), WindowStatus::OutsideWindow);
+        assert_eq!(windows.next_allowed(&time).unwrap(), chrono::Utc.with_ymd_and_hms(2026, 10, 17, 2, 0, 0).unwrap());
+
+        // 冻结日期也按所给时区计算：北京时间已是 10-18
+        let evening = chrono::Utc.with_ymd_and_hms(2026, 10, 17, 18, 0, 0).unwrap();
+        assert!(windows.blackout_at(&evening).is_none());
+        assert!(windows.blackout_at(&evening.with_timezone(&chrono_tz::Asia::Shanghai)).is_some());
+    }
+}
@@ -0,0 +1,258 @@
+//! 挂载点磁盘与 inode 使用情况
+//!
+//! 从 /proc/self/mounts 读取挂载表，过滤伪文件系统与重复的绑定挂载，
+//! 再通过 statvfs 获取每个挂载点的空间与 inode 使用量
+
+use std::collections::HashSet;
+use std::ffi::CString;
+use std::io;
+
+/// 挂载表路径
+const MOUNTS_PATH: &str = "/proc/self/mounts";
+
+/// 不计入磁盘统计的伪文件系统类型
+const PSEUDO_FILESYSTEMS: &[&str] = &[
+    "autofs",
+    "binfmt_misc",
+    "bpf",
+    "cgroup",
+    "cgroup2",
+    "configfs",
+    "debugfs",
+    "devpts",
+    "devtmpfs",
+    "efivarfs",
+    "fuse.lxcfs",
+    "fusectl",
+    "hugetlbfs",
+    "mqueue",
+    "nsfs",
+    "overlay",
+    "proc",
+    "pstore",
+    "ramfs",
+    "rpc_pipefs",
+    "securityfs",
+    "selinuxfs",
+    "squashfs",
+    "sysfs",
+    "tmpfs",
+    "tracefs",
+];
+
+/// 单个挂载点的使用情况
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MountUsage {
+    pub mount_point: String,
+    pub fs_type: String,
+    pub used_bytes: u64,
+    pub total_bytes: u64,
+    pub inodes_used: u64,
+    pub inodes_total: u64,
+}
+
+impl MountUsage {
+    /// 空间使用率（百分比）
+    pub fn usage_percent(&self) -> f32 {
+        percent(self.used_bytes, self.total_bytes)
+    }
+
+    /// inode 使用率（百分比），文件系统不支持 inode 统计时返回 None
+    pub fn inode_percent(&self) -> Option<f32> {
+        if self.inodes_total == 0 {
+            None
+        } else {
+            Some(percent(self.inodes_used, self.inodes_total))
+        }
+    }
+}
+
+/// 挂载表中的一条记录
+#[derive(Debug, Clone, PartialEq)]
+pub struct MountEntry {
+    pub device: String,
+    pub mount_point: String,
+    pub fs_type: String,
+}
+
+/// 判断是否为伪文件系统
+pub fn is_pseudo_filesystem(fs_type: &str) -> bool {
+    PSEUDO_FILESYSTEMS.contains(&fs_type)
+}
+
+/// 解析 /proc/mounts 格式的挂载表
+///
+/// 伪文件系统会被过滤；同一设备的多个挂载（如 Docker 的绑定挂载）只保留第一个
+pub fn parse_mounts(content: &str) -> Vec<MountEntry> {
+    let mut seen_devices = HashSet::new();
+    let mut entries = Vec::new();
+
+    for line in content.lines() {
+        let mut fields = line.split_whitespace();
+        let (Some(device), Some(mount_point), Some(fs_type)) = (fields.next(), fields.next(), fields.next()) else {
+            continue;
+        };
+
+        if is_pseudo_filesystem(fs_type) {
+            continue;
+        }
+
+        let device = unescape_mount_field(device);
+        if !seen_devices.insert(device.clone()) {
+            continue;
+        }
+
+        entries.push(MountEntry {
+            device,
+            mount_point: unescape_mount_field(mount_point),
+            fs_type: fs_type.to_string(),
+        });
+    }
+
+    entries
+}
+
+/// 还原挂载表中的八进制转义（例如空格写作 \040）
+fn unescape_mount_field(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && i + 3 < bytes.len() && bytes[i + 1..i + 4].iter().all(|b| (b'0'..=b'7').contains(b)) {
+            let value = (bytes[i + 1] - b'0') as u32 * 64 + (bytes[i + 2] - b'0') as u32 * 8 + (bytes[i + 3] - b'0') as u32;
+            if let Ok(byte) = u8::try_from(value) {
+                out.push(byte);
+                i += 4;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// 通过 statvfs 获取挂载点使用情况
+pub fn statvfs_usage(entry: &MountEntry) -> io::Result<MountUsage> {
+    let path = CString::new(entry.mount_point.as_str())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: path 是合法的 C 字符串，stat 指向有效的可写内存
+    let ret = unsafe { libc::statvfs(path.as_ptr(), &mut stat) };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let fragment_size = stat.f_frsize as u64;
+    let total_bytes = stat.f_blocks as u64 * fragment_size;
+    let free_bytes = stat.f_bfree as u64 * fragment_size;
+    let inodes_total = stat.f_files as u64;
+    let inodes_free = stat.f_ffree as u64;
+
+    Ok(MountUsage {
+        mount_point: entry.mount_point.clone(),
+        fs_type: entry.fs_type.clone(),
+        used_bytes: total_bytes.saturating_sub(free_bytes),
+        total_bytes,
+        inodes_used: inodes_total.saturating_sub(inodes_free),
+        inodes_total,
+    })
+}
+
+/// 获取所有真实挂载点的使用情况
+pub fn get_mount_usages() -> Vec<MountUsage> {
+    let content = match std::fs::read_to_string(MOUNTS_PATH) {
+        Ok(content) => content,
+        Err(e) => {
+            log::warn!("⚠️  读取挂载表失败: {}", e);
+            return Vec::new();
+        }
+    };
+
+    parse_mounts(&content)
+        .iter()
+        .filter_map(|entry| match statvfs_usage(entry) {
+            // 容量为 0 的文件系统没有统计意义
+            Ok(usage) if usage.total_bytes > 0 => Some(usage),
+            Ok(_) => None,
+            Err(e) => {
+                log::debug!("statvfs {} 失败: {}", entry.mount_point, e);
+                None
+            }
+        })
+        .collect()
+}
+
+fn percent(used: u64, total: u64) -> f32 {
+    if total == 0 {
+        return 0.0;
+    }
+    (used as f64 / total as f64 * 100.0) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_MOUNTS: &str = "\
+sysfs /sys sysfs rw,nosuid,nodev,noexec,relatime 0 0
+proc /proc proc rw,nosuid,nodev,noexec,relatime 0 0
+udev /dev devtmpfs rw,nosuid,relatime,size=1985032k 0 0
+tmpfs /run tmpfs rw,nosuid,nodev,noexec,relatime,size=401880k 0 0
+/dev/vda1 / ext4 rw,relatime,errors=remount-ro 0 0
+/dev/vda15 /boot/efi vfat rw,relatime,fmask=0077 0 0
+/dev/vdb1 /var xfs rw,relatime 0 0
+overlay /var/lib/docker/overlay2/abc/merged overlay rw,relatime,lowerdir=/x 0 0
+/dev/vda1 /var/lib/docker/containers/abc/hosts ext4 rw,relatime 0 0
+/dev/vdc1 /mnt/my\\040data ext4 rw,relatime 0 0
+";
+
+    #[test]
+    fn test_parse_mounts_filters_pseudo_and_duplicates() {
+        let entries = parse_mounts(SAMPLE_MOUNTS);
+        let mount_points: Vec<&str> = entries.iter().map(|e| e.mount_point.as_str()).collect();
+
+        assert_eq!(mount_points, vec!["/", "/boot/efi", "/var", "/mnt/my data"]);
+        assert_eq!(entries[2].fs_type, "xfs");
+        assert_eq!(entries[0].device, "/dev/vda1");
+    }
+
+    #[test]
+    fn test_is_pseudo_filesystem() {
+        assert!(is_pseudo_filesystem("tmpfs"));
+        assert!(is_pseudo_filesystem("overlay"));
+        assert!(!is_pseudo_filesystem("ext4"));
+        assert!(!is_pseudo_filesystem("btrfs"));
+    }
+
+    #[test]
+    fn test_mount_usage_percentages() {
+        let usage = MountUsage {
+            mount_point: "/var".to_string(),
+            fs_type: "xfs".to_string(),
+            used_bytes: 93,
+            total_bytes: 100,
+            inodes_used: 10,
+            inodes_total: 40,
+        };
+        assert_eq!(usage.usage_percent(), 93.0);
+        assert_eq!(usage.inode_percent(), Some(25.0));
+
+        let no_inodes = MountUsage { inodes_total: 0, ..usage };
+        assert_eq!(no_inodes.inode_percent(), None);
+    }
+
+    #[test]
+    fn test_statvfs_root() {
+        let entry = MountEntry {
+            device: "rootfs".to_string(),
+            mount_point: "/".to_string(),
+            fs_type: "ext4".to_string(),
+        };
+        let usage = statvfs_usage(&entry).expect("statvfs / 应该成功");
+        assert!(usage.used_bytes <= usage.total_bytes);
+    }
+}
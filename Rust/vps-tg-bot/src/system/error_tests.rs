@@ -336,6 +336,7 @@ mod tests {
                 network_rx: 100 * 1024 * 1024,
                 network_tx: 50 * 1024 * 1024,
                 uptime: 86400,
+                ..Default::default()
             },
             // 警告阈值
             SystemStatus {
@@ -347,6 +348,7 @@ mod tests {
                 network_rx: 1000 * 1024 * 1024,
                 network_tx: 500 * 1024 * 1024,
                 uptime: 86400 * 7,
+                ..Default::default()
             },
             // 危险阈值
             SystemStatus {
@@ -358,6 +360,7 @@ mod tests {
                 network_rx: 5000 * 1024 * 1024,
                 network_tx: 2500 * 1024 * 1024,
                 uptime: 86400 * 30,
+                ..Default::default()
             },
         ];
         
@@ -2,8 +2,24 @@
 //! 
 //! 使用 thiserror 定义具体的错误类型，便于根据不同错误提供精准的用户提示
 
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+/// 错误类别，用于按类别配置重试等策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ErrorClass {
+    Permission,
+    Network,
+    DiskSpace,
+    PackageManager,
+    Service,
+    Reboot,
+    FileOperation,
+    CommandExecution,
+    Pipeline,
+    Unknown,
+}
+
 #[derive(Error, Debug)]
 pub enum SystemError {
     #[error("权限不足: {0}")]
@@ -29,6 +45,10 @@ pub enum SystemError {
     
     #[error("命令执行失败: {0}")]
     CommandExecutionError(String),
+
+    /// 流水线中设置为失败即停止的步骤失败，信息为已执行步骤的日志
+    #[error("流水线已停止: {0}")]
+    PipelineStopped(String),
     
     #[error("未知系统错误: {0}")]
     #[allow(dead_code)]
@@ -55,12 +75,32 @@ impl SystemError {
                 "❌ 文件操作失败。请检查文件权限和磁盘空间。",
             SystemError::CommandExecutionError(_) => 
                 "❌ 命令执行失败。请检查命令路径和参数。",
+            SystemError::PipelineStopped(_) => 
+                "❌ 流水线步骤失败，后续步骤已停止。请查看失败步骤的日志。",
             SystemError::UnknownError(_) => 
                 "❌ 发生未知错误。请检查系统日志或联系技术支持。",
         }
     }
     
+    /// 错误所属类别
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            SystemError::PermissionDenied(_) => ErrorClass::Permission,
+            SystemError::NetworkError(_) => ErrorClass::Network,
+            SystemError::DiskSpaceError(_) => ErrorClass::DiskSpace,
+            SystemError::PackageManagerError(_) => ErrorClass::PackageManager,
+            SystemError::ServiceError(_) => ErrorClass::Service,
+            SystemError::RebootError(_) => ErrorClass::Reboot,
+            SystemError::FileOperationError(_) => ErrorClass::FileOperation,
+            SystemError::CommandExecutionError(_) => ErrorClass::CommandExecution,
+            SystemError::PipelineStopped(_) => ErrorClass::Pipeline,
+            SystemError::UnknownError(_) => ErrorClass::Unknown,
+        }
+    }
+
     /// 判断是否为可重试的错误
+    ///
+    /// 流水线停止不可重试，否则整条流水线（包括已成功的步骤）会被重新执行
     pub fn is_retryable(&self) -> bool {
         matches!(self, 
             SystemError::NetworkError(_) | 
@@ -168,6 +208,7 @@ mod tests {
         assert!(!SystemError::RebootError("test".to_string()).is_retryable());
         assert!(!SystemError::FileOperationError("test".to_string()).is_retryable());
         assert!(!SystemError::UnknownError("test".to_string()).is_retryable());
+        assert!(!SystemError::PipelineStopped("test".to_string()).is_retryable());
     }
 
     #[test]
@@ -182,6 +223,7 @@ mod tests {
             SystemError::FileOperationError("test7".to_string()),
             SystemError::CommandExecutionError("test8".to_string()),
             SystemError::UnknownError("test9".to_string()),
+            SystemError::PipelineStopped("test10".to_string()),
         ];
         
         for error in errors {
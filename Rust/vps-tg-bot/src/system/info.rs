@@ -1,32 +1,86 @@
 use anyhow::Result;
-use sysinfo::{NetworksExt, System, SystemExt, CpuExt, DiskExt, NetworkExt};
+use crate::system::disk::{self, MountUsage};
+use crate::system::netdev::{self, InterfaceTraffic};
+use crate::system::procfs::{self, LoadAverage, PressureInfo, ProcessUsage};
+use std::time::Duration;
+use sysinfo::{System, SystemExt, CpuExt};
 
-#[derive(Debug, Clone)]
+/// CPU 与网络速率的采样窗口
+const SAMPLE_WINDOW: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Default)]
 pub struct SystemStatus {
     pub cpu_usage: f32,
     pub memory_used: u64,
     pub memory_total: u64,
     pub disk_used: u64,
     pub disk_total: u64,
+    /// 所有非回环接口自开机以来累计接收字节数
     pub network_rx: u64,
+    /// 所有非回环接口自开机以来累计发送字节数
     pub network_tx: u64,
     pub uptime: u64,
+    /// 各网络接口的累计流量与采样窗口内的平均速率
+    pub interfaces: Vec<InterfaceTraffic>,
+    /// 各真实挂载点的磁盘与 inode 使用情况
+    pub mounts: Vec<MountUsage>,
+    /// 1/5/15 分钟平均负载
+    pub load_average: LoadAverage,
+    pub swap_used: u64,
+    pub swap_total: u64,
+    /// 采样窗口内 CPU 等待 IO 的时间占比
+    pub iowait_percent: f32,
+    /// 采样窗口内被宿主机抢占 (steal) 的时间占比
+    pub steal_percent: f32,
+    /// 压力阻塞信息 (PSI)
+    pub pressure: PressureInfo,
+    /// CPU 占用最高的进程
+    pub top_cpu_processes: Vec<ProcessUsage>,
+    /// 常驻内存最高的进程
+    pub top_memory_processes: Vec<ProcessUsage>,
 }
 
 pub fn get_system_status() -> Result<SystemStatus> {
     let mut system = System::new_all();
     system.refresh_all();
+    let counters_before = netdev::read_interface_counters();
+    let cpu_times_before = procfs::read_cpu_times();
+    let processes_before = procfs::read_process_stats();
+
+    // CPU 使用率、网络速率和进程占用都需要两次采样之间的差值才有意义
+    std::thread::sleep(SAMPLE_WINDOW);
+    system.refresh_cpu();
+    let counters_after = netdev::read_interface_counters();
+    let cpu_times_after = procfs::read_cpu_times();
+    let processes_after = procfs::read_process_stats();
+
+    let (iowait_percent, steal_percent) = match (cpu_times_before, cpu_times_after) {
+        (Some(before), Some(after)) => after.iowait_steal_since(&before),
+        _ => (0.0, 0.0),
+    };
+
+    let processes = procfs::compute_process_usage(
+        &processes_before,
+        &processes_after,
+        SAMPLE_WINDOW,
+        procfs::clock_ticks(),
+        procfs::page_size(),
+    );
+    let (swap_used, swap_total) = procfs::read_swap();
 
     let cpu_usage = system.global_cpu_info().cpu_usage();
 
     let memory_used = system.used_memory();
     let memory_total = system.total_memory();
 
-    let disk_used = system.disks().iter().fold(0u64, |acc, disk| acc + disk.total_space() - disk.available_space());
-    let disk_total = system.disks().iter().fold(0u64, |acc, disk| acc + disk.total_space());
+    // 只统计过滤掉伪文件系统和重复挂载后的挂载点
+    let mounts = disk::get_mount_usages();
+    let disk_used = mounts.iter().map(|m| m.used_bytes).sum();
+    let disk_total = mounts.iter().map(|m| m.total_bytes).sum();
 
-    let network_rx = system.networks().iter().fold(0u64, |acc, (_, data): (&String, &sysinfo::NetworkData)| acc + data.received());
-    let network_tx = system.networks().iter().fold(0u64, |acc, (_, data): (&String, &sysinfo::NetworkData)| acc + data.transmitted());
+    let interfaces = netdev::compute_rates(&counters_before, &counters_after, SAMPLE_WINDOW);
+    let network_rx = interfaces.iter().map(|i| i.rx_bytes).sum();
+    let network_tx = interfaces.iter().map(|i| i.tx_bytes).sum();
 
     let uptime = system.uptime();
 
@@ -39,6 +93,16 @@ pub fn get_system_status() -> Result<SystemStatus> {
         network_rx,
         network_tx,
         uptime,
+        interfaces,
+        mounts,
+        load_average: procfs::read_loadavg(),
+        swap_used,
+        swap_total,
+        iowait_percent,
+        steal_percent,
+        pressure: procfs::read_pressure(),
+        top_cpu_processes: procfs::top_by_cpu(&processes, procfs::TOP_PROCESS_COUNT),
+        top_memory_processes: procfs::top_by_rss(&processes, procfs::TOP_PROCESS_COUNT),
     })
 }
 
@@ -58,6 +122,7 @@ mod tests {
             network_rx: 1024 * 1024, // 1MB
             network_tx: 512 * 1024, // 512KB
             uptime: 3600, // 1小时
+            ..Default::default()
         };
         
         assert_eq!(status.cpu_usage, 25.5);
@@ -81,6 +146,7 @@ mod tests {
             network_rx: 0,
             network_tx: 0,
             uptime: 0,
+            ..Default::default()
         };
         
         assert_eq!(status.cpu_usage, 0.0);
@@ -104,6 +170,7 @@ mod tests {
             network_rx: u64::MAX,
             network_tx: u64::MAX,
             uptime: u64::MAX,
+            ..Default::default()
         };
         
         assert_eq!(status.cpu_usage, 100.0);
@@ -128,6 +195,7 @@ mod tests {
             network_rx: 0,
             network_tx: 0,
             uptime: 0,
+            ..Default::default()
         };
         
         // 内存使用率应该是 25%
@@ -147,6 +215,7 @@ mod tests {
             network_rx: 0,
             network_tx: 0,
             uptime: 0,
+            ..Default::default()
         };
         
         // 磁盘使用率应该是 30%
@@ -165,6 +234,7 @@ mod tests {
             network_rx: 1024 * 1024 * 1024, // 1GB
             network_tx: 512 * 1024 * 1024, // 512MB
             uptime: 0,
+            ..Default::default()
         };
         
         // 验证网络数据存储正确
@@ -189,6 +259,7 @@ mod tests {
             network_rx: 0,
             network_tx: 0,
             uptime: 86400, // 24小时
+            ..Default::default()
         };
         
         // 验证运行时间转换
@@ -209,6 +280,7 @@ mod tests {
             network_rx: 1024 * 1024,
             network_tx: 512 * 1024,
             uptime: 7200,
+            ..Default::default()
         };
         
         let debug_str = format!("{:?}", status);
@@ -235,6 +307,7 @@ mod tests {
             network_rx: 1024 * 1024,
             network_tx: 512 * 1024,
             uptime: 3600,
+            ..Default::default()
         };
         
         let status2 = SystemStatus {
@@ -246,6 +319,7 @@ mod tests {
             network_rx: 1024 * 1024,
             network_tx: 512 * 1024,
             uptime: 3600,
+            ..Default::default()
         };
         
         let status3 = SystemStatus {
@@ -257,6 +331,7 @@ mod tests {
             network_rx: 1024 * 1024,
             network_tx: 512 * 1024,
             uptime: 3600,
+            ..Default::default()
         };
         
         // 由于SystemStatus没有实现PartialEq，我们测试字段值
@@ -275,6 +350,7 @@ mod tests {
             network_rx: 1024 * 1024,
             network_tx: 512 * 1024,
             uptime: 3600,
+            ..Default::default()
         };
         
         let status2 = status1.clone();
@@ -301,6 +377,7 @@ mod tests {
             network_rx: 0,
             network_tx: 0,
             uptime: 0,
+            ..Default::default()
         };
         
         // 内存使用率应该是 37.5%
@@ -319,6 +396,7 @@ mod tests {
             network_rx: 0,
             network_tx: 0,
             uptime: 0,
+            ..Default::default()
         };
         
         // 磁盘使用率应该是 30%
@@ -337,6 +415,7 @@ mod tests {
             network_rx: 2048 * 1024 * 1024, // 2GB
             network_tx: 1536 * 1024 * 1024, // 1.5GB
             uptime: 0,
+            ..Default::default()
         };
         
         // 验证转换为MB的计算
@@ -363,6 +442,7 @@ mod tests {
             network_rx: 0,
             network_tx: 0,
             uptime: 90000, // 25小时
+            ..Default::default()
         };
         
         // 验证运行时间转换
@@ -387,6 +467,7 @@ mod tests {
             network_rx: 0,
             network_tx: 0,
             uptime: 0,
+            ..Default::default()
         };
         
         let status_full = SystemStatus {
@@ -398,6 +479,7 @@ mod tests {
             network_rx: 0,
             network_tx: 0,
             uptime: 0,
+            ..Default::default()
         };
         
         assert_eq!(status_zero.cpu_usage, 0.0);
@@ -416,6 +498,7 @@ mod tests {
             network_rx: 0,
             network_tx: 0,
             uptime: 0,
+            ..Default::default()
         };
         
         let status_full_memory = SystemStatus {
@@ -427,6 +510,7 @@ mod tests {
             network_rx: 0,
             network_tx: 0,
             uptime: 0,
+            ..Default::default()
         };
         
         assert_eq!(status_no_memory.memory_used, 0);
@@ -445,6 +529,7 @@ mod tests {
             network_rx: 0,
             network_tx: 0,
             uptime: 0,
+            ..Default::default()
         };
         
         let status_full_disk = SystemStatus {
@@ -456,6 +541,7 @@ mod tests {
             network_rx: 0,
             network_tx: 0,
             uptime: 0,
+            ..Default::default()
         };
         
         assert_eq!(status_empty_disk.disk_used, 0);
@@ -474,6 +560,7 @@ mod tests {
             network_rx: 0,
             network_tx: 0,
             uptime: 0,
+            ..Default::default()
         };
         
         let status_large_network = SystemStatus {
@@ -485,6 +572,7 @@ mod tests {
             network_rx: u64::MAX / 2,
             network_tx: u64::MAX / 2,
             uptime: 0,
+            ..Default::default()
         };
         
         assert_eq!(status_zero_network.network_rx, 0);
@@ -505,6 +593,7 @@ mod tests {
             network_rx: 0,
             network_tx: 0,
             uptime: 0,
+            ..Default::default()
         };
         
         let status_long_uptime = SystemStatus {
@@ -516,6 +605,7 @@ mod tests {
             network_rx: 0,
             network_tx: 0,
             uptime: u64::MAX,
+            ..Default::default()
         };
         
         assert_eq!(status_zero_uptime.uptime, 0);
@@ -538,6 +628,7 @@ mod tests {
             network_rx: 1024 * 1024 * 1024 * 5, // 5GB
             network_tx: 1024 * 1024 * 1024 * 2, // 2GB
             uptime: 86400 * 15, // 15天
+            ..Default::default()
         };
         
         // 验证计算结果
@@ -564,6 +655,7 @@ mod tests {
                 network_rx: u64::MAX,
                 network_tx: u64::MAX,
                 uptime: u64::MAX,
+                ..Default::default()
             },
             SystemStatus {
                 cpu_usage: f32::MIN,
@@ -574,6 +666,7 @@ mod tests {
                 network_rx: 0,
                 network_tx: 0,
                 uptime: 0,
+                ..Default::default()
             },
         ];
         
@@ -599,6 +692,7 @@ mod tests {
                 network_rx: 0,
                 network_tx: 0,
                 uptime: 0,
+                ..Default::default()
             },
             // 内存总容量为0
             SystemStatus {
@@ -610,6 +704,7 @@ mod tests {
                 network_rx: 0,
                 network_tx: 0,
                 uptime: 0,
+                ..Default::default()
             },
         ];
         
@@ -637,6 +732,7 @@ mod tests {
                 network_rx: 0,
                 network_tx: 0,
                 uptime: 0,
+                ..Default::default()
             },
             // 磁盘总容量为0
             SystemStatus {
@@ -648,6 +744,7 @@ mod tests {
                 network_rx: 0,
                 network_tx: 0,
                 uptime: 0,
+                ..Default::default()
             },
         ];
         
@@ -674,6 +771,7 @@ mod tests {
                 network_rx: u64::MAX, // 最大值
                 network_tx: u64::MAX, // 最大值
                 uptime: 0,
+                ..Default::default()
             },
             // 网络数据为负数（虽然u64不能为负，但测试计算逻辑）
             SystemStatus {
@@ -685,6 +783,7 @@ mod tests {
                 network_rx: 0,
                 network_tx: 0,
                 uptime: 0,
+                ..Default::default()
             },
         ];
         
@@ -717,6 +816,7 @@ mod tests {
                 network_rx: 0,
                 network_tx: 0,
                 uptime: 0,
+                ..Default::default()
             },
             // 超100%的CPU使用率
             SystemStatus {
@@ -728,6 +828,7 @@ mod tests {
                 network_rx: 0,
                 network_tx: 0,
                 uptime: 0,
+                ..Default::default()
             },
             // NaN 和无穷大
             SystemStatus {
@@ -739,6 +840,7 @@ mod tests {
                 network_rx: 0,
                 network_tx: 0,
                 uptime: 0,
+                ..Default::default()
             },
             SystemStatus {
                 cpu_usage: f32::INFINITY,
@@ -749,6 +851,7 @@ mod tests {
                 network_rx: 0,
                 network_tx: 0,
                 uptime: 0,
+                ..Default::default()
             },
         ];
         
@@ -780,6 +883,7 @@ mod tests {
                 network_rx: 0,
                 network_tx: 0,
                 uptime: u64::MAX,
+                ..Default::default()
             },
             // 异常的运行时间
             SystemStatus {
@@ -791,6 +895,7 @@ mod tests {
                 network_rx: 0,
                 network_tx: 0,
                 uptime: u64::MAX / 2,
+                ..Default::default()
             },
         ];
         
@@ -822,6 +927,7 @@ mod tests {
             network_rx: 1024 * 1024,
             network_tx: 512 * 1024,
             uptime: 3600,
+            ..Default::default()
         };
         
         // 模拟多次并发访问
@@ -852,6 +958,7 @@ mod tests {
             network_rx: 0,
             network_tx: 0,
             uptime: 0,
+            ..Default::default()
         };
         
         // 验证结构体大小合理
@@ -879,6 +986,7 @@ mod tests {
             network_rx: 1024 * 1024,
             network_tx: 512 * 1024,
             uptime: 7200,
+            ..Default::default()
         };
         
         // 验证所有字段都可以被序列化
@@ -916,6 +1024,7 @@ mod tests {
                 network_rx: net_rx,
                 network_tx: net_tx,
                 uptime: up,
+                ..Default::default()
             };
             
             // 验证数据完整性
@@ -942,6 +1051,7 @@ mod tests {
             network_rx: 2 * 1024 * 1024 * 1024, // 2GB
             network_tx: 1 * 1024 * 1024 * 1024, // 1GB
             uptime: 86400 * 30, // 30天
+            ..Default::default()
         };
         
         // 验证内存使用率计算
@@ -977,6 +1087,7 @@ mod tests {
                 network_rx: 1,
                 network_tx: 1,
                 uptime: 1,
+                ..Default::default()
             },
             // 接近最大值
             SystemStatus {
@@ -988,6 +1099,7 @@ mod tests {
                 network_rx: u64::MAX - 1,
                 network_tx: u64::MAX - 1,
                 uptime: u64::MAX - 1,
+                ..Default::default()
             },
         ];
         
@@ -1017,6 +1129,7 @@ mod tests {
             network_rx: u64::MAX,
             network_tx: u64::MAX,
             uptime: u64::MAX,
+            ..Default::default()
         };
         
         // 验证即使在异常情况下，结构体仍然可用
@@ -1050,6 +1163,7 @@ mod tests {
                 network_rx: 100 * 1024 * 1024, // 100MB
                 network_tx: 50 * 1024 * 1024, // 50MB
                 uptime: 86400, // 1天
+                ..Default::default()
             },
             // 高资源使用场景
             SystemStatus {
@@ -1061,6 +1175,7 @@ mod tests {
                 network_rx: 10 * 1024 * 1024 * 1024, // 10GB
                 network_tx: 5 * 1024 * 1024 * 1024, // 5GB
                 uptime: 86400 * 365, // 1年
+                ..Default::default()
             },
             // 临界资源场景
             SystemStatus {
@@ -1072,6 +1187,7 @@ mod tests {
                 network_rx: u64::MAX / 2,
                 network_tx: u64::MAX / 2,
                 uptime: u64::MAX / 2,
+                ..Default::default()
             },
         ];
         
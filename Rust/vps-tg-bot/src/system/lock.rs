@@ -0,0 +1,206 @@
+//! 全局维护锁
+//!
+//! 系统更新、核心维护、规则维护等操作会争用 dpkg/apt 锁，同一时间只允许一个
+//! 维护操作执行。锁基于 `flock`，因此同一进程内的 Bot 按钮、定时任务以及
+//! 另一个进程中的命令行子命令都会互相排斥。持有者信息（操作、发起人、开始
+//! 时间）写在锁文件中，供被拒绝的请求展示当前正在执行的内容
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const LOCK_DIR: &str = "/run/vps-tg-bot-rust";
+const LOCK_FILE_NAME: &str = "maintenance.lock";
+
+/// 排队等待时重新尝试加锁的间隔
+const QUEUE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 锁的当前持有者
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockHolder {
+    pub operation: String,
+    pub started_by: String,
+    pub since: DateTime<Local>,
+    pub pid: u32,
+}
+
+impl LockHolder {
+    /// 用于拒绝/排队消息的描述
+    pub fn describe(&self) -> String {
+        let minutes = (Local::now() - self.since).num_minutes().max(0);
+        format!(
+            "⏳ 当前正在执行: {}\n👤 发起人: {}\n🕒 开始时间: {}（已运行 {} 分钟）",
+            self.operation,
+            self.started_by,
+            self.since.format("%Y-%m-%d %H:%M:%S"),
+            minutes
+        )
+    }
+}
+
+/// 加锁失败的原因
+#[derive(Debug)]
+pub enum LockError {
+    /// 已有其他维护操作在执行；持有者信息可能尚未写入
+    Busy(Option<LockHolder>),
+    Io(io::Error),
+}
+
+impl std::fmt::Display for LockError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LockError::Busy(Some(holder)) => write!(f, "已有维护操作正在执行\n{}", holder.describe()),
+            LockError::Busy(None) => write!(f, "已有维护操作正在执行"),
+            LockError::Io(e) => write!(f, "无法获取维护锁: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for LockError {}
+
+/// 持有期间独占维护锁，丢弃时释放
+#[derive(Debug)]
+pub struct MaintenanceGuard {
+    file: File,
+}
+
+impl Drop for MaintenanceGuard {
+    fn drop(&mut self) {
+        // 先清空持有者信息再解锁，避免下一个读取者看到过期内容
+        let _ = self.file.set_len(0);
+        unsafe {
+            libc::flock(self.file.as_raw_fd(), libc::LOCK_UN);
+        }
+    }
+}
+
+/// 维护锁
+#[derive(Debug, Clone)]
+pub struct MaintenanceLock {
+    path: PathBuf,
+}
+
+impl MaintenanceLock {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// 进程间共享的默认锁；/run 不可写时退回临时目录
+    pub fn global() -> Self {
+        let dir = Path::new(LOCK_DIR);
+        if std::fs::create_dir_all(dir).is_ok() {
+            Self::new(dir.join(LOCK_FILE_NAME))
+        } else {
+            Self::new(std::env::temp_dir().join(format!("vps-tg-bot-rust-{}", LOCK_FILE_NAME)))
+        }
+    }
+
+    fn open(&self) -> io::Result<File> {
+        OpenOptions::new().read(true).write(true).create(true).truncate(false).open(&self.path)
+    }
+
+    /// 立即尝试加锁，已被占用时返回当前持有者
+    pub fn try_acquire(&self, operation: &str, started_by: &str) -> Result<MaintenanceGuard, LockError> {
+        let mut file = self.open().map_err(LockError::Io)?;
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+            let error = io::Error::last_os_error();
+            return if error.raw_os_error() == Some(libc::EWOULDBLOCK) {
+                Err(LockError::Busy(self.holder()))
+            } else {
+                Err(LockError::Io(error))
+            };
+        }
+
+        let holder = LockHolder {
+            operation: operation.to_string(),
+            started_by: started_by.to_string(),
+            since: Local::now(),
+            pid: std::process::id(),
+        };
+        let content = serde_json::to_string(&holder).map_err(|e| LockError::Io(e.into()))?;
+        file.set_len(0)
+            .and_then(|_| file.seek(SeekFrom::Start(0)))
+            .and_then(|_| file.write_all(content.as_bytes()))
+            .and_then(|_| file.flush())
+            .map_err(LockError::Io)?;
+
+        log::info!("🔒 获取维护锁: {} (发起人: {})", operation, started_by);
+        Ok(MaintenanceGuard { file })
+    }
+
+    /// 排队等待直到获得锁；首次需要等待时调用 `on_queued` 通知调用方
+    pub async fn acquire<F>(&self, operation: &str, started_by: &str, on_queued: F) -> Result<MaintenanceGuard, LockError>
+    where
+        F: FnOnce(Option<LockHolder>),
+    {
+        let mut on_queued = Some(on_queued);
+        loop {
+            match self.try_acquire(operation, started_by) {
+                Err(LockError::Busy(holder)) => {
+                    if let Some(notify) = on_queued.take() {
+                        log::info!("⏳ {} 排队等待维护锁", operation);
+                        notify(holder);
+                    }
+                    tokio::time::sleep(QUEUE_POLL_INTERVAL).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// 读取当前持有者，未被占用时返回 None
+    pub fn holder(&self) -> Option<LockHolder> {
+        let mut content = String::new();
+        File::open(&self.path).ok()?.read_to_string(&mut content).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_second_acquire_is_rejected_with_holder() {
+        let dir = tempfile::tempdir().unwrap();
+        let lock = MaintenanceLock::new(dir.path().join(LOCK_FILE_NAME));
+
+        let guard = lock.try_acquire("🔄 系统维护", "定时任务").unwrap();
+        match lock.try_acquire("🚀 核心维护", "@admin") {
+            Err(LockError::Busy(Some(holder))) => {
+                assert_eq!(holder.operation, "🔄 系统维护");
+                assert_eq!(holder.started_by, "定时任务");
+                assert!(holder.describe().contains("发起人: 定时任务"));
+            }
+            other => panic!("应该被拒绝: {:?}", other.map(|_| ())),
+        }
+
+        drop(guard);
+        assert!(lock.holder().is_none());
+        assert!(lock.try_acquire("🚀 核心维护", "@admin").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_queued_acquire_waits_for_release() {
+        let dir = tempfile::tempdir().unwrap();
+        let lock = MaintenanceLock::new(dir.path().join(LOCK_FILE_NAME));
+        let guard = lock.try_acquire("🌍 规则维护", "@admin").unwrap();
+
+        let releaser = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            drop(guard);
+        });
+
+        let mut queued_behind = None;
+        let acquired = lock.acquire("🔧 更新 Xray", "定时任务", |holder| queued_behind = holder).await;
+        releaser.await.unwrap();
+
+        assert!(acquired.is_ok());
+        assert_eq!(queued_behind.map(|h| h.operation), Some("🌍 规则维护".to_string()));
+        assert_eq!(lock.holder().map(|h| h.operation), Some("🔧 更新 Xray".to_string()));
+    }
+}
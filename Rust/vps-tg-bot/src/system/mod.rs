@@ -1,12 +1,20 @@
 #[cfg(test)]
 pub mod error_tests;
+pub mod disk;
 pub mod errors;
 pub mod info;
+pub mod lock;
+pub mod netdev;
 pub mod ops;
+pub mod procfs;
+pub mod proxy_config;
+pub mod reboot;
+pub mod sockets;
+pub mod systemd;
 pub mod update;
 
 #[allow(unused_imports)]
-pub use errors::SystemError;
+pub use errors::{ErrorClass, SystemError};
 pub use info::get_system_status;
 #[allow(unused_imports)]
 pub use info::SystemStatus;
@@ -0,0 +1,191 @@
+//! 网络接口流量统计
+//!
+//! 从 /proc/net/dev 读取各网络接口自开机以来的累计收发字节数。
+//! 容器网桥、隧道等虚拟接口的流量同时会经过物理接口，统计时跳过以免重复计入流量配额
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// 网络接口统计文件路径
+const NET_DEV_PATH: &str = "/proc/net/dev";
+
+/// 虚拟接口名称前缀：Docker 网桥与 veth、Linux 网桥、TUN/TAP 隧道、WireGuard 等
+const VIRTUAL_INTERFACE_PREFIXES: &[&str] =
+    &["docker", "veth", "br-", "virbr", "vnet", "tun", "tap", "wg", "tailscale"];
+
+/// 是否为回环或虚拟接口
+pub fn is_virtual_interface(name: &str) -> bool {
+    name == "lo" || VIRTUAL_INTERFACE_PREFIXES.iter().any(|prefix| name.starts_with(prefix))
+}
+
+/// 单个网络接口的累计计数器
+#[derive(Debug, Clone, PartialEq)]
+pub struct InterfaceCounters {
+    pub name: String,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+}
+
+/// 单个网络接口的流量与速率
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InterfaceTraffic {
+    pub name: String,
+    /// 自开机以来累计接收字节数
+    pub rx_bytes: u64,
+    /// 自开机以来累计发送字节数
+    pub tx_bytes: u64,
+    /// 采样窗口内的平均接收速率（字节/秒）
+    pub rx_rate: f64,
+    /// 采样窗口内的平均发送速率（字节/秒）
+    pub tx_rate: f64,
+}
+
+/// 解析 /proc/net/dev 内容，忽略回环接口和虚拟接口
+pub fn parse_net_dev(content: &str) -> Vec<InterfaceCounters> {
+    content
+        .lines()
+        // 前两行为表头
+        .skip(2)
+        .filter_map(|line| {
+            let (name, stats) = line.split_once(':')?;
+            let name = name.trim();
+            if name.is_empty() || is_virtual_interface(name) {
+                return None;
+            }
+
+            let fields: Vec<u64> = stats
+                .split_whitespace()
+                .map(|field| field.parse().ok())
+                .collect::<Option<Vec<u64>>>()?;
+            // 接收 8 列 + 发送 8 列
+            if fields.len() < 16 {
+                return None;
+            }
+
+            Some(InterfaceCounters {
+                name: name.to_string(),
+                rx_bytes: fields[0],
+                tx_bytes: fields[8],
+            })
+        })
+        .collect()
+}
+
+/// 读取当前所有网络接口的累计计数器
+pub fn read_interface_counters() -> Vec<InterfaceCounters> {
+    match std::fs::read_to_string(NET_DEV_PATH) {
+        Ok(content) => parse_net_dev(&content),
+        Err(e) => {
+            log::warn!("⚠️  读取 {} 失败: {}", NET_DEV_PATH, e);
+            Vec::new()
+        }
+    }
+}
+
+/// 根据两次采样计算各接口在窗口内的平均速率
+///
+/// 计数器回绕或接口重建导致数值变小时，速率按 0 处理
+pub fn compute_rates(
+    before: &[InterfaceCounters],
+    after: &[InterfaceCounters],
+    window: Duration,
+) -> Vec<InterfaceTraffic> {
+    let previous: HashMap<&str, &InterfaceCounters> =
+        before.iter().map(|c| (c.name.as_str(), c)).collect();
+    let seconds = window.as_secs_f64();
+
+    after
+        .iter()
+        .map(|current| {
+            let (rx_rate, tx_rate) = match previous.get(current.name.as_str()) {
+                Some(prev) if seconds > 0.0 => (
+                    current.rx_bytes.saturating_sub(prev.rx_bytes) as f64 / seconds,
+                    current.tx_bytes.saturating_sub(prev.tx_bytes) as f64 / seconds,
+                ),
+                _ => (0.0, 0.0),
+            };
+
+            InterfaceTraffic {
+                name: current.name.clone(),
+                rx_bytes: current.rx_bytes,
+                tx_bytes: current.tx_bytes,
+                rx_rate,
+                tx_rate,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_NET_DEV: &str = "\
+Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+    lo:  123456     100    0    0    0     0          0         0   123456     100    0    0    0     0       0          0
+  eth0: 1000000    2000    0    0    0     0          0         0   500000    1500    0    0    0     0       0          0
+docker0:    4096      10    0    0    0     0          0         0     8192      20    0    0    0     0       0          0
+  ens3:    2048       5    0    0    0     0          0         0     1024       4    0    0    0     0       0          0
+";
+
+    #[test]
+    fn test_parse_net_dev_skips_loopback() {
+        let counters = parse_net_dev(SAMPLE_NET_DEV);
+
+        assert_eq!(counters.len(), 2);
+        assert_eq!(counters[0], InterfaceCounters { name: "eth0".to_string(), rx_bytes: 1_000_000, tx_bytes: 500_000 });
+        assert_eq!(counters[1].name, "ens3");
+        assert_eq!(counters[1].tx_bytes, 1024);
+    }
+
+    #[test]
+    fn test_parse_net_dev_skips_virtual_interfaces() {
+        let content = "\
+header
+header
+  eth0:     100       1    0    0    0     0          0         0      200       2    0    0    0     0       0          0
+veth1a2b3c:    4096      10    0    0    0     0          0         0     8192      20    0    0    0     0       0          0
+br-5f2e9a:    4096      10    0    0    0     0          0         0     8192      20    0    0    0     0       0          0
+  tun0:    4096      10    0    0    0     0          0         0     8192      20    0    0    0     0       0          0
+   wg0:    4096      10    0    0    0     0          0         0     8192      20    0    0    0     0       0          0
+";
+        let names: Vec<String> = parse_net_dev(content).into_iter().map(|c| c.name).collect();
+        assert_eq!(names, vec!["eth0".to_string()]);
+
+        assert!(is_virtual_interface("docker0"));
+        assert!(!is_virtual_interface("enp1s0"));
+    }
+
+    #[test]
+    fn test_parse_net_dev_ignores_malformed_lines() {
+        let content = "header\nheader\n  eth0: 1 2 3\ngarbage line\n";
+        assert!(parse_net_dev(content).is_empty());
+    }
+
+    #[test]
+    fn test_compute_rates_over_window() {
+        let before = vec![InterfaceCounters { name: "eth0".to_string(), rx_bytes: 1000, tx_bytes: 500 }];
+        let after = vec![
+            InterfaceCounters { name: "eth0".to_string(), rx_bytes: 3000, tx_bytes: 1500 },
+            InterfaceCounters { name: "wg0".to_string(), rx_bytes: 10, tx_bytes: 10 },
+        ];
+
+        let rates = compute_rates(&before, &after, Duration::from_secs(2));
+        assert_eq!(rates[0].rx_rate, 1000.0);
+        assert_eq!(rates[0].tx_rate, 500.0);
+        assert_eq!(rates[0].rx_bytes, 3000);
+        // 新出现的接口没有上一次采样，速率为 0
+        assert_eq!(rates[1].rx_rate, 0.0);
+    }
+
+    #[test]
+    fn test_compute_rates_counter_reset() {
+        let before = vec![InterfaceCounters { name: "eth0".to_string(), rx_bytes: 5000, tx_bytes: 5000 }];
+        let after = vec![InterfaceCounters { name: "eth0".to_string(), rx_bytes: 10, tx_bytes: 10 }];
+
+        let rates = compute_rates(&before, &after, Duration::from_secs(1));
+        assert_eq!(rates[0].rx_rate, 0.0);
+        assert_eq!(rates[0].tx_rate, 0.0);
+    }
+}
@@ -1,29 +1,35 @@
 use anyhow::Result;
+use std::process::Stdio;
 use tokio::process::Command;
 use crate::system::errors::SystemError;
+use crate::system::reboot;
 use crate::scheduler::maintenance_history::{self, MaintenanceResult};
 
+/// 执行必须成功的 apt 步骤，失败时记录维护历史并返回分类后的错误，由重试策略决定是否重试
+async fn run_required_apt_step(log: &mut String, args: &[&str], step: &str, context: &str, task_name: &str) -> Result<(), SystemError> {
+    match run_command_with_error_context("apt-get", args, context).await {
+        Ok(output) => {
+            log.push_str(&format!("✅ {}: 成功\n{}\n", step, output));
+            Ok(())
+        }
+        Err(e) => {
+            log.push_str(&format!("❌ {}: 失败 ({})\n", step, e));
+            maintenance_history::record_maintenance(task_name, MaintenanceResult::Failed, log, Some(&e.to_string())).await;
+            Err(e)
+        }
+    }
+}
+
+/// 系统维护：更新和升级失败时返回错误，清理步骤失败只记为部分成功
 pub async fn perform_maintenance() -> Result<String, SystemError> {
     let mut log = String::new();
     let mut has_errors = false;
 
     log.push_str("🔄 正在更新系统...\n");
-    match run_command_with_error_context("apt-get", &["update"], "系统更新").await {
-        Ok(output) => log.push_str(&format!("✅ Apt 更新: 成功\n{}\n", output)),
-        Err(e) => {
-            log.push_str(&format!("❌ Apt 更新: 失败 ({})\n", e));
-            has_errors = true;
-        }
-    }
+    run_required_apt_step(&mut log, &["update"], "Apt 更新", "系统更新", "系统维护").await?;
 
     log.push_str("🔄 正在升级系统...\n");
-    match run_command_with_error_context("apt-get", &["full-upgrade", "-y"], "系统升级").await {
-        Ok(output) => log.push_str(&format!("✅ Apt 完全升级: 成功\n{}\n", output)),
-        Err(e) => {
-            log.push_str(&format!("❌ Apt 完全升级: 失败 ({})\n", e));
-            has_errors = true;
-        }
-    }
+    run_required_apt_step(&mut log, &["full-upgrade", "-y"], "Apt 完全升级", "系统升级", "系统维护").await?;
 
     log.push_str("🔄 正在清理不必要的软件包...\n");
     match run_command_with_error_context("apt-get", &["autoremove", "-y"], "清理软件包").await {
@@ -64,6 +70,16 @@ pub async fn check_security_updates() -> Result<bool, SystemError> {
     Ok(output.contains("security"))
 }
 
+/// 执行自定义命令，返回标准输出；调用方负责检查程序是否在白名单中
+pub async fn run_custom_command(program: &str, args: &[String]) -> Result<String, SystemError> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let output = run_command_with_error_context(program, &args, "自定义命令").await?;
+    if output.trim().is_empty() {
+        return Ok("（无输出）".to_string());
+    }
+    Ok(output)
+}
+
 pub async fn reboot_system() -> Result<(), SystemError> {
     let status = Command::new("reboot")
         .status()
@@ -77,7 +93,6 @@ pub async fn reboot_system() -> Result<(), SystemError> {
     Ok(())
 }
 
-#[allow(dead_code)]
 pub async fn restart_service(service_name: &str) -> Result<(), SystemError> {
     let status = Command::new("systemctl")
         .args(["restart", service_name])
@@ -94,9 +109,8 @@ pub async fn restart_service(service_name: &str) -> Result<(), SystemError> {
 
 pub async fn update_xray() -> Result<String, SystemError> {
     let script = "bash -c $(curl -L https://github.com/XTLS/Xray-install/raw/main/install-release.sh) @ install";
-    let result = run_command_with_error_context("bash", &["-c", script], "更新 Xray")
-        .await
-        .map_err(|e| SystemError::NetworkError(format!("Xray 更新失败: {}", e)))?;
+    // 保留命令错误的分类，只有网络等可重试的错误才会重试
+    let result = run_command_with_error_context("bash", &["-c", script], "更新 Xray").await?;
     
     // 记录维护历史
     maintenance_history::record_maintenance("Xray更新", MaintenanceResult::Success, &result, None).await;
@@ -105,9 +119,7 @@ pub async fn update_xray() -> Result<String, SystemError> {
 }
 
 pub async fn update_singbox() -> Result<String, SystemError> {
-    let result = run_command_with_error_context("sb", &["up"], "更新 Sing-box")
-        .await
-        .map_err(|e| SystemError::NetworkError(format!("Sing-box 更新失败: {}", e)))?;
+    let result = run_command_with_error_context("sb", &["up"], "更新 Sing-box").await?;
     
     // 记录维护历史
     maintenance_history::record_maintenance("Sing-box更新", MaintenanceResult::Success, &result, None).await;
@@ -115,41 +127,13 @@ pub async fn update_singbox() -> Result<String, SystemError> {
     Ok(result)
 }
 
+/// 核心维护：升级系统后重启
 pub async fn maintain_core() -> Result<String, SystemError> {
-    let mut log = String::new();
-    let mut has_errors = false;
-
-    log.push_str("🔄 正在执行核心维护...\n");
-    match run_command_with_error_context("apt-get", &["update"], "核心维护更新").await {
-        Ok(output) => log.push_str(&format!("✅ Apt 更新: 成功\n{}\n", output)),
-        Err(e) => {
-            log.push_str(&format!("❌ Apt 更新: 失败 ({})\n", e));
-            has_errors = true;
-        }
-    }
-
-    log.push_str("🔄 正在升级系统...\n");
-    match run_command_with_error_context("apt-get", &["full-upgrade", "-y"], "核心维护升级").await {
-        Ok(output) => log.push_str(&format!("✅ Apt 完全升级: 成功\n{}\n", output)),
-        Err(e) => {
-            log.push_str(&format!("❌ Apt 完全升级: 失败 ({})\n", e));
-            has_errors = true;
-        }
-    }
+    let mut log = upgrade_core().await?;
 
     log.push_str("🔄 系统更新完成，将在 3 秒后重启系统...\n");
     log.push_str("⚠️ 请保存您的工作，系统将自动重启\n");
 
-    // 记录维护历史
-    let result = if has_errors {
-        MaintenanceResult::Partial
-    } else {
-        MaintenanceResult::Success
-    };
-    
-    let error_message = if has_errors { Some("核心维护部分操作失败") } else { None };
-    maintenance_history::record_maintenance("核心维护", result, &log, error_message).await;
-
     // 启动异步重启任务，给 Bot 发送消息的时间
     tokio::spawn(async {
         tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
@@ -161,6 +145,22 @@ pub async fn maintain_core() -> Result<String, SystemError> {
     Ok(log)
 }
 
+/// 核心维护中的系统升级部分，不触发重启；任一步骤失败时返回错误
+pub async fn upgrade_core() -> Result<String, SystemError> {
+    let mut log = String::new();
+
+    log.push_str("🔄 正在执行核心维护...\n");
+    run_required_apt_step(&mut log, &["update"], "Apt 更新", "核心维护更新", "核心维护").await?;
+
+    log.push_str("🔄 正在升级系统...\n");
+    run_required_apt_step(&mut log, &["full-upgrade", "-y"], "Apt 完全升级", "核心维护升级", "核心维护").await?;
+
+    // 记录维护历史
+    maintenance_history::record_maintenance("核心维护", MaintenanceResult::Success, &log, None).await;
+
+    Ok(log)
+}
+
 pub async fn maintain_rules() -> Result<String, SystemError> {
     let result = run_command_with_error_context("bash", &["-c", "/usr/local/bin/vps-maintain-rules.sh"], "规则维护")
         .await
@@ -172,15 +172,18 @@ pub async fn maintain_rules() -> Result<String, SystemError> {
     Ok(result)
 }
 
+/// 完整维护：系统升级后执行规则维护，全部完成后按需重启
+///
+/// 系统升级使用不重启的 `upgrade_core`，保证规则维护在重启前执行
 pub async fn perform_full_maintenance() -> Result<String, SystemError> {
     let mut log = String::new();
     let mut has_errors = false;
 
     log.push_str("🚀 开始执行完整维护（核心+规则）...\n\n");
 
-    // 执行核心维护
+    // 执行核心维护（仅升级，不重启）
     log.push_str("🔧 执行核心维护：\n");
-    match maintain_core().await {
+    match upgrade_core().await {
         Ok(output) => {
             log.push_str(&format!("✅ 核心维护完成:\n{}\n\n", output));
         }
@@ -190,10 +193,6 @@ pub async fn perform_full_maintenance() -> Result<String, SystemError> {
         }
     }
 
-    // 等待系统重启完成
-    log.push_str("⏳ 等待系统重启完成...\n");
-    tokio::time::sleep(tokio::time::Duration::from_secs(10)).await;
-
     // 执行规则维护
     log.push_str("🌍 执行规则维护：\n");
     match maintain_rules().await {
@@ -218,6 +217,19 @@ pub async fn perform_full_maintenance() -> Result<String, SystemError> {
     let error_message = if has_errors { Some("完整维护部分操作失败") } else { None };
     maintenance_history::record_maintenance("完整维护", result, &log, error_message).await;
 
+    // 所有步骤结束后再决定是否重启
+    let reboot_status = reboot::check_reboot_required().await;
+    log.push_str(&format!("{}\n", reboot_status.describe()));
+    if reboot_status.required {
+        log.push_str("🔄 系统将在 3 秒后重启...\n");
+        tokio::spawn(async {
+            tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
+            if let Err(e) = reboot_system().await {
+                eprintln!("重启失败: {}", e);
+            }
+        });
+    }
+
     Ok(log)
 }
 
@@ -227,16 +239,107 @@ pub async fn get_system_logs(lines: usize) -> Result<String, SystemError> {
         .map_err(|e| SystemError::CommandExecutionError(format!("获取系统日志失败: {}", e)))
 }
 
+/// 读取 sshd 的 journal 记录（JSON 格式，每行一条）
+///
+/// 提供游标时只返回游标之后的记录，否则只返回整个 journal 的最新一条用于确定起始游标
+pub async fn get_sshd_journal(after_cursor: Option<&str>) -> Result<String, SystemError> {
+    get_journal_json(
+        &["SYSLOG_IDENTIFIER=sshd", "SYSLOG_IDENTIFIER=sshd-session"],
+        after_cursor,
+        "读取 SSH 日志",
+    )
+    .await
+}
+
+/// 读取内核日志的 journal 记录（JSON 格式，每行一条），游标语义同 [`get_sshd_journal`]
+pub async fn get_kernel_journal(after_cursor: Option<&str>) -> Result<String, SystemError> {
+    get_journal_json(&["_TRANSPORT=kernel"], after_cursor, "读取内核日志").await
+}
+
+async fn get_journal_json(
+    matches: &[&str],
+    after_cursor: Option<&str>,
+    context: &str,
+) -> Result<String, SystemError> {
+    let args = journal_args(matches, after_cursor);
+    run_command_with_error_context("journalctl", &args, context)
+        .await
+        .map_err(|e| SystemError::CommandExecutionError(format!("{}失败: {}", context, e)))
+}
+
+/// 构造 journalctl 参数
+///
+/// 没有游标时不带匹配条件，取整个 journal 的最新一条记录作为起始游标：
+/// journal 游标是全局的，之后带匹配条件的 `--after-cursor` 同样适用，
+/// 即使当前还没有匹配的记录，之后出现的第一条也不会被当作起点而漏掉
+fn journal_args<'a>(matches: &[&'a str], after_cursor: Option<&'a str>) -> Vec<&'a str> {
+    match after_cursor {
+        Some(cursor) => {
+            let mut args = matches.to_vec();
+            args.extend(["-o", "json", "--no-pager", "--after-cursor", cursor]);
+            args
+        }
+        None => vec!["-o", "json", "--no-pager", "-n", "1"],
+    }
+}
+
+/// 进程组守卫
+///
+/// 命令执行的 future 在子进程结束前被丢弃（任务超时或被取消）时，
+/// 向整个进程组发送 SIGKILL，避免 `curl | bash` 之类的孙进程残留
+struct ProcessGroupGuard {
+    pgid: Option<u32>,
+}
+
+impl ProcessGroupGuard {
+    fn new(pgid: Option<u32>) -> Self {
+        Self { pgid }
+    }
+
+    /// 子进程已正常结束，无需清理
+    fn disarm(&mut self) {
+        self.pgid = None;
+    }
+}
+
+impl Drop for ProcessGroupGuard {
+    fn drop(&mut self) {
+        if let Some(pgid) = self.pgid {
+            log::warn!("⛔ 终止未完成的命令进程组 {}", pgid);
+            // SAFETY: kill 只接收整数参数，负数 pid 表示整个进程组
+            unsafe {
+                libc::kill(-(pgid as i32), libc::SIGKILL);
+            }
+        }
+    }
+}
+
 async fn run_command_with_error_context(
     command: &str, 
     args: &[&str], 
     _context: &str
 ) -> Result<String, SystemError> {
-    let output = Command::new(command)
+    let mut builder = Command::new(command);
+    if command.starts_with("apt") {
+        // apt 输出固定为英文，维护通知依赖其中的升级统计判断是否有变化
+        builder.env("LC_ALL", "C");
+    }
+    // 子进程放入独立进程组，超时或取消时可以连同其派生的进程一起结束
+    let child = builder
         .args(args)
-        .output()
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .process_group(0)
+        .spawn()
+        .map_err(|e| SystemError::CommandExecutionError(format!("无法执行命令 {}: {}", command, e)))?;
+
+    let mut guard = ProcessGroupGuard::new(child.id());
+    let output = child
+        .wait_with_output()
         .await
         .map_err(|e| SystemError::CommandExecutionError(format!("无法执行命令 {}: {}", command, e)))?;
+    guard.disarm();
 
     if !output.status.success() {
         let error_message = String::from_utf8_lossy(&output.stderr);
@@ -293,6 +396,40 @@ mod tests {
     use std::sync::Arc;
     use tokio::sync::Mutex;
 
+    #[tokio::test]
+    async fn test_dropped_command_kills_process_group() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let pid_file = temp_dir.path().join("child.pid");
+        let script = format!("sleep 30 & echo $! > {}; wait", pid_file.display());
+
+        let result = tokio::time::timeout(
+            std::time::Duration::from_millis(500),
+            run_command_with_error_context("sh", &["-c", &script], "测试"),
+        )
+        .await;
+        assert!(result.is_err());
+
+        // 超时后后台的 sleep 也应被结束（进程消失或只剩僵尸进程）
+        let pid = std::fs::read_to_string(&pid_file).unwrap().trim().to_string();
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        let alive = std::fs::read_to_string(format!("/proc/{}/stat", pid))
+            .map(|stat| !stat.contains(") Z "))
+            .unwrap_or(false);
+        assert!(!alive, "后台进程 {} 仍在运行", pid);
+    }
+
+    #[test]
+    fn test_journal_args_start_from_global_tail() {
+        let matches = ["SYSLOG_IDENTIFIER=sshd"];
+
+        // 首次读取不带匹配条件，没有 sshd 记录时也能得到起始游标
+        assert_eq!(journal_args(&matches, None), vec!["-o", "json", "--no-pager", "-n", "1"]);
+        assert_eq!(
+            journal_args(&matches, Some("s=abc")),
+            vec!["SYSLOG_IDENTIFIER=sshd", "-o", "json", "--no-pager", "--after-cursor", "s=abc"]
+        );
+    }
+
     #[test]
     fn test_classify_command_error_permission_denied() {
         let error_message = "permission denied";
@@ -0,0 +1,408 @@
+//! /proc 指标解析
+//!
+//! 直接解析 /proc 下的文本文件获取负载、Swap、CPU iowait/steal、
+//! 压力阻塞信息 (PSI) 以及进程资源占用，解析函数均可用样例文件单独测试
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// 排行榜展示的进程数量
+pub const TOP_PROCESS_COUNT: usize = 5;
+
+/// 1/5/15 分钟平均负载
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LoadAverage {
+    pub one: f32,
+    pub five: f32,
+    pub fifteen: f32,
+}
+
+/// /proc/stat 中汇总 cpu 行的各项时间（单位: 时钟节拍）
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CpuTimes {
+    pub user: u64,
+    pub nice: u64,
+    pub system: u64,
+    pub idle: u64,
+    pub iowait: u64,
+    pub irq: u64,
+    pub softirq: u64,
+    pub steal: u64,
+}
+
+impl CpuTimes {
+    /// 总时间（guest 时间已包含在 user/nice 中，不重复计入）
+    pub fn total(&self) -> u64 {
+        self.user + self.nice + self.system + self.idle + self.iowait + self.irq + self.softirq + self.steal
+    }
+
+    /// 计算从 previous 到当前的 iowait 与 steal 百分比
+    pub fn iowait_steal_since(&self, previous: &CpuTimes) -> (f32, f32) {
+        let total = self.total().saturating_sub(previous.total());
+        if total == 0 {
+            return (0.0, 0.0);
+        }
+        let iowait = self.iowait.saturating_sub(previous.iowait);
+        let steal = self.steal.saturating_sub(previous.steal);
+        (
+            (iowait as f64 / total as f64 * 100.0) as f32,
+            (steal as f64 / total as f64 * 100.0) as f32,
+        )
+    }
+}
+
+/// 单类资源的压力阻塞信息（取 avg10，即最近 10 秒的平均值）
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PressureStall {
+    /// 至少有一个任务因该资源阻塞的时间占比
+    pub some_avg10: f32,
+    /// 所有非空闲任务同时阻塞的时间占比（CPU 在旧内核上没有该行）
+    pub full_avg10: Option<f32>,
+}
+
+/// CPU/内存/IO 三类资源的压力阻塞信息，内核未开启 PSI 时为 None
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PressureInfo {
+    pub cpu: Option<PressureStall>,
+    pub memory: Option<PressureStall>,
+    pub io: Option<PressureStall>,
+}
+
+/// 单个进程的资源占用
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProcessUsage {
+    pub pid: u32,
+    pub name: String,
+    /// 采样窗口内的 CPU 占用（单核为 100%）
+    pub cpu_percent: f32,
+    pub rss_bytes: u64,
+}
+
+/// /proc/[pid]/stat 中需要的字段
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessStat {
+    pub pid: u32,
+    pub name: String,
+    /// 用户态 + 内核态 CPU 时间（时钟节拍）
+    pub cpu_ticks: u64,
+    /// 常驻内存页数
+    pub rss_pages: u64,
+}
+
+/// 解析 /proc/loadavg
+pub fn parse_loadavg(content: &str) -> Option<LoadAverage> {
+    let mut fields = content.split_whitespace();
+    Some(LoadAverage {
+        one: fields.next()?.parse().ok()?,
+        five: fields.next()?.parse().ok()?,
+        fifteen: fields.next()?.parse().ok()?,
+    })
+}
+
+/// 解析 /proc/meminfo 中的 Swap 使用情况，返回 (已用字节, 总字节)
+pub fn parse_swap(content: &str) -> Option<(u64, u64)> {
+    parse_meminfo_usage(content, "SwapTotal", "SwapFree")
+}
+
+/// 解析 /proc/meminfo 中的内存使用情况（按 MemAvailable 计算），返回 (已用字节, 总字节)
+pub fn parse_memory(content: &str) -> Option<(u64, u64)> {
+    parse_meminfo_usage(content, "MemTotal", "MemAvailable")
+}
+
+fn parse_meminfo_usage(content: &str, total_key: &str, free_key: &str) -> Option<(u64, u64)> {
+    let mut total = None;
+    let mut free = None;
+
+    for line in content.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let kb = value.split_whitespace().next().and_then(|v| v.parse::<u64>().ok());
+        match key.trim() {
+            k if k == total_key => total = kb,
+            k if k == free_key => free = kb,
+            _ => {}
+        }
+    }
+
+    let total = total? * 1024;
+    let free = free? * 1024;
+    Some((total.saturating_sub(free), total))
+}
+
+/// 解析 /proc/stat 的汇总 cpu 行
+pub fn parse_cpu_times(content: &str) -> Option<CpuTimes> {
+    let line = content.lines().find(|line| line.starts_with("cpu "))?;
+    let values: Vec<u64> = line
+        .split_whitespace()
+        .skip(1)
+        .map(|v| v.parse().ok())
+        .collect::<Option<Vec<u64>>>()?;
+    // steal 自 2.6.11 起提供，更早的内核按 0 处理
+    let field = |index: usize| values.get(index).copied().unwrap_or(0);
+    if values.len() < 4 {
+        return None;
+    }
+
+    Some(CpuTimes {
+        user: field(0),
+        nice: field(1),
+        system: field(2),
+        idle: field(3),
+        iowait: field(4),
+        irq: field(5),
+        softirq: field(6),
+        steal: field(7),
+    })
+}
+
+/// 解析 /proc/pressure/{cpu,memory,io}
+pub fn parse_pressure(content: &str) -> Option<PressureStall> {
+    let mut some = None;
+    let mut full = None;
+
+    for line in content.lines() {
+        let mut fields = line.split_whitespace();
+        let kind = fields.next();
+        let avg10 = fields
+            .find_map(|field| field.strip_prefix("avg10="))
+            .and_then(|v| v.parse::<f32>().ok());
+        match kind {
+            Some("some") => some = avg10,
+            Some("full") => full = avg10,
+            _ => {}
+        }
+    }
+
+    Some(PressureStall {
+        some_avg10: some?,
+        full_avg10: full,
+    })
+}
+
+/// 解析 /proc/[pid]/stat
+///
+/// 进程名可能包含空格和括号，以最后一个 ')' 作为进程名的结束
+pub fn parse_process_stat(content: &str) -> Option<ProcessStat> {
+    let open = content.find('(')?;
+    let close = content.rfind(')')?;
+    let pid = content[..open].trim().parse().ok()?;
+    let name = content.get(open + 1..close)?.to_string();
+
+    // 从状态字段（第 3 列）开始计数
+    let fields: Vec<&str> = content[close + 1..].split_whitespace().collect();
+    let field = |column: usize| -> Option<u64> { fields.get(column - 3)?.parse().ok() };
+
+    Some(ProcessStat {
+        pid,
+        name,
+        cpu_ticks: field(14)? + field(15)?,
+        rss_pages: field(24)?,
+    })
+}
+
+/// 读取所有进程的 stat，进程在读取期间退出时直接跳过
+pub fn read_process_stats() -> Vec<ProcessStat> {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+
+    entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let pid = entry.file_name().to_str()?.parse::<u32>().ok()?;
+            let content = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+            parse_process_stat(&content)
+        })
+        .collect()
+}
+
+/// 根据两次进程采样计算 CPU 与内存占用
+pub fn compute_process_usage(
+    before: &[ProcessStat],
+    after: &[ProcessStat],
+    window: Duration,
+    clock_ticks: u64,
+    page_size: u64,
+) -> Vec<ProcessUsage> {
+    let previous: HashMap<u32, u64> = before.iter().map(|p| (p.pid, p.cpu_ticks)).collect();
+    let seconds = window.as_secs_f64();
+
+    after
+        .iter()
+        .map(|process| {
+            let cpu_percent = match previous.get(&process.pid) {
+                Some(prev_ticks) if seconds > 0.0 && clock_ticks > 0 => {
+                    let ticks = process.cpu_ticks.saturating_sub(*prev_ticks);
+                    (ticks as f64 / clock_ticks as f64 / seconds * 100.0) as f32
+                }
+                _ => 0.0,
+            };
+            ProcessUsage {
+                pid: process.pid,
+                name: process.name.clone(),
+                cpu_percent,
+                rss_bytes: process.rss_pages * page_size,
+            }
+        })
+        .collect()
+}
+
+/// 取 CPU 占用最高的前 N 个进程
+pub fn top_by_cpu(processes: &[ProcessUsage], count: usize) -> Vec<ProcessUsage> {
+    let mut sorted = processes.to_vec();
+    sorted.sort_by(|a, b| b.cpu_percent.total_cmp(&a.cpu_percent));
+    sorted.truncate(count);
+    sorted
+}
+
+/// 取常驻内存最高的前 N 个进程
+pub fn top_by_rss(processes: &[ProcessUsage], count: usize) -> Vec<ProcessUsage> {
+    let mut sorted = processes.to_vec();
+    sorted.sort_by_key(|p| std::cmp::Reverse(p.rss_bytes));
+    sorted.truncate(count);
+    sorted
+}
+
+/// 读取 /proc/loadavg
+pub fn read_loadavg() -> LoadAverage {
+    read_and_parse("/proc/loadavg", parse_loadavg).unwrap_or_default()
+}
+
+/// 读取 Swap 使用情况 (已用字节, 总字节)
+pub fn read_swap() -> (u64, u64) {
+    read_and_parse("/proc/meminfo", parse_swap).unwrap_or_default()
+}
+
+/// 读取内存使用情况 (已用字节, 总字节)
+pub fn read_memory() -> (u64, u64) {
+    read_and_parse("/proc/meminfo", parse_memory).unwrap_or_default()
+}
+
+/// 读取 /proc/stat 汇总 CPU 时间
+pub fn read_cpu_times() -> Option<CpuTimes> {
+    read_and_parse("/proc/stat", parse_cpu_times)
+}
+
+/// 读取 PSI 信息
+pub fn read_pressure() -> PressureInfo {
+    PressureInfo {
+        cpu: read_and_parse("/proc/pressure/cpu", parse_pressure),
+        memory: read_and_parse("/proc/pressure/memory", parse_pressure),
+        io: read_and_parse("/proc/pressure/io", parse_pressure),
+    }
+}
+
+/// 每秒时钟节拍数
+pub fn clock_ticks() -> u64 {
+    // SAFETY: sysconf 只读取系统配置
+    let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    if ticks > 0 { ticks as u64 } else { 100 }
+}
+
+/// 内存页大小
+pub fn page_size() -> u64 {
+    // SAFETY: sysconf 只读取系统配置
+    let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    if size > 0 { size as u64 } else { 4096 }
+}
+
+fn read_and_parse<T>(path: &str, parse: fn(&str) -> Option<T>) -> Option<T> {
+    std::fs::read_to_string(path).ok().as_deref().and_then(parse)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOADAVG: &str = include_str!("../../tests/fixtures/proc/loadavg");
+    const MEMINFO: &str = include_str!("../../tests/fixtures/proc/meminfo");
+    const STAT_BEFORE: &str = include_str!("../../tests/fixtures/proc/stat_before");
+    const STAT_AFTER: &str = include_str!("../../tests/fixtures/proc/stat_after");
+    const PRESSURE_CPU: &str = include_str!("../../tests/fixtures/proc/pressure_cpu");
+    const PRESSURE_IO: &str = include_str!("../../tests/fixtures/proc/pressure_io");
+    const PID_STAT: &str = include_str!("../../tests/fixtures/proc/pid_stat");
+
+    #[test]
+    fn test_parse_loadavg() {
+        let load = parse_loadavg(LOADAVG).unwrap();
+        assert_eq!(load, LoadAverage { one: 1.52, five: 0.98, fifteen: 0.45 });
+        assert!(parse_loadavg("").is_none());
+    }
+
+    #[test]
+    fn test_parse_swap() {
+        let (used, total) = parse_swap(MEMINFO).unwrap();
+        assert_eq!(total, 1048572 * 1024);
+        assert_eq!(used, (1048572 - 786428) * 1024);
+        assert!(parse_swap("MemTotal: 100 kB\n").is_none());
+
+        let (used, total) = parse_memory(MEMINFO).unwrap();
+        assert_eq!(total, 2014732 * 1024);
+        assert_eq!(used, (2014732 - 812456) * 1024);
+    }
+
+    #[test]
+    fn test_cpu_iowait_and_steal() {
+        let before = parse_cpu_times(STAT_BEFORE).unwrap();
+        let after = parse_cpu_times(STAT_AFTER).unwrap();
+        assert_eq!(before.steal, 700);
+        assert_eq!(after.total() - before.total(), 1000);
+
+        let (iowait, steal) = after.iowait_steal_since(&before);
+        assert_eq!(iowait, 10.0);
+        assert_eq!(steal, 10.0);
+
+        // 两次采样相同时不会除以 0
+        assert_eq!(after.iowait_steal_since(&after), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_parse_pressure() {
+        let cpu = parse_pressure(PRESSURE_CPU).unwrap();
+        assert_eq!(cpu.some_avg10, 12.5);
+        assert_eq!(cpu.full_avg10, Some(0.0));
+
+        let io = parse_pressure(PRESSURE_IO).unwrap();
+        assert_eq!(io.some_avg10, 3.25);
+        assert_eq!(io.full_avg10, Some(1.75));
+
+        let legacy_cpu = parse_pressure("some avg10=1.00 avg60=0.50 avg300=0.10 total=10\n").unwrap();
+        assert_eq!(legacy_cpu.full_avg10, None);
+        assert!(parse_pressure("").is_none());
+    }
+
+    #[test]
+    fn test_parse_process_stat_with_parentheses_in_name() {
+        let stat = parse_process_stat(PID_STAT).unwrap();
+        assert_eq!(stat.pid, 1234);
+        assert_eq!(stat.name, "my worker (v2)");
+        assert_eq!(stat.cpu_ticks, 1800);
+        assert_eq!(stat.rss_pages, 25600);
+    }
+
+    #[test]
+    fn test_compute_process_usage_and_top() {
+        let stat = |pid: u32, name: &str, cpu_ticks: u64, rss_pages: u64| ProcessStat {
+            pid,
+            name: name.to_string(),
+            cpu_ticks,
+            rss_pages,
+        };
+        let before = vec![stat(1, "a", 100, 10), stat(2, "b", 100, 500), stat(3, "c", 100, 20)];
+        let after = vec![stat(1, "a", 150, 10), stat(2, "b", 110, 500), stat(3, "c", 100, 20), stat(4, "d", 999, 1)];
+
+        let usage = compute_process_usage(&before, &after, Duration::from_secs(1), 100, 4096);
+        assert_eq!(usage[0].cpu_percent, 50.0);
+        assert_eq!(usage[1].rss_bytes, 500 * 4096);
+        // 新进程没有上一次采样
+        assert_eq!(usage[3].cpu_percent, 0.0);
+
+        let top_cpu = top_by_cpu(&usage, 2);
+        assert_eq!(top_cpu.iter().map(|p| p.pid).collect::<Vec<_>>(), vec![1, 2]);
+
+        let top_rss = top_by_rss(&usage, TOP_PROCESS_COUNT);
+        assert_eq!(top_rss.len(), 4);
+        assert_eq!(top_rss[0].name, "b");
+    }
+}
@@ -0,0 +1,375 @@
+//! Xray / Sing-box 配置文件读取
+//!
+//! 与 vps_secure.sh 扫描相同的配置目录，去掉 `//` 注释后按 JSON 解析
+
+use crate::system::sockets::Transport;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// 视为仅本机访问的监听地址
+const LOCAL_LISTEN_ADDRESSES: [&str; 3] = ["127.0.0.1", "localhost", "::1"];
+
+/// 只使用 UDP 的 Sing-box 入站类型
+const SINGBOX_UDP_INBOUNDS: [&str; 4] = ["hysteria", "hysteria2", "tuic", "naive-quic"];
+
+/// 代理核心类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProxyKind {
+    Xray,
+    SingBox,
+}
+
+impl ProxyKind {
+    pub const ALL: [ProxyKind; 2] = [ProxyKind::Xray, ProxyKind::SingBox];
+
+    /// 显示名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProxyKind::Xray => "Xray",
+            ProxyKind::SingBox => "Sing-box",
+        }
+    }
+
+    /// 配置文件所在目录
+    pub fn config_dirs(&self) -> &'static [&'static str] {
+        match self {
+            ProxyKind::Xray => &["/etc/xray/conf", "/etc/v2ray-agent/xray/conf", "/usr/local/etc/xray"],
+            ProxyKind::SingBox => &["/etc/sing-box/conf", "/etc/v2ray-agent/sing-box/conf/config", "/etc/sing-box"],
+        }
+    }
+
+    /// 入站端口字段名
+    pub fn port_key(&self) -> &'static str {
+        match self {
+            ProxyKind::Xray => "port",
+            ProxyKind::SingBox => "listen_port",
+        }
+    }
+
+    /// TLS 证书路径字段名
+    pub fn certificate_key(&self) -> &'static str {
+        match self {
+            ProxyKind::Xray => "certificateFile",
+            ProxyKind::SingBox => "certificate_path",
+        }
+    }
+}
+
+/// 去掉 JSON 中的 `//` 行注释，字符串内的 `//`（如 URL）保持不变
+pub fn strip_line_comments(content: &str) -> String {
+    let mut output = String::with_capacity(content.len());
+
+    for line in content.lines() {
+        let mut in_string = false;
+        let mut escaped = false;
+        let mut end = line.len();
+        let mut chars = line.char_indices().peekable();
+
+        while let Some((index, c)) = chars.next() {
+            if in_string {
+                match c {
+                    _ if escaped => escaped = false,
+                    '\\' => escaped = true,
+                    '"' => in_string = false,
+                    _ => {}
+                }
+            } else if c == '"' {
+                in_string = true;
+            } else if c == '/' && chars.peek().map(|(_, next)| *next) == Some('/') {
+                end = index;
+                break;
+            }
+        }
+
+        output.push_str(&line[..end]);
+        output.push('\n');
+    }
+
+    output
+}
+
+/// 对外开放的入站端口
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InboundPort {
+    pub kind: ProxyKind,
+    /// 入站标签，配置中没有 tag 时为 None
+    pub tag: Option<String>,
+    pub port: u16,
+    pub transport: Transport,
+}
+
+impl InboundPort {
+    /// 用于消息展示的描述，如 "Xray 入站 vless-in (443/tcp)"
+    pub fn describe(&self) -> String {
+        format!(
+            "{} 入站 {} ({}/{})",
+            self.kind.name(),
+            self.tag.as_deref().unwrap_or("未命名"),
+            self.port,
+            self.transport.as_str()
+        )
+    }
+}
+
+/// 解析配置文件内容（允许 `//` 注释）
+pub fn parse_config(content: &str) -> Option<Value> {
+    serde_json::from_str(&strip_line_comments(content)).ok()
+}
+
+/// 列出某类代理的全部配置文件
+pub fn list_config_files(kind: ProxyKind) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+
+    for dir in kind.config_dirs() {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        let mut dir_files: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        dir_files.sort();
+        files.extend(dir_files);
+    }
+
+    files
+}
+
+/// 读取并解析配置文件
+pub fn load_config(path: &Path) -> Option<Value> {
+    let content = std::fs::read_to_string(path).ok()?;
+    parse_config(&content)
+}
+
+/// 递归查找所有名为 key 的字符串字段
+pub fn find_string_fields(value: &Value, key: &str) -> Vec<String> {
+    let mut found = Vec::new();
+    collect_string_fields(value, key, &mut found);
+    found
+}
+
+fn collect_string_fields(value: &Value, key: &str, found: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            for (k, v) in map {
+                match v {
+                    Value::String(s) if k == key => found.push(s.clone()),
+                    _ => collect_string_fields(v, key, found),
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect_string_fields(item, key, found);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 提取对外开放的入站端口（对应 vps_secure.sh 的 extract_public_ports）
+///
+/// 跳过 listen 明确绑定到 127.0.0.1 / localhost 的入站
+pub fn extract_public_inbounds(kind: ProxyKind, config: &Value) -> Vec<InboundPort> {
+    let Some(inbounds) = config.get("inbounds").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+
+    inbounds
+        .iter()
+        .filter(|inbound| {
+            let listen = inbound.get("listen").and_then(Value::as_str);
+            !listen.is_some_and(|addr| LOCAL_LISTEN_ADDRESSES.contains(&addr))
+        })
+        .filter_map(|inbound| {
+            let port = match inbound.get(kind.port_key())? {
+                Value::Number(n) => u16::try_from(n.as_u64()?).ok()?,
+                // Xray 允许字符串形式的端口，端口范围等写法不做检查
+                Value::String(s) => s.trim().parse().ok()?,
+                _ => return None,
+            };
+            Some(InboundPort {
+                kind,
+                tag: inbound.get("tag").and_then(Value::as_str).map(str::to_string),
+                port,
+                transport: inbound_transport(kind, inbound),
+            })
+        })
+        .collect()
+}
+
+fn inbound_transport(kind: ProxyKind, inbound: &Value) -> Transport {
+    let inbound_type = inbound.get("type").and_then(Value::as_str).unwrap_or_default();
+    if kind == ProxyKind::SingBox && SINGBOX_UDP_INBOUNDS.contains(&inbound_type) {
+        Transport::Udp
+    } else {
+        Transport::Tcp
+    }
+}
+
+/// JSON 解析失败时的兜底：与脚本一致，文件中没有绑定 127.0.0.1 的 listen 时按正则匹配所有端口
+pub fn fallback_extract_ports(kind: ProxyKind, content: &str) -> Vec<InboundPort> {
+    let compact: String = content.chars().filter(|c| !c.is_whitespace()).collect();
+    if compact.contains("\"listen\":\"127.0.0.1\"") {
+        return Vec::new();
+    }
+
+    let needle = format!("\"{}\":", kind.port_key());
+    let mut ports: Vec<u16> = compact
+        .match_indices(&needle)
+        .filter_map(|(index, _)| {
+            let digits: String = compact[index + needle.len()..]
+                .chars()
+                .take_while(|c| c.is_ascii_digit())
+                .collect();
+            digits.parse().ok()
+        })
+        .collect();
+    ports.sort_unstable();
+    ports.dedup();
+
+    ports
+        .into_iter()
+        .map(|port| InboundPort {
+            kind,
+            tag: None,
+            port,
+            transport: Transport::Tcp,
+        })
+        .collect()
+}
+
+/// 扫描所有配置文件，返回对外开放的入站端口
+pub fn discover_public_inbounds() -> Vec<InboundPort> {
+    let mut inbounds = Vec::new();
+
+    for kind in ProxyKind::ALL {
+        for file in list_config_files(kind) {
+            let Ok(content) = std::fs::read_to_string(&file) else {
+                continue;
+            };
+            match parse_config(&content) {
+                Some(config) => inbounds.extend(extract_public_inbounds(kind, &config)),
+                None => inbounds.extend(fallback_extract_ports(kind, &content)),
+            }
+        }
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    inbounds.retain(|inbound| seen.insert((inbound.kind, inbound.port, inbound.transport)));
+    inbounds
+}
+
+/// 从 Xray / Sing-box 配置中发现引用的证书路径
+pub fn discover_certificate_paths() -> Vec<String> {
+    let mut paths = Vec::new();
+
+    for kind in ProxyKind::ALL {
+        for file in list_config_files(kind) {
+            if let Some(config) = load_config(&file) {
+                paths.extend(find_string_fields(&config, kind.certificate_key()));
+            }
+        }
+    }
+
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_line_comments_keeps_urls() {
+        let content = r#"{
+  // 入站配置
+  "url": "https://example.com/path", // 行尾注释
+  "escaped": "quote \" // still string"
+}"#;
+        let stripped = strip_line_comments(content);
+
+        assert!(!stripped.contains("入站配置"));
+        assert!(!stripped.contains("行尾注释"));
+        assert!(stripped.contains("https://example.com/path"));
+        assert!(stripped.contains(r#"quote \" // still string"#));
+
+        let value = parse_config(content).unwrap();
+        assert_eq!(value["url"], "https://example.com/path");
+    }
+
+    #[test]
+    fn test_extract_public_inbounds_skips_localhost() {
+        let xray = parse_config(r#"{
+  // API 入站只监听本机
+  "inbounds": [
+    { "tag": "api", "listen": "127.0.0.1", "port": 10085, "protocol": "dokodemo-door" },
+    { "tag": "vless-in", "port": 443, "protocol": "vless" },
+    { "tag": "vmess-ws", "listen": "0.0.0.0", "port": "8443", "protocol": "vmess" },
+    { "tag": "local", "listen": "localhost", "port": 9000 },
+    { "tag": "range", "port": "1000-2000" }
+  ]
+}"#).unwrap();
+
+        let inbounds = extract_public_inbounds(ProxyKind::Xray, &xray);
+        let ports: Vec<u16> = inbounds.iter().map(|i| i.port).collect();
+        assert_eq!(ports, vec![443, 8443]);
+        assert_eq!(inbounds[0].describe(), "Xray 入站 vless-in (443/tcp)");
+    }
+
+    #[test]
+    fn test_extract_singbox_inbounds_with_udp() {
+        let singbox = parse_config(r#"{
+  "inbounds": [
+    { "type": "vless", "tag": "reality", "listen": "::", "listen_port": 443 },
+    { "type": "hysteria2", "tag": "hy2", "listen_port": 8443 },
+    { "type": "mixed", "listen": "::1", "listen_port": 2080 }
+  ]
+}"#).unwrap();
+
+        let inbounds = extract_public_inbounds(ProxyKind::SingBox, &singbox);
+        assert_eq!(inbounds.len(), 2);
+        assert_eq!(inbounds[1].transport, Transport::Udp);
+        assert_eq!(inbounds[1].describe(), "Sing-box 入站 hy2 (8443/udp)");
+    }
+
+    #[test]
+    fn test_fallback_extract_ports() {
+        let broken = r#"{ "inbounds": [ { "port": 443, }, { "port" : 8080 } "#;
+        let ports: Vec<u16> = fallback_extract_ports(ProxyKind::Xray, broken).iter().map(|i| i.port).collect();
+        assert_eq!(ports, vec![443, 8080]);
+
+        let local = r#"{ "inbounds": [ { "listen": "127.0.0.1", "port": 443, } "#;
+        assert!(fallback_extract_ports(ProxyKind::Xray, local).is_empty());
+    }
+
+    #[test]
+    fn test_find_certificate_fields() {
+        let xray = parse_config(r#"{
+  "inbounds": [{
+    "port": 443,
+    "streamSettings": {
+      "tlsSettings": {
+        "certificates": [
+          { "certificateFile": "/etc/ssl/a.crt", "keyFile": "/etc/ssl/a.key" },
+          { "certificateFile": "/etc/ssl/b.crt" }
+        ]
+      }
+    }
+  }]
+}"#).unwrap();
+        assert_eq!(
+            find_string_fields(&xray, ProxyKind::Xray.certificate_key()),
+            vec!["/etc/ssl/a.crt", "/etc/ssl/b.crt"]
+        );
+
+        let singbox = parse_config(r#"{
+  "inbounds": [{ "type": "vless", "listen_port": 443, "tls": { "enabled": true, "certificate_path": "/root/cert/fullchain.pem" } }]
+}"#).unwrap();
+        assert_eq!(
+            find_string_fields(&singbox, ProxyKind::SingBox.certificate_key()),
+            vec!["/root/cert/fullchain.pem"]
+        );
+    }
+}
@@ -0,0 +1,230 @@
+//! 重启需求检测
+//!
+//! 综合以下信号判断系统是否需要重启：
+//! - Debian/Ubuntu 的 /var/run/reboot-required 与 reboot-required.pkgs
+//! - 正在运行的内核与已安装的最新内核是否一致
+//! - RHEL 系的 `needs-restarting -r`
+
+use std::cmp::Ordering;
+use std::path::Path;
+use tokio::process::Command;
+
+const REBOOT_REQUIRED_FLAG: &str = "/var/run/reboot-required";
+const REBOOT_REQUIRED_PKGS: &str = "/var/run/reboot-required.pkgs";
+const RUNNING_KERNEL_PATH: &str = "/proc/sys/kernel/osrelease";
+const KERNEL_IMAGE_DIR: &str = "/boot";
+const KERNEL_IMAGE_PREFIX: &str = "vmlinuz-";
+const KERNEL_MODULES_DIR: &str = "/lib/modules";
+
+/// 重启检测结果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RebootStatus {
+    pub required: bool,
+    /// 需要重启的原因，不需要重启时为空
+    pub reasons: Vec<String>,
+}
+
+impl RebootStatus {
+    /// 用于状态消息的一行描述
+    pub fn describe(&self) -> String {
+        if self.required {
+            format!("🔁 需要重启: {}", self.reasons.join("；"))
+        } else {
+            "🔁 无需重启".to_string()
+        }
+    }
+}
+
+/// 检测时收集到的原始信号
+#[derive(Debug, Clone, Default)]
+pub struct RebootSignals {
+    pub flag_exists: bool,
+    pub required_packages: Vec<String>,
+    pub running_kernel: Option<String>,
+    pub installed_kernels: Vec<String>,
+    /// `needs-restarting -r` 的结论，命令不可用时为 None
+    pub needs_restarting: Option<bool>,
+}
+
+/// 根据收集到的信号得出结论
+pub fn evaluate(signals: &RebootSignals) -> RebootStatus {
+    let mut reasons = Vec::new();
+
+    if signals.flag_exists {
+        if signals.required_packages.is_empty() {
+            reasons.push("系统标记需要重启".to_string());
+        } else {
+            reasons.push(format!("软件包更新需要重启 ({})", signals.required_packages.join(", ")));
+        }
+    }
+
+    if let (Some(running), Some(newest)) = (&signals.running_kernel, newest_kernel(&signals.installed_kernels)) {
+        if compare_versions(newest, running) == Ordering::Greater {
+            reasons.push(format!("运行内核 {}，已安装更新的内核 {}", running, newest));
+        }
+    }
+
+    if signals.needs_restarting == Some(true) {
+        reasons.push("needs-restarting 报告需要重启".to_string());
+    }
+
+    RebootStatus {
+        required: !reasons.is_empty(),
+        reasons,
+    }
+}
+
+/// 解析 reboot-required.pkgs（每行一个包名，可能重复）
+pub fn parse_required_packages(content: &str) -> Vec<String> {
+    let mut packages: Vec<String> = Vec::new();
+    for line in content.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if !packages.iter().any(|p| p == line) {
+            packages.push(line.to_string());
+        }
+    }
+    packages
+}
+
+/// 从 /boot 文件名中提取内核版本
+pub fn kernel_versions_from_images<'a>(names: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    names
+        .into_iter()
+        .filter_map(|name| name.strip_prefix(KERNEL_IMAGE_PREFIX))
+        // 跳过救援镜像
+        .filter(|version| !version.contains("rescue"))
+        .map(str::to_string)
+        .collect()
+}
+
+/// 已安装内核中版本最高的一个
+pub fn newest_kernel(installed: &[String]) -> Option<&String> {
+    installed.iter().max_by(|a, b| compare_versions(a, b))
+}
+
+/// 按"自然顺序"比较版本号：数字段按数值比较，其余按字符比较
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let (a_parts, b_parts) = (version_parts(a), version_parts(b));
+    for (x, y) in a_parts.iter().zip(b_parts.iter()) {
+        let ordering = match (x.parse::<u64>(), y.parse::<u64>()) {
+            (Ok(x), Ok(y)) => x.cmp(&y),
+            _ => x.cmp(y),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a_parts.len().cmp(&b_parts.len())
+}
+
+fn version_parts(version: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut chars = version.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        if let Some(&(next_index, next)) = chars.peek() {
+            if c.is_ascii_digit() != next.is_ascii_digit() {
+                parts.push(&version[start..next_index]);
+                start = next_index;
+            }
+        } else {
+            parts.push(&version[start..index + c.len_utf8()]);
+        }
+    }
+    parts
+}
+
+fn read_installed_kernels() -> Vec<String> {
+    let list_dir = |dir: &str| -> Vec<String> {
+        std::fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter_map(|entry| entry.file_name().into_string().ok())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let images = list_dir(KERNEL_IMAGE_DIR);
+    let kernels = kernel_versions_from_images(images.iter().map(String::as_str));
+    if kernels.is_empty() {
+        list_dir(KERNEL_MODULES_DIR)
+    } else {
+        kernels
+    }
+}
+
+/// 运行 `needs-restarting -r`：退出码 1 表示需要重启，0 表示不需要
+async fn run_needs_restarting() -> Option<bool> {
+    let status = Command::new("needs-restarting").arg("-r").output().await.ok()?.status;
+    match status.code() {
+        Some(0) => Some(false),
+        Some(1) => Some(true),
+        _ => None,
+    }
+}
+
+/// 检测系统当前是否需要重启
+pub async fn check_reboot_required() -> RebootStatus {
+    let signals = RebootSignals {
+        flag_exists: Path::new(REBOOT_REQUIRED_FLAG).exists(),
+        required_packages: std::fs::read_to_string(REBOOT_REQUIRED_PKGS)
+            .map(|content| parse_required_packages(&content))
+            .unwrap_or_default(),
+        running_kernel: std::fs::read_to_string(RUNNING_KERNEL_PATH)
+            .ok()
+            .map(|release| release.trim().to_string()),
+        installed_kernels: read_installed_kernels(),
+        needs_restarting: run_needs_restarting().await,
+    };
+    evaluate(&signals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_kernel_versions() {
+        assert_eq!(compare_versions("6.1.0-18-amd64", "6.1.0-9-amd64"), Ordering::Greater);
+        assert_eq!(compare_versions("5.14.0-362.8.1.el9_3.x86_64", "5.14.0-362.24.1.el9_3.x86_64"), Ordering::Less);
+        assert_eq!(compare_versions("6.8.0-45-generic", "6.8.0-45-generic"), Ordering::Equal);
+
+        let installed = vec!["6.1.0-9-amd64".to_string(), "6.1.0-18-amd64".to_string(), "6.1.0-13-amd64".to_string()];
+        assert_eq!(newest_kernel(&installed).map(String::as_str), Some("6.1.0-18-amd64"));
+    }
+
+    #[test]
+    fn test_kernel_versions_from_images() {
+        let names = ["vmlinuz-6.1.0-18-amd64", "initrd.img-6.1.0-18-amd64", "vmlinuz-0-rescue-abc", "config-6.1.0-18-amd64"];
+        assert_eq!(kernel_versions_from_images(names), vec!["6.1.0-18-amd64"]);
+    }
+
+    #[test]
+    fn test_parse_required_packages() {
+        let packages = parse_required_packages("linux-image-6.1.0-18-amd64\nlibc6\n\nlibc6\n");
+        assert_eq!(packages, vec!["linux-image-6.1.0-18-amd64", "libc6"]);
+    }
+
+    #[test]
+    fn test_evaluate_reboot_signals() {
+        let mut signals = RebootSignals {
+            running_kernel: Some("6.1.0-18-amd64".to_string()),
+            installed_kernels: vec!["6.1.0-18-amd64".to_string()],
+            ..Default::default()
+        };
+        let status = evaluate(&signals);
+        assert!(!status.required);
+        assert_eq!(status.describe(), "🔁 无需重启");
+
+        signals.installed_kernels.push("6.1.0-21-amd64".to_string());
+        signals.flag_exists = true;
+        signals.required_packages = vec!["libc6".to_string()];
+        signals.needs_restarting = Some(true);
+        let status = evaluate(&signals);
+        assert!(status.required);
+        assert_eq!(status.reasons.len(), 3);
+        assert!(status.describe().contains("已安装更新的内核 6.1.0-21-amd64"));
+        assert!(status.describe().contains("libc6"));
+    }
+}
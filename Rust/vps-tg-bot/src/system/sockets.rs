@@ -0,0 +1,135 @@
+//! 监听套接字查询
+//!
+//! 解析 /proc/net/{tcp,tcp6,udp,udp6}，得到当前正在监听的端口
+
+use std::collections::HashSet;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// TCP LISTEN 状态
+const TCP_LISTEN: &str = "0A";
+/// UDP 未连接（即绑定等待数据）状态
+const UDP_UNCONNECTED: &str = "07";
+
+/// 传输层协议
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Transport {
+    Tcp,
+    Udp,
+}
+
+impl Transport {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Transport::Tcp => "tcp",
+            Transport::Udp => "udp",
+        }
+    }
+}
+
+/// 一个处于监听状态的本地地址
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListeningSocket {
+    pub address: IpAddr,
+    pub port: u16,
+}
+
+/// 解析 /proc/net/{tcp,tcp6,udp,udp6} 内容，只保留指定状态的本地地址
+pub fn parse_proc_net(content: &str, state: &str) -> Vec<ListeningSocket> {
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            // sl local_address rem_address st ...
+            if fields.len() < 4 || fields[3] != state {
+                return None;
+            }
+            let (address, port) = fields[1].split_once(':')?;
+            Some(ListeningSocket {
+                address: parse_hex_address(address)?,
+                port: u16::from_str_radix(port, 16).ok()?,
+            })
+        })
+        .collect()
+}
+
+/// 解析内核以主机字节序（按 32 位分组）输出的十六进制地址
+fn parse_hex_address(hex: &str) -> Option<IpAddr> {
+    let words: Vec<u32> = (0..hex.len() / 8)
+        .map(|i| u32::from_str_radix(hex.get(i * 8..i * 8 + 8)?, 16).ok())
+        .collect::<Option<Vec<u32>>>()?;
+
+    match words.as_slice() {
+        [v4] => Some(IpAddr::V4(Ipv4Addr::from(v4.swap_bytes()))),
+        [a, b, c, d] => {
+            let mut bytes = [0u8; 16];
+            for (i, word) in [a, b, c, d].iter().enumerate() {
+                bytes[i * 4..i * 4 + 4].copy_from_slice(&word.swap_bytes().to_be_bytes());
+            }
+            Some(IpAddr::V6(Ipv6Addr::from(bytes)))
+        }
+        _ => None,
+    }
+}
+
+/// 从监听套接字中取出对外可访问（非回环地址）的端口
+pub fn public_ports(sockets: &[ListeningSocket]) -> HashSet<u16> {
+    sockets
+        .iter()
+        .filter(|socket| !socket.address.is_loopback())
+        .map(|socket| socket.port)
+        .collect()
+}
+
+/// 读取当前对外监听的端口
+pub fn read_public_listening_ports(transport: Transport) -> HashSet<u16> {
+    let (files, state) = match transport {
+        Transport::Tcp => (["/proc/net/tcp", "/proc/net/tcp6"], TCP_LISTEN),
+        Transport::Udp => (["/proc/net/udp", "/proc/net/udp6"], UDP_UNCONNECTED),
+    };
+
+    let sockets: Vec<ListeningSocket> = files
+        .iter()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .flat_map(|content| parse_proc_net(&content, state))
+        .collect();
+
+    public_ports(&sockets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NET_TCP: &str = include_str!("../../tests/fixtures/proc/net_tcp");
+    const NET_TCP6: &str = include_str!("../../tests/fixtures/proc/net_tcp6");
+    const NET_UDP: &str = include_str!("../../tests/fixtures/proc/net_udp");
+
+    #[test]
+    fn test_parse_tcp_listeners() {
+        let sockets = parse_proc_net(NET_TCP, TCP_LISTEN);
+
+        assert_eq!(sockets.len(), 3);
+        assert_eq!(sockets[0], ListeningSocket { address: "0.0.0.0".parse().unwrap(), port: 22 });
+        assert_eq!(sockets[1], ListeningSocket { address: "127.0.0.1".parse().unwrap(), port: 10001 });
+
+        // 回环地址上的监听不算对外开放，已建立的连接不算监听
+        let ports = public_ports(&sockets);
+        assert_eq!(ports, HashSet::from([22, 9000]));
+    }
+
+    #[test]
+    fn test_parse_tcp6_listeners() {
+        let sockets = parse_proc_net(NET_TCP6, TCP_LISTEN);
+
+        assert_eq!(sockets[0].address, "::".parse::<IpAddr>().unwrap());
+        assert_eq!(sockets[1].address, "::1".parse::<IpAddr>().unwrap());
+        assert_eq!(public_ports(&sockets), HashSet::from([443]));
+    }
+
+    #[test]
+    fn test_parse_udp_sockets() {
+        let sockets = parse_proc_net(NET_UDP, UDP_UNCONNECTED);
+        assert_eq!(public_ports(&sockets), HashSet::from([8888]));
+    }
+}
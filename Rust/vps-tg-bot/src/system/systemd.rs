@@ -0,0 +1,98 @@
+//! systemd 服务状态查询
+//!
+//! 通过 `systemctl show` 读取服务的 LoadState/ActiveState/SubState/NRestarts
+
+use crate::system::errors::SystemError;
+use tokio::process::Command;
+
+/// systemd 服务状态
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UnitStatus {
+    pub load_state: String,
+    pub active_state: String,
+    pub sub_state: String,
+    /// systemd 自身 (Restart=) 重启该服务的次数
+    pub restarts: u64,
+}
+
+impl UnitStatus {
+    /// 服务单元文件是否存在
+    pub fn exists(&self) -> bool {
+        self.load_state != "not-found"
+    }
+
+    /// 是否处于异常状态（failed 或 inactive）
+    pub fn is_down(&self) -> bool {
+        matches!(self.active_state.as_str(), "failed" | "inactive")
+    }
+
+    /// 用于消息展示的状态描述，如 "failed (exit-code)"
+    pub fn describe(&self) -> String {
+        format!("{} ({})", self.active_state, self.sub_state)
+    }
+}
+
+/// 解析 `systemctl show --property=...` 的 KEY=VALUE 输出
+pub fn parse_unit_show(output: &str) -> UnitStatus {
+    let mut status = UnitStatus::default();
+
+    for line in output.lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim().to_string();
+        match key.trim() {
+            "LoadState" => status.load_state = value,
+            "ActiveState" => status.active_state = value,
+            "SubState" => status.sub_state = value,
+            "NRestarts" => status.restarts = value.parse().unwrap_or(0),
+            _ => {}
+        }
+    }
+
+    status
+}
+
+/// 查询服务当前状态
+pub async fn show_unit(unit: &str) -> Result<UnitStatus, SystemError> {
+    let output = Command::new("systemctl")
+        .args(["show", unit, "--property=LoadState,ActiveState,SubState,NRestarts"])
+        .output()
+        .await
+        .map_err(|e| SystemError::ServiceError(format!("无法查询服务 {} 状态: {}", unit, e)))?;
+
+    if !output.status.success() {
+        return Err(SystemError::ServiceError(format!(
+            "查询服务 {} 状态失败: {}",
+            unit,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(parse_unit_show(&String::from_utf8_lossy(&output.stdout)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_unit_show_failed() {
+        let status = parse_unit_show("LoadState=loaded\nActiveState=failed\nSubState=failed\nNRestarts=3\n");
+
+        assert!(status.exists());
+        assert!(status.is_down());
+        assert_eq!(status.restarts, 3);
+        assert_eq!(status.describe(), "failed (failed)");
+    }
+
+    #[test]
+    fn test_parse_unit_show_running_and_missing() {
+        let running = parse_unit_show("ActiveState=active\nSubState=running\nLoadState=loaded\nNRestarts=0");
+        assert!(!running.is_down());
+
+        let missing = parse_unit_show("LoadState=not-found\nActiveState=inactive\nSubState=dead\nNRestarts=[not set]");
+        assert!(!missing.exists());
+        assert_eq!(missing.restarts, 0);
+    }
+}